@@ -1,5 +1,6 @@
 // allows unused code during development
 #![allow(dead_code)]
+use std::fmt;
 #[derive(Debug, Clone, PartialEq, Eq)]
 
 
@@ -8,47 +9,281 @@ pub enum Type {
     Int,
     Float,
     Char,
+    Bool, // `_Bool`: 0 or 1
     Void,
+    UnsignedInt,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+    Double,
+    LongDouble,
+    Pointer(Box<Type>), // only from string literals and library prototypes
 }
 
 
-// expressions
+// type classification helpers
+impl Type {
+    // char, _Bool and all the int/long variants
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::Int | Type::Char | Type::Bool | Type::UnsignedInt | Type::Long | Type::UnsignedLong | Type::LongLong | Type::UnsignedLongLong)
+    }
+
+    // float, double and long double
+    pub fn is_floating(&self) -> bool {
+        matches!(self, Type::Float | Type::Double | Type::LongDouble)
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, Type::Bool | Type::UnsignedInt | Type::UnsignedLong | Type::UnsignedLongLong)
+    }
+
+    // width of the value representation on the LP64 x86-64 targets; char is signed
+    pub fn bits(&self) -> u32 {
+        match self {
+            Type::Void => 0,
+            Type::Char | Type::Bool => 8,
+            Type::Int | Type::UnsignedInt | Type::Float => 32,
+            Type::Long | Type::UnsignedLong | Type::LongLong | Type::UnsignedLongLong | Type::Double | Type::Pointer(_) => 64,
+            Type::LongDouble => 80,
+        }
+    }
+
+    // smallest and largest value of an integer type
+    pub fn int_range(&self) -> Option<(i128, i128)> {
+        if !self.is_integer() {
+            return None;
+        }
+        if *self == Type::Bool {
+            return Some((0, 1));
+        }
+        let bits = self.bits();
+        if self.is_unsigned() {
+            Some((0, (1i128 << bits) - 1))
+        } else {
+            Some((-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1))
+        }
+    }
+
+    // the integer promotions: char and _Bool operands are computed as int
+    pub fn promoted(&self) -> Type {
+        match self {
+            Type::Char | Type::Bool => Type::Int,
+            other => other.clone(),
+        }
+    }
+
+    // the usual arithmetic conversions: the wider floating type if either
+    // operand is floating, else the promoted integer type of higher rank,
+    // unsigned when the signed type cannot hold every unsigned value.
+    // None unless both operands are arithmetic
+    pub fn common(left: &Type, right: &Type) -> Option<Type> {
+        if !(left.is_integer() || left.is_floating()) || !(right.is_integer() || right.is_floating()) {
+            return None;
+        }
+        if left.is_floating() || right.is_floating() {
+            let rank = |t: &Type| if t.is_floating() { t.bits() } else { 0 };
+            return Some(if rank(left) >= rank(right) { left.clone() } else { right.clone() });
+        }
+        let (a, b) = (left.promoted(), right.promoted());
+        let rank = |t: &Type| match t {
+            Type::Int | Type::UnsignedInt => 1,
+            Type::Long | Type::UnsignedLong => 2,
+            _ => 3,
+        };
+        let (high, low) = if rank(&a) >= rank(&b) { (a, b) } else { (b, a) };
+        if high.is_unsigned() || !low.is_unsigned() || high.bits() > low.bits() {
+            return Some(high);
+        }
+        if rank(&high) == rank(&low) {
+            return Some(low);
+        }
+        Some(match high {
+            Type::Long => Type::UnsignedLong,
+            _ => Type::UnsignedLongLong,
+        })
+    }
+
+    // reduce a value modulo the width of an integer type, two's complement for
+    // signed types; unsigned 64-bit values are kept in an i64 by their bits.
+    // _Bool is not reduced: every nonzero value becomes 1
+    pub fn wrap(&self, value: i128) -> i64 {
+        if *self == Type::Bool {
+            return (value != 0) as i64;
+        }
+        let modulus = 1i128 << self.bits();
+        let low = value.rem_euclid(modulus);
+        if !self.is_unsigned() && low >= modulus / 2 {
+            (low - modulus) as i64
+        } else {
+            low as i64
+        }
+    }
+
+    // the mathematical value of an integer converted to this type
+    pub fn exact(&self, value: i64) -> i128 {
+        let value = self.wrap(value as i128);
+        if self.is_unsigned() && self.bits() == 64 {
+            value as u64 as i128
+        } else {
+            value as i128
+        }
+    }
+}
+
+
+// C spelling of a type
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Char => "char",
+            Type::Bool => "_Bool",
+            Type::Void => "void",
+            Type::UnsignedInt => "unsigned int",
+            Type::Long => "long",
+            Type::UnsignedLong => "unsigned long",
+            Type::LongLong => "long long",
+            Type::UnsignedLongLong => "unsigned long long",
+            Type::Double => "double",
+            Type::LongDouble => "long double",
+            Type::Pointer(to) if matches!(**to, Type::Pointer(_)) => return write!(f, "{}*", to),
+            Type::Pointer(to) => return write!(f, "{} *", to),
+        };
+        f.write_str(name)
+    }
+}
+
+
+// identity of a node, unique within one program; analysis results are kept in
+// side tables keyed by it instead of on the nodes themselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub u32);
+
+
+impl NodeId {
+    // placeholder for synthesized nodes until `assign_ids` numbers them
+    pub const DUMMY: NodeId = NodeId(u32::MAX);
+}
+
+
+// an expression node; equality compares structure only, never ids
 #[derive(Debug, Clone)]
-pub enum Expr {
-    Number(i64),
-    FloatNumber(f64),
+pub struct Expr {
+    pub id: NodeId,
+    pub kind: ExprKind,
+}
+
+
+impl Expr {
+    pub fn new(kind: ExprKind) -> Self {
+        Expr { id: NodeId::DUMMY, kind }
+    }
+}
+
+
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+
+// expressions
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Number(i64, Type),      // value and literal type chosen by the lexer
+    FloatNumber(f64, Type),
     CharLiteral(char),
     StringLiteral(String),
     Ident(String),
     Unary { op: UnaryOp, expr: Box<Expr> },
     Binary { op: BinaryOp, left: Box<Expr>, right: Box<Expr> },
-    Assign { name: String, value: Box<Expr> },
+    Assign { target: Box<Expr>, value: Box<Expr> },
     Call { name: String, args: Vec<Expr> },
 }
 
 
-// unary operators
-#[derive(Debug, Clone)]
-pub enum UnaryOp { Neg, Not }
+// unary operators; `&` only applies to variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp { Neg, Not, AddrOf }
 
 
 // binary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp { Add, Sub, Mul, Div, Lt, Gt, Le, Ge, Eq, Ne }
+
+
+impl BinaryOp {
+    // relational and equality operators, which give an int 0 or 1
+    pub fn is_comparison(self) -> bool {
+        matches!(self, BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Ge | BinaryOp::Eq | BinaryOp::Ne)
+    }
+}
+
+
+// a statement node; equality ignores the id like for expressions
 #[derive(Debug, Clone)]
-pub enum BinaryOp { Add, Sub, Mul, Div }
+pub struct Stmt {
+    pub id: NodeId,
+    pub kind: StmtKind,
+}
+
+
+impl Stmt {
+    pub fn new(kind: StmtKind) -> Self {
+        Stmt { id: NodeId::DUMMY, kind }
+    }
+
+    // the statements directly inside this one, in source order
+    pub fn substatements(&self) -> Vec<&Stmt> {
+        match &self.kind {
+            StmtKind::Block(block) => block.stmts.iter().collect(),
+            StmtKind::If { then, otherwise, .. } => std::iter::once(&**then).chain(otherwise.as_deref()).collect(),
+            StmtKind::While { body, .. } => vec![body],
+            _ => Vec::new(),
+        }
+    }
+}
+
+
+// every statement of a block, nested ones included, each before the ones inside it
+pub fn statements(block: &Block) -> Vec<&Stmt> {
+    let mut found = Vec::new();
+    let mut pending: Vec<&Stmt> = block.stmts.iter().rev().collect();
+    while let Some(stmt) = pending.pop() {
+        found.push(stmt);
+        pending.extend(stmt.substatements().into_iter().rev());
+    }
+    found
+}
+
+
+impl PartialEq for Stmt {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
 
 
 // statements
-#[derive(Debug, Clone)]
-pub enum Stmt {
-    VarDecl { ty: Type, name: String, value: Expr },
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    VarDecl { ty: Type, name: String, value: Option<Expr>, is_const: bool },  // `int x;` has no initializer
     ExprStmt(Expr),
-    Return(Expr),
-    // minimal subset for now; add If/While later
+    Return(Option<Expr>),   // `return;` has no value
+    StaticAssert { cond: Expr, message: String },  // checked during analysis, never run
+    Empty,                  // a lone `;`
+    Block(Block),           // `{ ... }`, a scope of its own
+    If { cond: Expr, then: Box<Stmt>, otherwise: Option<Box<Stmt>> },
+    While { cond: Expr, body: Box<Stmt> },
 }
 
 
 // a block of statements -> this is for inside functions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Block {
     pub stmts: Vec<Stmt>,
 }
@@ -57,18 +292,307 @@ pub struct Block {
 // a function with name, return type, parameters, and body
 #[derive(Debug, Clone)]
 pub struct Function {
+    pub id: NodeId,
     pub name: String,
     pub return_type: Type,
     pub params: Vec<(Type, String)>,  // param type and name
+    pub prototyped: bool,             // false for `()`, which leaves the parameters unspecified
     pub body: Block,
 }
 
 
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.return_type == other.return_type && self.params == other.params && self.prototyped == other.prototyped && self.body == other.body
+    }
+}
 
-// the whole program with multiple functions
-#[derive(Debug, Clone)]
+
+
+// the whole program with multiple functions, and the `_Static_assert`s at file
+// scope (StaticAssert statements), which are visited before the functions
+#[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub functions: Vec<Function>,
+    pub static_asserts: Vec<Stmt>,
 }
 
 
+// give every node of a (possibly synthesized) program a fresh id, in the same
+// post-order the parser uses (when no file-scope assertion follows a function),
+// so side tables can be built for it
+pub fn assign_ids(program: &mut Program) {
+    struct Numbering(u32);
+
+    impl Numbering {
+        fn next(&mut self) -> NodeId {
+            self.0 += 1;
+            NodeId(self.0 - 1)
+        }
+    }
+
+    impl VisitorMut for Numbering {
+        fn visit_function_mut(&mut self, func: &mut Function) {
+            walk_function_mut(self, func);
+            func.id = self.next();
+        }
+
+        fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+            walk_stmt_mut(self, stmt);
+            stmt.id = self.next();
+        }
+
+        fn visit_expr_mut(&mut self, expr: &mut Expr) {
+            walk_expr_mut(self, expr);
+            expr.id = self.next();
+        }
+    }
+
+    Numbering(0).visit_program_mut(program);
+}
+
+
+
+
+// read-only traversal: override the visit_* methods of interest and call the
+// matching walk_* function from them to keep descending into children
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_function(&mut self, func: &Function) {
+        walk_function(self, func);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+}
+
+
+pub fn walk_program<V: Visitor + ?Sized>(v: &mut V, program: &Program) {
+    for stmt in &program.static_asserts {
+        v.visit_stmt(stmt);
+    }
+    for func in &program.functions {
+        v.visit_function(func);
+    }
+}
+
+pub fn walk_function<V: Visitor + ?Sized>(v: &mut V, func: &Function) {
+    v.visit_block(&func.body);
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(v: &mut V, block: &Block) {
+    for stmt in &block.stmts {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(v: &mut V, stmt: &Stmt) {
+    match &stmt.kind {
+        StmtKind::VarDecl { value: Some(expr), .. } | StmtKind::ExprStmt(expr) | StmtKind::Return(Some(expr)) | StmtKind::StaticAssert { cond: expr, .. } => v.visit_expr(expr),
+        StmtKind::VarDecl { value: None, .. } | StmtKind::Return(None) | StmtKind::Empty => {}
+        StmtKind::Block(block) => v.visit_block(block),
+        StmtKind::If { cond, then, otherwise } => {
+            v.visit_expr(cond);
+            v.visit_stmt(then);
+            if let Some(otherwise) = otherwise {
+                v.visit_stmt(otherwise);
+            }
+        }
+        StmtKind::While { cond, body } => {
+            v.visit_expr(cond);
+            v.visit_stmt(body);
+        }
+    }
+}
+
+// children are visited left to right, in evaluation order
+pub fn walk_expr<V: Visitor + ?Sized>(v: &mut V, expr: &Expr) {
+    match &expr.kind {
+        ExprKind::Number(..) | ExprKind::FloatNumber(..) | ExprKind::CharLiteral(_) | ExprKind::StringLiteral(_) | ExprKind::Ident(_) => {}
+        ExprKind::Unary { expr, .. } => v.visit_expr(expr),
+        ExprKind::Binary { left, right, .. } => {
+            v.visit_expr(left);
+            v.visit_expr(right);
+        }
+        ExprKind::Assign { target, value } => {
+            v.visit_expr(target);
+            v.visit_expr(value);
+        }
+        ExprKind::Call { args, .. } => {
+            for arg in args {
+                v.visit_expr(arg);
+            }
+        }
+    }
+}
+
+
+// in-place traversal, for passes that edit nodes without changing their shape
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program);
+    }
+
+    fn visit_function_mut(&mut self, func: &mut Function) {
+        walk_function_mut(self, func);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+}
+
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(v: &mut V, program: &mut Program) {
+    for stmt in &mut program.static_asserts {
+        v.visit_stmt_mut(stmt);
+    }
+    for func in &mut program.functions {
+        v.visit_function_mut(func);
+    }
+}
+
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(v: &mut V, func: &mut Function) {
+    v.visit_block_mut(&mut func.body);
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(v: &mut V, block: &mut Block) {
+    for stmt in &mut block.stmts {
+        v.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(v: &mut V, stmt: &mut Stmt) {
+    match &mut stmt.kind {
+        StmtKind::VarDecl { value: Some(expr), .. } | StmtKind::ExprStmt(expr) | StmtKind::Return(Some(expr)) | StmtKind::StaticAssert { cond: expr, .. } => v.visit_expr_mut(expr),
+        StmtKind::VarDecl { value: None, .. } | StmtKind::Return(None) | StmtKind::Empty => {}
+        StmtKind::Block(block) => v.visit_block_mut(block),
+        StmtKind::If { cond, then, otherwise } => {
+            v.visit_expr_mut(cond);
+            v.visit_stmt_mut(then);
+            if let Some(otherwise) = otherwise {
+                v.visit_stmt_mut(otherwise);
+            }
+        }
+        StmtKind::While { cond, body } => {
+            v.visit_expr_mut(cond);
+            v.visit_stmt_mut(body);
+        }
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(v: &mut V, expr: &mut Expr) {
+    match &mut expr.kind {
+        ExprKind::Number(..) | ExprKind::FloatNumber(..) | ExprKind::CharLiteral(_) | ExprKind::StringLiteral(_) | ExprKind::Ident(_) => {}
+        ExprKind::Unary { expr, .. } => v.visit_expr_mut(expr),
+        ExprKind::Binary { left, right, .. } => {
+            v.visit_expr_mut(left);
+            v.visit_expr_mut(right);
+        }
+        ExprKind::Assign { target, value } => {
+            v.visit_expr_mut(target);
+            v.visit_expr_mut(value);
+        }
+        ExprKind::Call { args, .. } => {
+            for arg in args {
+                v.visit_expr_mut(arg);
+            }
+        }
+    }
+}
+
+
+// rewriting traversal that takes nodes by value and builds new ones, so a node may
+// be replaced by one of a different kind; the fold_* functions rebuild the children
+pub trait Folder {
+    fn fold_program(&mut self, program: Program) -> Program {
+        fold_program(self, program)
+    }
+
+    fn fold_function(&mut self, func: Function) -> Function {
+        fold_function(self, func)
+    }
+
+    fn fold_block(&mut self, block: Block) -> Block {
+        fold_block(self, block)
+    }
+
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        fold_stmt(self, stmt)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        fold_expr(self, expr)
+    }
+}
+
+
+pub fn fold_program<F: Folder + ?Sized>(f: &mut F, program: Program) -> Program {
+    let static_asserts = program.static_asserts.into_iter().map(|stmt| f.fold_stmt(stmt)).collect();
+    Program { functions: program.functions.into_iter().map(|func| f.fold_function(func)).collect(), static_asserts }
+}
+
+pub fn fold_function<F: Folder + ?Sized>(f: &mut F, func: Function) -> Function {
+    Function { body: f.fold_block(func.body), ..func }
+}
+
+pub fn fold_block<F: Folder + ?Sized>(f: &mut F, block: Block) -> Block {
+    Block { stmts: block.stmts.into_iter().map(|stmt| f.fold_stmt(stmt)).collect() }
+}
+
+pub fn fold_stmt<F: Folder + ?Sized>(f: &mut F, stmt: Stmt) -> Stmt {
+    let kind = match stmt.kind {
+        StmtKind::VarDecl { ty, name, value, is_const } => StmtKind::VarDecl { ty, name, value: value.map(|e| f.fold_expr(e)), is_const },
+        StmtKind::ExprStmt(expr) => StmtKind::ExprStmt(f.fold_expr(expr)),
+        StmtKind::Return(expr) => StmtKind::Return(expr.map(|e| f.fold_expr(e))),
+        StmtKind::StaticAssert { cond, message } => StmtKind::StaticAssert { cond: f.fold_expr(cond), message },
+        StmtKind::Empty => StmtKind::Empty,
+        StmtKind::Block(block) => StmtKind::Block(f.fold_block(block)),
+        StmtKind::If { cond, then, otherwise } => {
+            let cond = f.fold_expr(cond);
+            let then = Box::new(f.fold_stmt(*then));
+            StmtKind::If { cond, then, otherwise: otherwise.map(|o| Box::new(f.fold_stmt(*o))) }
+        }
+        StmtKind::While { cond, body } => {
+            let cond = f.fold_expr(cond);
+            StmtKind::While { cond, body: Box::new(f.fold_stmt(*body)) }
+        }
+    };
+    Stmt { id: stmt.id, kind }
+}
+
+// the rebuilt node keeps the id of the one it replaces
+pub fn fold_expr<F: Folder + ?Sized>(f: &mut F, expr: Expr) -> Expr {
+    let kind = match expr.kind {
+        ExprKind::Unary { op, expr } => ExprKind::Unary { op, expr: Box::new(f.fold_expr(*expr)) },
+        ExprKind::Binary { op, left, right } => ExprKind::Binary {
+            op,
+            left: Box::new(f.fold_expr(*left)),
+            right: Box::new(f.fold_expr(*right)),
+        },
+        ExprKind::Assign { target, value } => ExprKind::Assign { target: Box::new(f.fold_expr(*target)), value: Box::new(f.fold_expr(*value)) },
+        ExprKind::Call { name, args } => ExprKind::Call { name, args: args.into_iter().map(|arg| f.fold_expr(arg)).collect() },
+        leaf => leaf,
+    };
+    Expr { id: expr.id, kind }
+}
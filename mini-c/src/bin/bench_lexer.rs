@@ -0,0 +1,77 @@
+use std::env;
+use std::time::{Duration, Instant};
+use mini_c::lexer::Lexer;
+
+// Lexer throughput benchmark on a generated multi-megabyte source.
+// Usage: cargo run --release --bin bench_lexer -- [megabytes]   (default 8)
+
+// build roughly `bytes` of mini-c source with a realistic token mix
+fn generate_source(bytes: usize) -> String {
+    let mut src = String::with_capacity(bytes + 256);
+    let mut i: u64 = 0;
+    while src.len() < bytes {
+        src.push_str(&format!(
+            "// function number {i}\n\
+             int func_{i}(int a_{i}, float b_{i}) {{\n\
+             \tunsigned long mask_{i} = 0x{i:X}ul;\n\
+             \tdouble ratio_{i} = {i}.25e-3;\n\
+             \t/* block comment with some words in it */\n\
+             \tchar c_{i} = 'x';\n\
+             \tprintf(\"value %d of %f\\n\", a_{i}, b_{i});\n\
+             \treturn {i};\n\
+             }}\n\n",
+        ));
+        i += 1;
+    }
+    src
+}
+
+// run `f` a few times and keep the fastest time
+fn best_of<F: FnMut() -> usize>(runs: usize, mut f: F) -> (Duration, usize) {
+    let mut best = Duration::MAX;
+    let mut count = 0;
+    for _ in 0..runs {
+        let start = Instant::now();
+        count = f();
+        best = best.min(start.elapsed());
+    }
+    (best, count)
+}
+
+fn report(name: &str, bytes: usize, (time, tokens): (Duration, usize)) {
+    let secs = time.as_secs_f64();
+    println!(
+        "{:<28} {:>10} tokens  {:>8.2} ms  {:>8.1} MB/s  {:>6.1} Mtok/s",
+        name,
+        tokens,
+        secs * 1e3,
+        bytes as f64 / secs / 1e6,
+        tokens as f64 / secs / 1e6
+    );
+}
+
+fn main() {
+    let megabytes: usize = env::args().nth(1).and_then(|a| a.parse().ok()).unwrap_or(8);
+    let source = generate_source(megabytes * 1_000_000);
+    println!("Lexing {:.1} MB of generated source (best of 5 runs)", source.len() as f64 / 1e6);
+
+    // borrowed input: tokens only touch the source through slices
+    report("iterate (count)", source.len(), best_of(5, || Lexer::new(&source).count()));
+
+    // zero-copy consumer: look at token text without building owned tokens for it
+    report("token_text (bytes seen)", source.len(), best_of(5, || {
+        let mut lexer = Lexer::new(&source);
+        let mut n = 0;
+        while lexer.next().is_some() {
+            n += usize::from(!lexer.token_text().is_empty());
+        }
+        n
+    }));
+
+    // what the parser does: collect everything into a Vec<Token>
+    report("collect Vec<Token>", source.len(), best_of(5, || Lexer::new(&source).collect::<Vec<_>>().len()));
+
+    // CRLF input has to be normalized into an owned buffer first
+    let crlf = source.replace('\n', "\r\n");
+    report("collect (CRLF input)", crlf.len(), best_of(5, || Lexer::new(&crlf).collect::<Vec<_>>().len()));
+}
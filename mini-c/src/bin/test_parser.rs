@@ -1,33 +1,27 @@
 use std::fs;
 use mini_c::lexer::Lexer;
 use mini_c::parser::Parser;
+use mini_c::printer;
+use mini_c::ast::{walk_expr, walk_stmt, Expr, ExprKind, Stmt, StmtKind, Visitor};
 use mini_c::token::Token;
 
 fn main() {
     let source = fs::read_to_string("examples/example1.c").unwrap();
     
     // First, tokenize the input
-    let mut lexer = Lexer::new(&source);
-    let mut tokens = Vec::new();
-    
-    // Collect all tokens
-    loop {
-        let token = lexer.next_token();
-        if matches!(token, Token::EOF) {
-            tokens.push(token);
-            break;
-        }
-        tokens.push(token);
-    }
+    let tokens: Vec<Token> = Lexer::new(&source).collect();
     
     // Parse the tokens into an AST
     let mut parser = Parser::new(tokens);
     let program = parser.parse_program();
+    for e in parser.errors() {
+        eprintln!("parse error: {}", e);
+    }
     
-    // Print the resulting AST with nice formatting
+    // Print the resulting AST back as canonical source
     println!("Parser Output (AST) for examples/example1.c:");
     println!("-------------------------------------------");
-    println!("{:#?}", program);
+    print!("{}", printer::print_program(&program));
     
     // Print a simplified summary of the parsed program
     println!("\nProgram Summary:");
@@ -40,24 +34,45 @@ fn main() {
         println!("  Body contains {} statements", function.body.stmts.len());
         
         // Print a condensed view of each statement
-        for (i, stmt) in function.body.stmts.iter().enumerate() {
-            match stmt {
-                mini_c::ast::Stmt::VarDecl { name, .. } => {
-                    println!("    Stmt {}: Variable Declaration: {} = ...", i+1, name);
-                },
-                mini_c::ast::Stmt::ExprStmt(expr) => {
-                    match expr {
-                        mini_c::ast::Expr::Call { name, .. } => {
-                            println!("    Stmt {}: Function Call: {}(...)", i+1, name);
-                        },
-                        _ => println!("    Stmt {}: Expression Statement", i+1),
-                    }
-                },
-                mini_c::ast::Stmt::Return(..) => {
-                    println!("    Stmt {}: Return Statement", i+1);
-                },
-            }
+        let mut summary = Summary::default();
+        summary.visit_function(function);
+        for (i, line) in summary.stmts.iter().enumerate() {
+            println!("    Stmt {}: {}", i + 1, line);
+        }
+        println!("  Calls: [{}]", summary.calls.join(", "));
+        println!();
+    }
+}
+
+
+// one line per statement plus every function called, nested calls included
+#[derive(Default)]
+struct Summary {
+    stmts: Vec<String>,
+    calls: Vec<String>,
+}
+
+impl Visitor for Summary {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        let line = match &stmt.kind {
+            StmtKind::VarDecl { name, .. } => format!("Variable Declaration: {} = ...", name),
+            StmtKind::ExprStmt(Expr { kind: ExprKind::Call { name, .. }, .. }) => format!("Function Call: {}(...)", name),
+            StmtKind::ExprStmt(_) => "Expression Statement".to_string(),
+            StmtKind::Return(..) => "Return Statement".to_string(),
+            StmtKind::StaticAssert { .. } => "Static Assertion".to_string(),
+            StmtKind::Empty => "Empty Statement".to_string(),
+            StmtKind::Block(_) => "Block".to_string(),
+            StmtKind::If { .. } => "If Statement".to_string(),
+            StmtKind::While { .. } => "While Loop".to_string(),
+        };
+        self.stmts.push(line);
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Call { name, .. } = &expr.kind {
+            self.calls.push(name.clone());
         }
-        println!("");
+        walk_expr(self, expr);
     }
 }
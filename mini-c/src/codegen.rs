@@ -1,6 +1,17 @@
 // A simple interpreter for a subset of Mini C, for testing purposes.
+// integers behave like on the LP64 targets: each value is kept in the range of
+// its type, unsigned arithmetic and (unless trapping) signed overflow wrap
+// around, and division by zero or of the minimum by -1 stop the program
 use crate::ast::*;
+use crate::consteval::ConstError;
+use crate::printer::print_expr;
+use crate::resolve::SymbolId;
+use crate::symbol::Symbol;
+use crate::semantic::{ConstValue, SideTables};
+use crate::format::{self, FormatArg};
 use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, Write};
 
 
 // supress warnings for unused code
@@ -13,6 +24,9 @@ pub enum Value {
     	Int(i64),
     	Float(f64),
     	Char(char),
+    	Str(String), // a string literal, or the text a `char *` variable was given
+    	Ref(SymbolId), // the address of a variable, for the library functions to store through
+    	Args(Vec<String>), // main's argv: the command line it was run with
     	Void,
 }
 
@@ -25,273 +39,747 @@ impl Value {
 			_ => None,
 		}
 	}
+}
+
+
+
+// a mapping of resolved variables to their runtime values, so shadowed names stay apart
+type Locals = HashMap<SymbolId, Value>;
+
+
+// how a program is run
+#[derive(Default)]
+pub struct RunConfig<'a> {
+	pub trap_overflow: bool, // signed overflow is an error instead of wrapping (-ftrapv)
+	pub stdin: Option<&'a mut dyn BufRead>, // what getchar, scanf and fgets read; the process's stdin if None
+	pub stdout: Option<&'a mut dyn Write>, // where the program's output goes; captured in the RunOutput if None
+	pub args: Vec<String>, // the command line, program name first; main sees their count as argc
+}
+
+
+// how a program ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutput {
+	pub exit_code: i32,
+	pub stdout: Vec<u8>, // the output, unless the configuration had a stdout to write it to
+}
+
 
+// why a program stopped; undefined arithmetic keeps the node it happened in
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+	Undefined { error: ConstError, node: NodeId },
+	Other(String),
+}
 
 
-	// extract float value
-	fn as_float(&self) -> Option<f64> {
+impl RuntimeError {
+	// the node to locate the error at, if any
+	pub fn node(&self) -> Option<NodeId> {
 		match self {
-			Value::Float(f) => Some(*f),
-			_ => None,
+			RuntimeError::Undefined { node, .. } => Some(*node),
+			RuntimeError::Other(_) => None,
 		}
 	}
 }
 
 
+impl fmt::Display for RuntimeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			RuntimeError::Undefined { error, .. } => write!(f, "{}", error),
+			RuntimeError::Other(message) => f.write_str(message),
+		}
+	}
+}
+
+
+impl From<String> for RuntimeError {
+	fn from(message: String) -> Self {
+		RuntimeError::Other(message)
+	}
+}
+
+
+// what every function of a running program can see
+struct Runtime<'a> {
+	program: &'a Program,
+	tables: &'a SideTables,
+	trap_overflow: bool,
+	input: &'a mut dyn BufRead,
+	output: &'a mut dyn Write,
+}
+
 
-// a mapping of variable names to their runtime values
-type Locals = HashMap<String, Value>;
+// Execute the whole program with the side tables from semantic analysis, on
+// the process's standard input and output.
+// Returns the exit code of `main` 0 to 255 on success
+pub fn run(program: &Program, tables: &SideTables) -> Result<i32, RuntimeError> {
+	let mut stdout = std::io::stdout();
+	let mut config = RunConfig { stdout: Some(&mut stdout), ..Default::default() };
+	run_with(program, tables, &mut config).map(|output| output.exit_code)
+}
 
 
-// Execute the whole program. Returns the exit code of `main` 0 to 255 on success
-pub fn run(program: &Program) -> Result<i32, String> {
+// run with a configuration
+pub fn run_with(program: &Program, tables: &SideTables, config: &mut RunConfig) -> Result<RunOutput, RuntimeError> {
 	let main_func = program.functions.iter().find(|f| f.name == "main");
 	if main_func.is_none() {
-		return Err("No `main` function found".to_string());
+		return Err("No `main` function found".to_string().into());
 	}
 
 
 	let main = main_func.unwrap();
-	// execute main with no args
-	match execute_function(main, program, vec![]) {
-		Ok(v) => match v {
-			Value::Int(i) => Ok((i & 0xff) as i32),
-			Value::Void => Ok(0),
-			_ => Ok(0),
-		},
-		Err(e) => Err(e),
-	}
+	// the process stdin is only locked when the caller supplies no input,
+	// which may itself be a lock on stdin
+	let mut stdin;
+	let input: &mut dyn BufRead = match config.stdin.as_deref_mut() {
+		Some(input) => input,
+		None => {
+			stdin = std::io::stdin().lock();
+			&mut stdin
+		}
+	};
+	let mut captured = Vec::new();
+	let output: &mut dyn Write = match config.stdout.as_deref_mut() {
+		Some(output) => output,
+		None => &mut captured,
+	};
+	let mut rt = Runtime { program, tables, trap_overflow: config.trap_overflow, input, output };
+
+	// main gets argc and argv from the configured command line
+	let args = vec![Value::Int(config.args.len() as i64), Value::Args(config.args.clone())];
+	// semantic analysis made sure it returns int; falling off its end returns 0
+	let result = execute_function(main, &mut rt, args);
+	flush(&mut rt)?;
+	let exit_code = match convert(result?, &Type::Int) {
+		Value::Int(i) => (i & 0xff) as i32,
+		Value::Void => 0,
+		other => return Err(format!("`main` returned a non-int value {:?}", other).into()),
+	};
+	Ok(RunOutput { exit_code, stdout: captured })
 }
 
 
-// Execute a function with given arguments. Returns the return value or an error string.
-fn execute_function(func: &Function, program: &Program, args: Vec<Value>) -> Result<Value, String> {
+// Execute a function with given arguments. Returns the return value or an error.
+fn execute_function(func: &Function, rt: &mut Runtime, args: Vec<Value>) -> Result<Value, RuntimeError> {
+	let tables = rt.tables;
 	let mut locals: Locals = HashMap::new();
-	for (i, (ty, name)) in func.params.iter().enumerate() {
+	let param_ids = tables.resolution.params_of(func.id);
+	for (i, ((ty, _), &id)) in func.params.iter().zip(param_ids).enumerate() {
 		if i < args.len() {
-			locals.insert(name.clone(), args[i].clone());
+			// arguments are converted to the parameter types like by assignment
+			locals.insert(id, convert(args[i].clone(), ty));
 		} else {
 			// missing param -> default to zero-like
 			let v = match ty {
-				Type::Int => Value::Int(0),
-				Type::Float => Value::Float(0.0),
 				Type::Char => Value::Char('\0'),
 				Type::Void => Value::Void,
+				t if t.is_floating() => Value::Float(0.0),
+				_ => Value::Int(0),
 			};
-			locals.insert(name.clone(), v);
+			locals.insert(id, v);
 		}
 	}
 
-	// execute statements sequentially
-	for stmt in &func.body.stmts {
-		if let Some(ret) = execute_stmt(stmt, &mut locals, program)? {
-			return Ok(ret);
-		}
+	// execute statements sequentially; the value returned is converted to the return type
+	let mut interpreter = Interpreter { rt, locals, values: Vec::new(), stop: None };
+	interpreter.visit_block(&func.body);
+	match interpreter.stop {
+		Some(Stop::Return(ret)) => Ok(convert(ret, &func.return_type)),
+		Some(Stop::Error(e)) => Err(e),
+		// no explicit return -> default to void
+		None => Ok(Value::Void),
 	}
-	
+}
+
+
 
-	// no explicit return -> default to void
-	Ok(Value::Void)
+// why a call stopped before the end of its function
+enum Stop {
+	Return(Value),
+	Error(RuntimeError),
 }
 
 
+// the interpreter of one call. it collects the values of the expressions
+// evaluated so far, innermost last, and once a `return` or an error stops
+// the call nothing more is visited
+struct Interpreter<'r, 'a> {
+	rt: &'r mut Runtime<'a>,
+	locals: Locals,
+	values: Vec<Value>,
+	stop: Option<Stop>,
+}
 
-// Execute a statement. Returns Ok(Some(value)) if a return occurred with that value.
-fn execute_stmt(stmt: &Stmt, locals: &mut Locals, program: &Program) -> Result<Option<Value>, String> {
-	match stmt {
 
-		// variable declaration: evaluate initializer and store in locals
-		Stmt::VarDecl { ty: _, name, value } => {
-			let v = eval_expr(value, locals, program)?;
-			locals.insert(name.clone(), v);
-			Ok(None)
-		}
-		
-		// expression statement: evaluate expression, discard result
-		Stmt::ExprStmt(e) => {
-			let _ = eval_expr(e, locals, program)?;
-			Ok(None)
-		}
-		
-		// return statement: evaluate expression and return value
-		Stmt::Return(expr) => {
-			let v = eval_expr(expr, locals, program)?;
-			Ok(Some(v))
+impl Interpreter<'_, '_> {
+	fn pop(&mut self) -> Value {
+		self.values.pop().expect("evaluated expression left no value")
+	}
+
+	// evaluate an expression and take its value
+	fn value(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+		self.visit_expr(expr);
+		match self.stop.take() {
+			Some(Stop::Error(e)) => Err(e),
+			Some(Stop::Return(_)) => unreachable!("an expression cannot return"),
+			None => Ok(self.pop()),
 		}
 	}
-}
+
+	// whether a condition holds: it is not zero
+	fn holds(&mut self, cond: &Expr) -> Result<bool, RuntimeError> {
+		truthy(&self.value(cond)?)
+	}
 
 
+	// Execute a statement; a `return` stops the call with its value.
+	fn execute(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
+		let tables = self.rt.tables;
+		match &stmt.kind {
 
-// Evaluate an expression and return its value
-fn eval_expr(expr: &Expr, locals: &mut Locals, program: &Program) -> Result<Value, String> {
-	
-	// match on expression type 
-	match expr {
-		Expr::Number(n) => Ok(Value::Int(*n)),
-		Expr::FloatNumber(f) => Ok(Value::Float(*f)),
-		Expr::CharLiteral(c) => Ok(Value::Char(*c)),
-	Expr::StringLiteral(_s) => Ok(Value::Void), 
-		
-	Expr::Ident(name) => {
-
-		// look up variable in locals
-			if let Some(v) = locals.get(name) {
-				Ok(v.clone())
-			} else {
-				Err(format!("Undefined variable at runtime: {}", name))
+			// variable declaration: evaluate initializer and store in locals,
+			// converted to the declared type when the initializer's type differs;
+			// without one the variable has no value until it is assigned, even if
+			// an earlier iteration of a loop gave it one
+			StmtKind::VarDecl { value: None, .. } => {
+				if let Some(id) = tables.resolution.declared_by(stmt.id) {
+					self.locals.remove(&id);
+				}
+			}
+			StmtKind::VarDecl { ty, value: Some(value), .. } => {
+				let mut v = self.value(value)?;
+				if tables.type_of(value) != Some(ty) {
+					v = convert(v, ty);
+				}
+				if let Some(id) = tables.resolution.declared_by(stmt.id) {
+					self.locals.insert(id, v);
+				}
+			}
+
+			// expression statement: evaluate expression, discard result
+			StmtKind::ExprStmt(e) => {
+				self.value(e)?;
 			}
-		}
 
+			// return statement: evaluate expression and return value, `return;` gives void
+			StmtKind::Return(Some(expr)) => {
+				let v = self.value(expr)?;
+				self.stop = Some(Stop::Return(v));
+			}
+			StmtKind::Return(None) => self.stop = Some(Stop::Return(Value::Void)),
 
+			// checked by semantic analysis, nothing to do at run time
+			StmtKind::StaticAssert { .. } | StmtKind::Empty => {}
 
-		// unary operation: evaluate sub-expression and apply operator
-		Expr::Unary { op, expr } => {
-			let v = eval_expr(expr, locals, program)?;
-			match (op, v) {
-				(UnaryOp::Neg, Value::Int(i)) => Ok(Value::Int(-i)),
-				(UnaryOp::Neg, Value::Float(f)) => Ok(Value::Float(-f)),
-				(UnaryOp::Not, Value::Int(i)) => Ok(Value::Int((i == 0) as i64)),
-				_ => Err("Unsupported unary operation or type".to_string()),
+			StmtKind::Block(_) => walk_stmt(self, stmt),
+			StmtKind::If { cond, then, otherwise } => {
+				if self.holds(cond)? {
+					self.visit_stmt(then);
+				} else if let Some(otherwise) = otherwise {
+					self.visit_stmt(otherwise);
+				}
+			}
+			StmtKind::While { cond, body } => {
+				while self.stop.is_none() && self.holds(cond)? {
+					self.visit_stmt(body);
+				}
 			}
 		}
+		Ok(())
+	}
+
+
 
+	// The value of an expression whose operands were evaluated, taking their
+	// values off the stack.
+	fn evaluate(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+		let tables = self.rt.tables;
+		match &expr.kind {
+			ExprKind::Number(n, _) => Ok(Value::Int(*n)),
+			ExprKind::FloatNumber(f, _) => Ok(Value::Float(*f)),
+			ExprKind::CharLiteral(c) => Ok(Value::Char(*c)),
+			ExprKind::StringLiteral(s) => Ok(Value::Str(s.clone())),
 
+			ExprKind::Ident(name) => {
 
-		// binary operation: evaluate left and right, apply operator
-		Expr::Binary { op, left, right } => {
-			let l = eval_expr(left, locals, program)?;
-			let r = eval_expr(right, locals, program)?;
-			match (l, r) {
-				(Value::Int(a), Value::Int(b)) => match op {
-					BinaryOp::Add => Ok(Value::Int(a + b)),
-					BinaryOp::Sub => Ok(Value::Int(a - b)),
-					BinaryOp::Mul => Ok(Value::Int(a * b)),
-					BinaryOp::Div => Ok(Value::Int(a / b)),
-				},
-				
-				
-				(Value::Float(a), Value::Float(b)) => match op {
-					BinaryOp::Add => Ok(Value::Float(a + b)),
-					BinaryOp::Sub => Ok(Value::Float(a - b)),
-					BinaryOp::Mul => Ok(Value::Float(a * b)),
-					BinaryOp::Div => Ok(Value::Float(a / b)),
-				},
-
-
-
-				// simple mixed int/float coercion
-				(Value::Int(a), Value::Float(b)) => {
-					let af = a as f64;
-					match op {
-						BinaryOp::Add => Ok(Value::Float(af + b)),
-						BinaryOp::Sub => Ok(Value::Float(af - b)),
-						BinaryOp::Mul => Ok(Value::Float(af * b)),
-						BinaryOp::Div => Ok(Value::Float(af / b)),
+				// look up the variable the name was resolved to
+				if let Some(v) = tables.resolution.binding(expr.id).and_then(|id| self.locals.get(&id)) {
+					Ok(v.clone())
+				} else {
+					Err(format!("Uninitialized variable read at runtime: {}", name).into())
+				}
+			}
+
+
+
+			// the address of a variable refers to it, without reading it
+			ExprKind::Unary { op: UnaryOp::AddrOf, expr: operand } => match tables.resolution.binding(operand.id) {
+				Some(id) => Ok(Value::Ref(id)),
+				None => Err(format!("Cannot take the address of `{}` at runtime", print_expr(operand)).into()),
+			},
+
+			// unary operation: apply the operator to the operand's value;
+			// a char operand is promoted to int first
+			ExprKind::Unary { op, expr: operand } => {
+				let v = self.pop();
+				match (op, int_of(&v), v) {
+					(UnaryOp::Neg, Some(i), _) => {
+						let ty = arithmetic_type(tables.type_of(operand), tables.type_of(operand));
+						int_result(expr, &ty, -ty.exact(i), self.rt)
 					}
+					(UnaryOp::Neg, None, Value::Float(f)) => Ok(Value::Float(-f)),
+					(UnaryOp::Not, Some(i), _) => Ok(Value::Int((i == 0) as i64)),
+					(UnaryOp::Not, None, Value::Float(f)) => Ok(Value::Int((f == 0.0) as i64)),
+					_ => Err("Unsupported unary operation or type".to_string().into()),
 				}
+			}
 
 
 
+			// binary operation: apply the operator to the left and right values
+			ExprKind::Binary { op, left, right } => {
+				let r = self.pop();
+				let l = self.pop();
+				// integer operands are computed in their common type
+				if let (Some(a), Some(b)) = (int_of(&l), int_of(&r)) {
+					let ty = arithmetic_type(tables.type_of(left), tables.type_of(right));
+					let (a, b) = (ty.exact(a), ty.exact(b));
+					if op.is_comparison() {
+						return Ok(truth(*op, Some(a.cmp(&b))));
+					}
+					let result = match op {
+						BinaryOp::Add => a + b,
+						BinaryOp::Sub => a - b,
+						BinaryOp::Mul => a * b,
+						_ if b == 0 => return Err(undefined(ConstError::DivisionByZero { expr: print_expr(expr) }, expr)),
+						// the quotient of the minimum by -1 does not fit, and traps even without -ftrapv
+						_ if a / b != ty.exact(ty.wrap(a / b)) => {
+							return Err(undefined(ConstError::Overflow { expr: print_expr(expr), ty }, expr))
+						}
+						_ => a / b,
+					};
+					return int_result(expr, &ty, result, self.rt);
+				}
+				if op.is_comparison() {
+					return compare(*op, &l, &r).map_err(Into::into);
+				}
+				let char_to_int = |v: Value| match v {
+					Value::Char(c) => Value::Int(char_code(c)),
+					v => v,
+				};
+				match (char_to_int(l), char_to_int(r)) {
+
+
+					(Value::Float(a), Value::Float(b)) => match op {
+						BinaryOp::Add => Ok(Value::Float(a + b)),
+						BinaryOp::Sub => Ok(Value::Float(a - b)),
+						BinaryOp::Mul => Ok(Value::Float(a * b)),
+						BinaryOp::Div => Ok(Value::Float(a / b)),
+						_ => unreachable!("comparisons are handled above"),
+					},
 
-				(Value::Float(a), Value::Int(b)) => {
-					let bf = b as f64;
-					match op {
-						BinaryOp::Add => Ok(Value::Float(a + bf)),
-						BinaryOp::Sub => Ok(Value::Float(a - bf)),
-						BinaryOp::Mul => Ok(Value::Float(a * bf)),
-						BinaryOp::Div => Ok(Value::Float(a / bf)),
+
+
+					// simple mixed int/float coercion
+					(Value::Int(a), Value::Float(b)) => {
+						let af = a as f64;
+						match op {
+							BinaryOp::Add => Ok(Value::Float(af + b)),
+							BinaryOp::Sub => Ok(Value::Float(af - b)),
+							BinaryOp::Mul => Ok(Value::Float(af * b)),
+							BinaryOp::Div => Ok(Value::Float(af / b)),
+							_ => unreachable!("comparisons are handled above"),
+						}
 					}
+
+
+
+
+					(Value::Float(a), Value::Int(b)) => {
+						let bf = b as f64;
+						match op {
+							BinaryOp::Add => Ok(Value::Float(a + bf)),
+							BinaryOp::Sub => Ok(Value::Float(a - bf)),
+							BinaryOp::Mul => Ok(Value::Float(a * bf)),
+							BinaryOp::Div => Ok(Value::Float(a / bf)),
+							_ => unreachable!("comparisons are handled above"),
+						}
+					}
+
+
+
+
+					_ => Err("Unsupported binary operand types".to_string().into()),
 				}
+			}
 
 
 
+			// the value is converted to the type of the variable assigned to
+			ExprKind::Assign { target, value } => {
+				let mut v = self.pop();
+				let ExprKind::Ident(name) = &target.kind else {
+					return Err("Assignment to a non-variable at runtime".to_string().into());
+				};
+				if let Some(ty) = tables.type_of(target) {
+					if tables.type_of(value) != Some(ty) {
+						v = convert(v, ty);
+					}
+				}
+				match tables.resolution.binding(target.id) {
+					Some(id) => self.locals.insert(id, v.clone()),
+					None => return Err(format!("Undefined variable at runtime: {}", name).into()),
+				};
+				Ok(v)
+			}
+			ExprKind::Call { name, args } => {
+				let vals = self.values.split_off(self.values.len() - args.len());
+
+				// library functions the interpreter provides
+				if let Some(v) = self.call_builtin(name, args, &vals)? {
+					return Ok(v);
+				}
 
-				_ => Err("Unsupported binary operand types".to_string()),
+				// user-defined functions
+				if let Some(f) = self.rt.program.functions.iter().find(|ff| ff.name == *name) {
+					return execute_function(f, self.rt, vals);
+				}
+
+				Err(format!("Unknown function called at runtime: {}", name).into())
 			}
 		}
+	}
 
 
-		
-		Expr::Assign { name, value } => {
-			let v = eval_expr(value, locals, program)?;
-			locals.insert(name.clone(), v.clone());
-			Ok(v)
+
+
+	// Call one of the input and output functions of stdio.h with the values
+	// of its arguments, None for any other name. there are no arrays, so the
+	// buffer of sprintf, snprintf and fgets is the `char *` variable passed,
+	// which is given the text; `stdin` is `__stream(0)`
+	fn call_builtin(&mut self, name: &str, args: &[Expr], vals: &[Value]) -> Result<Option<Value>, RuntimeError> {
+		let (min_args, format_at) = match name {
+			"printf" | "scanf" => (1, 0),
+			"sprintf" => (2, 1),
+			"snprintf" => (3, 2),
+			"puts" | "putchar" | "__stream" => (1, 0),
+			"fgets" => (3, 0),
+			"getchar" => (0, 0),
+			_ => return Ok(None),
+		};
+		if args.len() < min_args {
+			return Err(format!("{} requires at least {} argument(s)", name, min_args).into());
 		}
-		Expr::Call { name, args } => {
-			// builtin printf handling
-			if name == "printf" {
-				// very small subset: first arg must be string literal
-				if args.is_empty() {
-					return Err("printf requires at least a format string".to_string());
-				}
-				// evaluate first arg specially if it's a string literal
-				let fmt = match &args[0] {
-					Expr::StringLiteral(s) => s.clone(),
-					_other => {
-						// allow evaluated string-like via expression (not implemented)
-						return Err("printf: first argument must be a string literal in this runtime".to_string());
-					}
-				};
-				// evaluate remaining args
-				let mut vals: Vec<Value> = Vec::new();
-				for a in &args[1..] {
-					vals.push(eval_expr(a, locals, program)?);
+
+		let written = match name {
+			"puts" => {
+				let Value::Str(s) = &vals[0] else { return Err("puts: argument is not a string".to_string().into()) };
+				write_out(self.rt, format!("{}\n", s).as_bytes())?
+			}
+			"putchar" => {
+				let Some(c) = int_of(&vals[0]) else { return Err("putchar: argument is not an integer".to_string().into()) };
+				write_out(self.rt, &[c as u8])?;
+				return Ok(Some(Value::Int(c as u8 as i64)));
+			}
+			"__stream" => return Ok(Some(vals[0].clone())),
+
+			// input: EOF is -1, and fgets gives a null pointer when nothing is left
+			"getchar" => {
+				let c = read_byte(self.rt)?.map_or(-1, |b| b as i64);
+				return Ok(Some(Value::Int(c)));
+			}
+			"fgets" => {
+				if int_of(&vals[2]) != Some(0) {
+					return Err("fgets: only stdin can be read".to_string().into());
 				}
-				// support %d and %f only
-				let mut out = String::new();
-				let mut arg_i = 0;
-				let mut chars = fmt.chars().peekable();
-				while let Some(ch) = chars.next() {
-					if ch == '%' {
-						if let Some(&next) = chars.peek() {
-							if next == 'd' {
-								chars.next();
-								if arg_i < vals.len() {
-									if let Some(iv) = vals[arg_i].as_int() {
-										out.push_str(&format!("{}", iv));
-									} else {
-										return Err("printf: %d with non-int argument".to_string());
-									}
-								}
-								arg_i += 1;
-								continue;
-							} else if next == 'f' {
-								chars.next();
-								if arg_i < vals.len() {
-									if let Some(fv) = vals[arg_i].as_float() {
-										out.push_str(&format!("{}", fv));
-									} else {
-										return Err("printf: %f with non-float argument".to_string());
-									}
-								}
-								arg_i += 1;
-								continue;
+				let Some(size) = int_of(&vals[1]) else { return Err("fgets: size is not an integer".to_string().into()) };
+				let mut line = Vec::new();
+				while (line.len() as i64) < size - 1 {
+					match read_byte(self.rt)? {
+						Some(b) => {
+							line.push(b);
+							if b == b'\n' {
+								break;
 							}
 						}
-						// unsupported format, print % literally
-						out.push('%');
-					} else {
-						out.push(ch);
+						None => break,
 					}
 				}
-				print!("{}", out);
-				return Ok(Value::Int(out.len() as i64));
+				// a size of 1 only has room for the terminator, so nothing is read
+				// and the empty string is stored even at the end of the input
+				if size < 1 || (line.is_empty() && size > 1) {
+					return Ok(Some(Value::Int(0)));
+				}
+				let text = Value::Str(String::from_utf8_lossy(&line).into_owned());
+				self.store(name, &args[0], &vals[0], text.clone())?;
+				return Ok(Some(text));
+			}
+			"scanf" => {
+				let Value::Str(fmt) = &vals[0] else { return Err("scanf: the format is not a string".to_string().into()) };
+				flush(self.rt)?;
+				let scanned = format::scan(fmt, self.rt.input).map_err(|e| format!("scanf: cannot scan `{}`", e))?;
+				if scanned.values.len() > args.len() - 1 {
+					return Err("scanf: too few arguments for the format".to_string().into());
+				}
+				for (i, value) in scanned.values.into_iter().enumerate() {
+					let value = match value {
+						FormatArg::Int(v) => Value::Int(v),
+						FormatArg::Float(f) => Value::Float(f),
+						FormatArg::Str(s) => Value::Str(s),
+					};
+					self.store(name, &args[i + 1], &vals[i + 1], value)?;
+				}
+				return Ok(Some(Value::Int(scanned.result as i64)));
 			}
 
-			// user-defined functions
-			if let Some(f) = program.functions.iter().find(|ff| ff.name == *name) {
-				// evaluate args
-				let mut evaled: Vec<Value> = Vec::new();
-				for a in args {
-					evaled.push(eval_expr(a, locals, program)?);
+			_ => {
+				let out = format_output(name, &vals[format_at..])?;
+				if name == "printf" {
+					write_out(self.rt, &out)?
+				} else {
+					// snprintf keeps at most size - 1 bytes, but counts them all
+					let kept = match name {
+						"snprintf" => match int_of(&vals[1]) {
+							Some(0) => None,
+							Some(size) => Some(&out[..out.len().min(size as usize - 1)]),
+							None => return Err("snprintf: size is not an integer".to_string().into()),
+						},
+						_ => Some(&out[..]),
+					};
+					if let Some(kept) = kept {
+						let text = Value::Str(String::from_utf8_lossy(kept).into_owned());
+						self.store(name, &args[0], &vals[0], text)?;
+					}
+					out.len()
 				}
-				return execute_function(f, program, evaled);
 			}
+		};
+		Ok(Some(Value::Int(written as i64)))
+	}
 
-			Err(format!("Unknown function called at runtime: {}", name))
+
+	// store a value read or formatted by a library function through its argument:
+	// `&x` stores into x, converted to its type (a char takes the first one of a
+	// string), and a `char *` variable passed as a buffer is given the text
+	fn store(&mut self, name: &str, arg: &Expr, pointer: &Value, value: Value) -> Result<(), RuntimeError> {
+		let tables = self.rt.tables;
+		let id = match (pointer, &arg.kind) {
+			(Value::Ref(id), _) => *id,
+			(Value::Str(_), ExprKind::Ident(_)) => match tables.resolution.binding(arg.id) {
+				Some(id) => id,
+				None => return Err(format!("Undefined variable at runtime: {}", print_expr(arg)).into()),
+			},
+			_ => return Err(format!("{}: cannot store through `{}`", name, print_expr(arg)).into()),
+		};
+		let value = match (value, &tables.resolution.declaration(id).symbol) {
+			(Value::Str(s), Symbol::Variable { ty: Type::Char, .. } | Symbol::Param { ty: Type::Char, .. }) => {
+				Value::Char(s.bytes().next().unwrap_or(0) as char)
+			}
+			(value, Symbol::Variable { ty, .. } | Symbol::Param { ty, .. }) => convert(value, ty),
+			(value, Symbol::Function(_)) => value,
+		};
+		self.locals.insert(id, value);
+		Ok(())
+	}
+}
+
+
+impl Visitor for Interpreter<'_, '_> {
+	fn visit_stmt(&mut self, stmt: &Stmt) {
+		if self.stop.is_some() {
+			return;
+		}
+		if let Err(e) = self.execute(stmt) {
+			self.stop = Some(Stop::Error(e));
+		}
+	}
+
+
+	// Evaluate an expression: the walk evaluates the operands first, leaving
+	// their values on the stack, then this node's value is pushed.
+	// constants were already computed by semantic analysis; the target of an
+	// assignment and the operand of `&` are not read
+	fn visit_expr(&mut self, expr: &Expr) {
+		if self.stop.is_some() {
+			return;
 		}
+		if let Some(value) = self.rt.tables.const_of(expr) {
+			self.values.push(match value {
+				ConstValue::Int(i) => Value::Int(i),
+				ConstValue::Float(f) => Value::Float(f),
+			});
+			return;
+		}
+		match &expr.kind {
+			ExprKind::Unary { op: UnaryOp::AddrOf, .. } => {}
+			ExprKind::Assign { value, .. } => self.visit_expr(value),
+			_ => walk_expr(self, expr),
+		}
+		if self.stop.is_some() {
+			return;
+		}
+		match self.evaluate(expr) {
+			Ok(v) => self.values.push(v),
+			Err(e) => self.stop = Some(Stop::Error(e)),
+		}
+	}
+}
+
+
+
+// the next byte of the program's input, None at its end; output written so
+// far is flushed first, so prompts show before the program waits
+fn read_byte(rt: &mut Runtime) -> Result<Option<u8>, RuntimeError> {
+	flush(rt)?;
+	let buf = rt.input.fill_buf().map_err(|e| format!("Cannot read input: {}", e))?;
+	let b = buf.first().copied();
+	if b.is_some() {
+		rt.input.consume(1);
+	}
+	Ok(b)
+}
+
+
+// the bytes a printf-style call produces from its format and the arguments after it
+fn format_output(name: &str, vals: &[Value]) -> Result<Vec<u8>, RuntimeError> {
+	let Value::Str(fmt) = &vals[0] else {
+		return Err(format!("{}: the format is not a string", name).into());
+	};
+	let mut args = Vec::new();
+	for v in &vals[1..] {
+		args.push(match v {
+			Value::Int(i) => FormatArg::Int(*i),
+			Value::Char(c) => FormatArg::Int(char_code(*c)),
+			Value::Float(f) => FormatArg::Float(*f),
+			Value::Str(s) => FormatArg::Str(s.clone()),
+			Value::Ref(_) | Value::Args(_) => return Err(format!("{}: addresses cannot be printed", name).into()),
+			Value::Void => return Err(format!("{}: void value used as an argument", name).into()),
+		});
+	}
+	format::render(fmt, &args).map_err(|e| format!("{}: {}", name, e).into())
+}
+
+
+// write program output, returning the number of bytes
+fn write_out(rt: &mut Runtime, bytes: &[u8]) -> Result<usize, RuntimeError> {
+	rt.output.write_all(bytes).map_err(|e| format!("Cannot write output: {}", e))?;
+	Ok(bytes.len())
+}
+
+
+fn flush(rt: &mut Runtime) -> Result<(), RuntimeError> {
+	rt.output.flush().map_err(|e| format!("Cannot write output: {}", e).into())
+}
+
+
+
+// convert a value to the representation of a declared type; integers are
+// truncated to the width of the type, and become 0 or 1 for _Bool
+fn convert(value: Value, ty: &Type) -> Value {
+	match (value, ty) {
+		(Value::Int(i), Type::Char) => Value::Char((i as u8) as char),
+		(Value::Float(f), Type::Char) => Value::Char((f as i64 as u8) as char),
+		(Value::Float(f), Type::Bool) => Value::Int((f != 0.0) as i64),
+		(Value::Char(c), t) if t.is_integer() && *t != Type::Char => Value::Int(t.wrap(char_code(c) as i128)),
+		(Value::Int(i), t) if t.is_integer() => Value::Int(t.wrap(i as i128)),
+		(Value::Float(f), t) if t.is_integer() => Value::Int(t.wrap(f as i128)),
+		(Value::Char(c), t) if t.is_floating() => Value::Float(char_code(c) as f64),
+		(Value::Int(i), t) if t.is_floating() => Value::Float(i as f64),
+		(value, _) => value,
+	}
+}
+
+
+
+// the value of a char, which is signed
+fn char_code(c: char) -> i64 {
+	c as u32 as u8 as i8 as i64
+}
+
+
+// whether a condition holds: a number is true when it is not zero, an address
+// and a string always are
+fn truthy(value: &Value) -> Result<bool, RuntimeError> {
+	match value {
+		Value::Int(i) => Ok(*i != 0),
+		Value::Char(c) => Ok(*c != '\0'),
+		Value::Float(f) => Ok(*f != 0.0),
+		Value::Str(_) | Value::Ref(_) | Value::Args(_) => Ok(true),
+		Value::Void => Err("void value used as a condition".to_string().into()),
 	}
 }
 
 
+// the integer held by an int or char value
+fn int_of(value: &Value) -> Option<i64> {
+	match value {
+		Value::Int(i) => Some(*i),
+		Value::Char(c) => Some(char_code(*c)),
+		_ => None,
+	}
+}
+
+
+// the type integer operands are computed in, after the usual arithmetic
+// conversions; operands of unknown type count as long
+fn arithmetic_type(left: Option<&Type>, right: Option<&Type>) -> Type {
+	let known = |ty: Option<&Type>| ty.filter(|t| t.is_integer()).cloned().unwrap_or(Type::Long);
+	Type::common(&known(left), &known(right)).unwrap_or(Type::Long)
+}
+
+
+// the exact result of an integer operation as a value of its type; signed
+// overflow wraps around unless the run traps it
+fn int_result(expr: &Expr, ty: &Type, result: i128, rt: &mut Runtime) -> Result<Value, RuntimeError> {
+	let wrapped = ty.wrap(result);
+	if rt.trap_overflow && !ty.is_unsigned() && ty.exact(wrapped) != result {
+		return Err(undefined(ConstError::Overflow { expr: print_expr(expr), ty: ty.clone() }, expr));
+	}
+	Ok(Value::Int(wrapped))
+}
+
+
+fn undefined(error: ConstError, expr: &Expr) -> RuntimeError {
+	RuntimeError::Undefined { error, node: expr.id }
+}
+
+
+
+// evaluate a comparison to 1 or 0; integers compare exactly, anything else
+// as doubles, like after the usual arithmetic conversions
+fn compare(op: BinaryOp, l: &Value, r: &Value) -> Result<Value, String> {
+	let number = |v: &Value| match v {
+		Value::Int(i) => Some(*i as f64),
+		Value::Char(c) => Some(char_code(*c) as f64),
+		Value::Float(f) => Some(*f),
+		Value::Str(_) | Value::Ref(_) | Value::Args(_) | Value::Void => None,
+	};
+	let ordering = match (l.as_int(), r.as_int()) {
+		(Some(a), Some(b)) => Some(a.cmp(&b)),
+		_ => match (number(l), number(r)) {
+			(Some(a), Some(b)) => a.partial_cmp(&b),
+			_ => return Err("Unsupported comparison operand types".to_string()),
+		},
+	};
+	Ok(truth(op, ordering))
+}
+
+
+// the result of a comparison from how its operands are ordered; unordered
+// (NaN) operands only compare unequal
+fn truth(op: BinaryOp, ordering: Option<std::cmp::Ordering>) -> Value {
+	let result = match ordering {
+		Some(o) => match op {
+			BinaryOp::Lt => o.is_lt(),
+			BinaryOp::Gt => o.is_gt(),
+			BinaryOp::Le => o.is_le(),
+			BinaryOp::Ge => o.is_ge(),
+			BinaryOp::Eq => o.is_eq(),
+			_ => o.is_ne(),
+		},
+		None => op == BinaryOp::Ne,
+	};
+	Value::Int(result as i64)
+}
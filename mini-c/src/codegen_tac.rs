@@ -16,7 +16,7 @@ pub fn emit_function(f: &FunctionIR) -> String {
         let line = match instr {
             Instr::StoreLocal { name, src } => format!("  MOV %{}, {}", name, fmt_operand(src)),
             Instr::Call { dest, name, args } => {
-                let a = args.iter().map(|o| fmt_operand(o)).collect::<Vec<_>>().join(", ");
+                let a = args.iter().map(fmt_operand).collect::<Vec<_>>().join(", ");
                 if let Some(d) = dest {
                     format!("  {} = CALL {}({})", d, name, a)
                 } else {
@@ -31,6 +31,11 @@ pub fn emit_function(f: &FunctionIR) -> String {
             
             // binary operation TAC instruction
             Instr::BinOp { dest, op, left, right } => format!("  {} = {} {} {}", dest, fmt_operand(left), op, fmt_operand(right)),
+
+            // control flow
+            Instr::Label(name) => format!("{}:", name),
+            Instr::Jump { target } => format!("  JMP {}", target),
+            Instr::JumpIfZero { cond, target } => format!("  JZ {}, {}", fmt_operand(cond), target),
         };
 
         // append line to output
@@ -51,6 +56,6 @@ fn fmt_operand(o: &Operand) -> String {
         Operand::Local(n) => format!("%{}", n),
         Operand::ConstInt(i) => format!("{}", i),
         Operand::ConstFloat(f) => format!("{}", f),
-        Operand::ConstString(s) => format!("\"{}\"", s),
+        Operand::ConstString(s) => crate::printer::quote_str(s),
     }
 }
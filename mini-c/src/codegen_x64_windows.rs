@@ -1,5 +1,6 @@
 // x64 Windows calling convention code generator
 use crate::ir::{FunctionIR, Instr, Operand};
+use crate::printer::quote_str;
 use std::collections::HashMap;
 
 
@@ -50,23 +51,26 @@ pub fn emit_function(f: &FunctionIR) -> String {
     // assign slots for locals and temps
     for instr in &f.instrs {
         match instr {
-            Instr::StoreLocal { name, .. } => {
-                if !slots.contains_key(name) {
-                    offset += 8;
-                    slots.insert(name.clone(), offset);
-                }
+            Instr::StoreLocal { name, .. } if !slots.contains_key(name) => {
+                offset += 8;
+                slots.insert(name.clone(), offset);
             }
-            Instr::BinOp { dest, .. } => {
-                if !slots.contains_key(dest) {
-                    offset += 8;
-                    slots.insert(dest.clone(), offset);
+            Instr::BinOp { dest, op, left, .. } => {
+                // a local whose address is taken needs a slot even if never stored
+                let addressed = match left {
+                    Operand::Local(name) if op == "addr" => Some(name),
+                    _ => None,
+                };
+                for name in addressed.into_iter().chain([dest]) {
+                    if !slots.contains_key(name) {
+                        offset += 8;
+                        slots.insert(name.clone(), offset);
+                    }
                 }
             }
-            Instr::Call { dest: Some(d), .. } => {
-                if !slots.contains_key(d) {
-                    offset += 8;
-                    slots.insert(d.clone(), offset);
-                }
+            Instr::Call { dest: Some(d), .. } if !slots.contains_key(d) => {
+                offset += 8;
+                slots.insert(d.clone(), offset);
             }
             _ => {}
         }
@@ -99,6 +103,12 @@ pub fn emit_function(f: &FunctionIR) -> String {
                 out.push_str(&format!("mov [rbp-{}], rax\n", off));
             }
 
+            // address of a local: its stack slot
+            Instr::BinOp { dest, op, left: Operand::Local(name), .. } if op == "addr" => {
+                out.push_str(&format!("lea rax, [rbp-{}]\n", slots[name]));
+                out.push_str(&format!("mov [rbp-{}], rax\n", slots[dest]));
+            }
+
             // binary op: load left and right, apply op, store result
                 Instr::BinOp { dest, op, left, right } => {
                 emit_load_operand(&mut out, left, &slots);
@@ -108,6 +118,12 @@ pub fn emit_function(f: &FunctionIR) -> String {
                     "-" => "sub rax, rdx",
                     "*" => "imul rax, rdx",
                     "/" => "cqo\n    idiv rdx",
+                    "<" => "cmp rax, rdx\n    setl al\n    movzx rax, al",
+                    ">" => "cmp rax, rdx\n    setg al\n    movzx rax, al",
+                    "<=" => "cmp rax, rdx\n    setle al\n    movzx rax, al",
+                    ">=" => "cmp rax, rdx\n    setge al\n    movzx rax, al",
+                    "==" => "cmp rax, rdx\n    sete al\n    movzx rax, al",
+                    "!=" => "cmp rax, rdx\n    setne al\n    movzx rax, al",
                     other => other,
                 };
 
@@ -134,6 +150,14 @@ pub fn emit_function(f: &FunctionIR) -> String {
                     out.push_str(&format!("mov [rbp-{}], rax\n", off));
                 }
             }
+            // labels are prefixed with the function name to keep them apart
+            Instr::Label(name) => out.push_str(&format!("{}_{}:\n", f.name, name)),
+            Instr::Jump { target } => out.push_str(&format!("jmp {}_{}\n", f.name, target)),
+            Instr::JumpIfZero { cond, target } => {
+                emit_load_operand(&mut out, cond, &slots);
+                out.push_str("cmp rax, 0\n");
+                out.push_str(&format!("je {}_{}\n", f.name, target));
+            }
             Instr::Return { src } => {
                 if let Some(s) = src {
                     emit_load_operand(&mut out, s, &slots);
@@ -156,7 +180,7 @@ pub fn emit_function(f: &FunctionIR) -> String {
     if !str_pool.is_empty() {
         out.push_str("\n; data section\n");
         for (s, lbl) in &str_pool {
-            out.push_str(&format!("{}: db \"{}\",0\n", lbl, s.replace("\"","\\\"")));
+            out.push_str(&format!("{}: db {}\n", lbl, nasm_bytes(s)));
         }
     }
 
@@ -182,7 +206,7 @@ fn emit_load_operand(out: &mut String, op: &Operand, slots: &HashMap<String, i32
         }
         Operand::ConstString(s) => {
             // placeholder: load address of string label into rax
-            out.push_str(&format!("lea rax, [rel {}] ; string {}\n", find_label_for_string(s, slots), s));
+            out.push_str(&format!("lea rax, [rel {}] ; string {}\n", find_label_for_string(s, slots), quote_str(s)));
         }
     }
 }
@@ -193,7 +217,7 @@ fn emit_load_operand_to_reg(out: &mut String, op: &Operand, slots: &HashMap<Stri
         Operand::Local(n) => { let off = slots.get(n).unwrap(); out.push_str(&format!("mov {}, [rbp-{}]\n", reg, off)); }
         Operand::ConstInt(i) => { out.push_str(&format!("mov {}, {}\n", reg, i)); }
         Operand::ConstFloat(f) => { out.push_str(&format!("; mov {} <- float {} (not implemented)\n", reg, f)); out.push_str(&format!("mov {}, 0\n", reg)); }
-        Operand::ConstString(s) => { out.push_str(&format!("lea {}, [rel {}] ; string {}\n", reg, find_label_for_string(s, slots), s)); }
+        Operand::ConstString(s) => { out.push_str(&format!("lea {}, [rel {}] ; string {}\n", reg, find_label_for_string(s, slots), quote_str(s))); }
     }
 }
 
@@ -201,3 +225,26 @@ fn find_label_for_string(s: &str, _slots: &HashMap<String, i32>) -> String {
     let h = crc32fast::hash(s.as_bytes());
     format!("LSTR_{}", h)
 }
+
+
+// NUL-terminated `db` operands: printable runs stay quoted, other bytes are numeric
+fn nasm_bytes(s: &str) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut run = String::new();
+    for &b in s.as_bytes() {
+        if (b' '..=b'~').contains(&b) && b != b'"' {
+            run.push(b as char);
+        } else {
+            if !run.is_empty() {
+                parts.push(format!("\"{}\"", run));
+                run.clear();
+            }
+            parts.push(b.to_string());
+        }
+    }
+    if !run.is_empty() {
+        parts.push(format!("\"{}\"", run));
+    }
+    parts.push("0".to_string());
+    parts.join(",")
+}
@@ -0,0 +1,154 @@
+// Compile-time evaluation of constant expressions: literals combined with
+// unary, arithmetic and comparison operators.
+// integer arithmetic follows C: signed results outside their type overflow,
+// unsigned ones wrap around, and integer division by zero has no value; both
+// errors are reported instead of producing a constant
+use crate::ast::*;
+use crate::printer::print_expr;
+use std::fmt;
+
+
+// compile-time value of a constant expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+}
+
+
+impl ConstValue {
+    pub fn as_float(self) -> f64 {
+        match self {
+            ConstValue::Int(i) => i as f64,
+            ConstValue::Float(f) => f,
+        }
+    }
+}
+
+
+// why an operation on constants has no value
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstError {
+    Overflow { expr: String, ty: Type },
+    DivisionByZero { expr: String },
+}
+
+
+impl fmt::Display for ConstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstError::Overflow { expr, ty } => write!(f, "integer overflow in `{}` of type {}", expr, ty),
+            ConstError::DivisionByZero { expr } => write!(f, "division by zero in `{}`", expr),
+        }
+    }
+}
+
+
+// evaluate one node from the values of its operands, `Ok(None)` when it is
+// not constant. `type_of` gives the type of a node, if known; integer operands
+// are converted to their common type and results are checked against the type
+// of the node, or against i64 without one. an integer division by a constant
+// zero is an error even when the dividend is not constant
+pub fn eval_node(expr: &Expr, type_of: &dyn Fn(&Expr) -> Option<Type>, mut operand: impl FnMut(&Expr) -> Option<ConstValue>) -> Result<Option<ConstValue>, ConstError> {
+    let ty = type_of(expr);
+    let value = match &expr.kind {
+        ExprKind::Number(n, _) => ConstValue::Int(*n),
+        ExprKind::FloatNumber(f, _) => ConstValue::Float(*f),
+        // char is signed
+        ExprKind::CharLiteral(c) => ConstValue::Int(*c as u8 as i8 as i64),
+        ExprKind::Unary { op, expr: inner } => match (op, operand(inner)) {
+            (UnaryOp::Neg, Some(ConstValue::Int(i))) => {
+                let i = ty.as_ref().filter(|t| t.is_integer()).map_or(i as i128, |t| t.exact(i));
+                return int_result(expr, ty.as_ref(), -i).map(Some);
+            }
+            (UnaryOp::Neg, Some(ConstValue::Float(f))) => ConstValue::Float(-f),
+            (UnaryOp::Not, Some(v)) => ConstValue::Int((v.as_float() == 0.0) as i64),
+            (UnaryOp::AddrOf, _) | (_, None) => return Ok(None),
+        },
+        ExprKind::Binary { op, left, right } => {
+            let (a, b) = (operand(left), operand(right));
+            let floating = ty.as_ref().is_some_and(|t| t.is_floating()) || matches!(a, Some(ConstValue::Float(_)));
+            if *op == BinaryOp::Div && b == Some(ConstValue::Int(0)) && !floating {
+                return Err(ConstError::DivisionByZero { expr: print_expr(expr) });
+            }
+            let (Some(a), Some(b)) = (a, b) else { return Ok(None) };
+            let common = type_of(left).zip(type_of(right)).and_then(|(l, r)| Type::common(&l, &r));
+            binary(expr, ty.as_ref(), common.as_ref(), *op, a, b)?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+
+// evaluate a whole expression; `type_of` gives the type of each node
+pub fn eval(expr: &Expr, type_of: &dyn Fn(&Expr) -> Option<Type>) -> Result<Option<ConstValue>, ConstError> {
+    let mut failed = None;
+    let result = eval_node(expr, type_of, |inner| match eval(inner, type_of) {
+        Ok(value) => value,
+        Err(e) => {
+            failed.get_or_insert(e);
+            None
+        }
+    });
+    match failed {
+        Some(e) => Err(e),
+        None => result,
+    }
+}
+
+
+// `common` is the type the operands are converted to, if known
+fn binary(expr: &Expr, ty: Option<&Type>, common: Option<&Type>, op: BinaryOp, a: ConstValue, b: ConstValue) -> Result<ConstValue, ConstError> {
+    let integer = |i: i64| common.filter(|t| t.is_integer()).map_or(i as i128, |t| t.exact(i));
+    if op.is_comparison() {
+        let ordering = match (a, b) {
+            (ConstValue::Int(a), ConstValue::Int(b)) => Some(integer(a).cmp(&integer(b))),
+            (a, b) => a.as_float().partial_cmp(&b.as_float()),
+        };
+        let result = match ordering {
+            Some(o) => match op {
+                BinaryOp::Lt => o.is_lt(),
+                BinaryOp::Gt => o.is_gt(),
+                BinaryOp::Le => o.is_le(),
+                BinaryOp::Ge => o.is_ge(),
+                BinaryOp::Eq => o.is_eq(),
+                _ => o.is_ne(),
+            },
+            None => op == BinaryOp::Ne,
+        };
+        return Ok(ConstValue::Int(result as i64));
+    }
+    match (a, b) {
+        (ConstValue::Int(a), ConstValue::Int(b)) => {
+            let (a, b) = (integer(a), integer(b));
+            let exact = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                _ => a / b, // b != 0, see eval_node
+            };
+            int_result(expr, ty, exact)
+        }
+        (a, b) => {
+            let (a, b) = (a.as_float(), b.as_float());
+            Ok(ConstValue::Float(match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                _ => a / b,
+            }))
+        }
+    }
+}
+
+
+// the exact result of an integer operation as a value of its type
+fn int_result(expr: &Expr, ty: Option<&Type>, exact: i128) -> Result<ConstValue, ConstError> {
+    let overflow = || ConstError::Overflow { expr: print_expr(expr), ty: ty.cloned().unwrap_or(Type::Long) };
+    match ty.and_then(|t| t.int_range().map(|range| (t, range))) {
+        Some((t, (_, hi))) if t.is_unsigned() => Ok(ConstValue::Int(exact.rem_euclid(hi + 1) as i64)),
+        Some((_, (lo, hi))) if exact < lo || exact > hi => Err(overflow()),
+        _ => i64::try_from(exact).map(ConstValue::Int).map_err(|_| overflow()),
+    }
+}
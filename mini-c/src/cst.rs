@@ -0,0 +1,364 @@
+// lossless concrete syntax tree: every byte of the source is kept, either in a
+// token or in the trivia (whitespace, newlines, comments, splices) around it,
+// so printing the tree gives back the original file byte-for-byte
+use crate::lexer::{Lexer, TriviaKind};
+use crate::parser::is_type_start;
+use crate::token::Token;
+use std::fmt;
+
+
+// a piece of trivia and its exact source text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+
+// a token with its exact spelling; trivia up to the end of its line trails it,
+// everything else leads the next token
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub kind: Token,
+    pub text: String,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+
+// kinds of interior nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Program,
+    Function,
+    ParamList,
+    Param,
+    Block,
+    VarDecl,
+    ReturnStmt,
+    ExprStmt,
+    IfStmt,
+    WhileStmt,
+    CallExpr,
+    ArgList,
+    Arg,
+    ParenExpr,
+    StaticAssert,
+    Declaration, // a top-level declaration that is not a function
+    Error,       // tokens that do not fit anywhere
+}
+
+
+// a child of a node: another node or a token
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+
+// an interior node; a Program always ends with the EOF token holding the final trivia
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+
+impl SyntaxNode {
+    // the source text covered by this node, trivia included
+    pub fn text(&self) -> String {
+        self.to_string()
+    }
+
+    // all tokens below this node in source order
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut out = Vec::new();
+        self.collect_tokens(&mut out);
+        out
+    }
+
+    fn collect_tokens<'a>(&'a self, out: &mut Vec<&'a SyntaxToken>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(out),
+                SyntaxElement::Token(tok) => out.push(tok),
+            }
+        }
+    }
+
+    pub fn first_token(&self) -> Option<&SyntaxToken> {
+        self.children.iter().find_map(|child| match child {
+            SyntaxElement::Node(node) => node.first_token(),
+            SyntaxElement::Token(tok) => Some(tok),
+        })
+    }
+
+    // this node and every node below it with the given kind, in source order
+    pub fn descendants(&self, kind: SyntaxKind) -> Vec<&SyntaxNode> {
+        let mut out = Vec::new();
+        self.collect_nodes(kind, &mut out);
+        out
+    }
+
+    fn collect_nodes<'a>(&'a self, kind: SyntaxKind, out: &mut Vec<&'a SyntaxNode>) {
+        if self.kind == kind {
+            out.push(self);
+        }
+        for child in &self.children {
+            if let SyntaxElement::Node(node) = child {
+                node.collect_nodes(kind, out);
+            }
+        }
+    }
+
+    // comments directly above the node, e.g. the doc comment of a function
+    pub fn leading_comments(&self) -> Vec<&str> {
+        self.first_token()
+            .map(|tok| {
+                tok.leading
+                    .iter()
+                    .filter(|t| matches!(t.kind, TriviaKind::LineComment | TriviaKind::BlockComment))
+                    .map(|t| t.text.as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+
+impl fmt::Display for Trivia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for t in &self.leading {
+            write!(f, "{}", t)?;
+        }
+        f.write_str(&self.text)?;
+        for t in &self.trailing {
+            write!(f, "{}", t)?;
+        }
+        Ok(())
+    }
+}
+
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => write!(f, "{}", node)?,
+                SyntaxElement::Token(tok) => write!(f, "{}", tok)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+
+// build the lossless tree for a source file; this never fails, text that does
+// not fit the grammar ends up in Error nodes
+pub fn parse(source: &str) -> SyntaxNode {
+    let mut tokens = lex(source);
+    tokens.reverse();
+    Builder { tokens }.program()
+}
+
+
+// lex the raw source into tokens with their trivia attached
+fn lex(source: &str) -> Vec<SyntaxToken> {
+    let mut lexer = Lexer::lossless(source);
+    let mut tokens: Vec<SyntaxToken> = Vec::new();
+    loop {
+        let mut leading = Vec::new();
+        let mut trailing = !tokens.is_empty();
+        while let Some(kind) = lexer.next_trivia() {
+            let piece = Trivia { kind, text: lexer.token_text().to_string() };
+            if kind == TriviaKind::Newline {
+                trailing = false;
+            }
+            match tokens.last_mut() {
+                Some(prev) if trailing => prev.trailing.push(piece),
+                _ => leading.push(piece),
+            }
+        }
+
+        let kind = lexer.next_token();
+        let done = kind == Token::EOF;
+        tokens.push(SyntaxToken { kind, text: lexer.token_text().to_string(), leading, trailing: Vec::new() });
+        if done {
+            return tokens;
+        }
+    }
+}
+
+
+// groups tokens into nodes; tokens are kept reversed so the next one is at the end,
+// and the EOF token is only taken by `program`
+struct Builder {
+    tokens: Vec<SyntaxToken>,
+}
+
+
+impl Builder {
+    fn peek(&self) -> &Token {
+        self.tokens.last().map_or(&Token::EOF, |t| &t.kind)
+    }
+
+    // the token after the next one
+    fn peek_second(&self) -> &Token {
+        self.tokens.len().checked_sub(2).map_or(&Token::EOF, |i| &self.tokens[i].kind)
+    }
+
+    fn at(&self, tok: &Token) -> bool {
+        self.peek() == tok
+    }
+
+    // move the next token into `children`
+    fn bump(&mut self, children: &mut Vec<SyntaxElement>) {
+        if let Some(tok) = self.tokens.pop() {
+            children.push(SyntaxElement::Token(tok));
+        }
+    }
+
+    // take the next token only if it is `tok`
+    fn eat(&mut self, tok: &Token, children: &mut Vec<SyntaxElement>) {
+        if self.at(tok) {
+            self.bump(children);
+        }
+    }
+
+    fn program(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        while !self.at(&Token::EOF) {
+            if is_type_start(self.peek()) {
+                children.push(SyntaxElement::Node(self.function()));
+            } else if self.at(&Token::StaticAssert) {
+                children.push(SyntaxElement::Node(self.statement()));
+            } else {
+                let mut stray = Vec::new();
+                self.bump(&mut stray);
+                children.push(SyntaxElement::Node(SyntaxNode { kind: SyntaxKind::Error, children: stray }));
+            }
+        }
+        self.bump(&mut children);
+        SyntaxNode { kind: SyntaxKind::Program, children }
+    }
+
+    // type, name, parameter list and body; anything else at the top level is a declaration
+    fn function(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        while is_type_start(self.peek()) {
+            self.bump(&mut children);
+        }
+        if matches!(self.peek(), Token::Ident(_)) || self.peek().keyword_text().is_some() {
+            self.bump(&mut children);
+        }
+        if !self.at(&Token::LParen) {
+            self.expr(&[Token::Semicolon], &mut children);
+            self.eat(&Token::Semicolon, &mut children);
+            return SyntaxNode { kind: SyntaxKind::Declaration, children };
+        }
+
+        children.push(SyntaxElement::Node(self.list(SyntaxKind::ParamList, SyntaxKind::Param)));
+        if self.at(&Token::LBrace) {
+            children.push(SyntaxElement::Node(self.block()));
+        } else {
+            self.eat(&Token::Semicolon, &mut children);
+        }
+        SyntaxNode { kind: SyntaxKind::Function, children }
+    }
+
+    // a parenthesized, comma separated list such as parameters or call arguments
+    fn list(&mut self, kind: SyntaxKind, item: SyntaxKind) -> SyntaxNode {
+        let mut children = Vec::new();
+        self.bump(&mut children);
+        while !matches!(self.peek(), Token::RParen | Token::RBrace | Token::EOF) {
+            let mut item_children = Vec::new();
+            self.expr(&[Token::Comma, Token::RParen], &mut item_children);
+            children.push(SyntaxElement::Node(SyntaxNode { kind: item, children: item_children }));
+            self.eat(&Token::Comma, &mut children);
+        }
+        self.eat(&Token::RParen, &mut children);
+        SyntaxNode { kind, children }
+    }
+
+    fn block(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        self.bump(&mut children);
+        while !matches!(self.peek(), Token::RBrace | Token::EOF) {
+            children.push(SyntaxElement::Node(self.statement()));
+        }
+        self.eat(&Token::RBrace, &mut children);
+        SyntaxNode { kind: SyntaxKind::Block, children }
+    }
+
+    fn statement(&mut self) -> SyntaxNode {
+        if self.at(&Token::LBrace) {
+            return self.block();
+        }
+        if self.at(&Token::If) || self.at(&Token::While) {
+            return self.conditional();
+        }
+        let kind = match self.peek() {
+            Token::Return => SyntaxKind::ReturnStmt,
+            Token::StaticAssert => SyntaxKind::StaticAssert,
+            t if is_type_start(t) || *t == Token::Const => SyntaxKind::VarDecl,
+            _ => SyntaxKind::ExprStmt,
+        };
+        let mut children = Vec::new();
+        self.expr(&[Token::Semicolon], &mut children);
+        self.eat(&Token::Semicolon, &mut children);
+        SyntaxNode { kind, children }
+    }
+
+    // `if (...) statement [else statement]` or `while (...) statement`
+    fn conditional(&mut self) -> SyntaxNode {
+        let kind = if self.at(&Token::If) { SyntaxKind::IfStmt } else { SyntaxKind::WhileStmt };
+        let mut children = Vec::new();
+        self.bump(&mut children);
+        if self.at(&Token::LParen) {
+            children.push(SyntaxElement::Node(self.paren()));
+        }
+        if !matches!(self.peek(), Token::RBrace | Token::EOF) {
+            children.push(SyntaxElement::Node(self.statement()));
+        }
+        if kind == SyntaxKind::IfStmt && self.at(&Token::Else) {
+            self.bump(&mut children);
+            if !matches!(self.peek(), Token::RBrace | Token::EOF) {
+                children.push(SyntaxElement::Node(self.statement()));
+            }
+        }
+        SyntaxNode { kind, children }
+    }
+
+    // a parenthesized group, up to its `)`
+    fn paren(&mut self) -> SyntaxNode {
+        let mut group = Vec::new();
+        self.bump(&mut group);
+        self.expr(&[Token::RParen], &mut group);
+        self.eat(&Token::RParen, &mut group);
+        SyntaxNode { kind: SyntaxKind::ParenExpr, children: group }
+    }
+
+    // tokens up to one of `stops` (or a closing brace), grouping calls and parentheses
+    fn expr(&mut self, stops: &[Token], children: &mut Vec<SyntaxElement>) {
+        while !stops.contains(self.peek()) && !matches!(self.peek(), Token::RBrace | Token::EOF) {
+            match (self.peek(), self.peek_second()) {
+                (Token::Ident(_), Token::LParen) => {
+                    let mut call = Vec::new();
+                    self.bump(&mut call);
+                    call.push(SyntaxElement::Node(self.list(SyntaxKind::ArgList, SyntaxKind::Arg)));
+                    children.push(SyntaxElement::Node(SyntaxNode { kind: SyntaxKind::CallExpr, children: call }));
+                }
+                (Token::LParen, _) => children.push(SyntaxElement::Node(self.paren())),
+                _ => self.bump(children),
+            }
+        }
+    }
+}
@@ -0,0 +1,355 @@
+// Control-flow analysis of function bodies: which statements can be reached,
+// whether control can fall off the end of a function without a `return`, and
+// which locals may be read before they are assigned.
+// each statement is summarized by how it can complete, so compound statements
+// only need to combine the summaries of their parts
+use crate::ast::*;
+use crate::resolve::{Resolution, SymbolId};
+use crate::symbol::Symbol;
+use std::collections::HashSet;
+
+
+// how control can leave a statement or a sequence of statements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completion {
+    Normal,  // control continues with the next statement
+    Returns, // no path continues: each ends in a `return` or a loop that never ends
+}
+
+
+impl Completion {
+    // how control leaves a choice between two paths
+    fn join(self, other: Completion) -> Completion {
+        if self == Completion::Returns && other == Completion::Returns {
+            Completion::Returns
+        } else {
+            Completion::Normal
+        }
+    }
+}
+
+
+// what the analysis found for one function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionFlow {
+    pub completion: Completion,
+    // first statement of every run of statements no path reaches
+    pub unreachable: Vec<NodeId>,
+}
+
+
+impl FunctionFlow {
+    // control can reach the closing brace of the function
+    pub fn falls_off_end(&self) -> bool {
+        self.completion == Completion::Normal
+    }
+}
+
+
+// analyze the body of one function
+pub fn analyze_function(func: &Function) -> FunctionFlow {
+    let mut unreachable = Vec::new();
+    let completion = block_completion(&func.body, &mut unreachable);
+    FunctionFlow { completion, unreachable }
+}
+
+
+// a sequence completes normally only if each statement does; the statements
+// after one that cannot are dead, reported once per run
+fn block_completion(block: &Block, unreachable: &mut Vec<NodeId>) -> Completion {
+    let mut completion = Completion::Normal;
+    for stmt in &block.stmts {
+        if completion != Completion::Normal {
+            unreachable.push(stmt.id);
+            break;
+        }
+        completion = stmt_completion(stmt, unreachable);
+    }
+    completion
+}
+
+
+// an `if` completes normally if either branch does (a missing `else` does);
+// without `break`, a loop is only left when its condition is false, so one
+// whose condition is a nonzero literal is never left
+fn stmt_completion(stmt: &Stmt, unreachable: &mut Vec<NodeId>) -> Completion {
+    match &stmt.kind {
+        StmtKind::Return(_) => Completion::Returns,
+        StmtKind::VarDecl { .. } | StmtKind::ExprStmt(_) | StmtKind::StaticAssert { .. } | StmtKind::Empty => Completion::Normal,
+        StmtKind::Block(block) => block_completion(block, unreachable),
+        StmtKind::If { then, otherwise, .. } => {
+            let then = stmt_completion(then, unreachable);
+            let otherwise = otherwise.as_ref().map_or(Completion::Normal, |o| stmt_completion(o, unreachable));
+            then.join(otherwise)
+        }
+        StmtKind::While { cond, body } => {
+            stmt_completion(body, unreachable);
+            if always_true(cond) {
+                Completion::Returns
+            } else {
+                Completion::Normal
+            }
+        }
+    }
+}
+
+
+// a condition that is a nonzero literal, as in `while (1)`
+fn always_true(cond: &Expr) -> bool {
+    match cond.kind {
+        ExprKind::Number(n, _) => n != 0,
+        ExprKind::CharLiteral(c) => c != '\0',
+        _ => false,
+    }
+}
+
+
+// a read of a local that may happen before any value was stored in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UninitRead {
+    pub symbol: SymbolId,
+    pub stmt: NodeId,     // the statement containing the read
+    pub definitely: bool, // no path assigns it first, rather than only some
+}
+
+
+// a local assigned by one operand and read or assigned by another operand of
+// the same operator or call, which is undefined behaviour
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsequenced {
+    pub symbol: SymbolId,
+    pub stmt: NodeId,
+}
+
+
+// what the checks of the locals of one function found
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalAccesses {
+    pub uninitialized: Vec<UninitRead>,
+    pub unsequenced: Vec<Unsequenced>,
+}
+
+
+// find the reads of locals that are not definitely assigned at that point.
+// params start assigned, locals once their initializer or an assignment to
+// them has been evaluated; a declaration without an initializer starts a new
+// lifetime, without a value. where paths join (after an `if`, or at the head
+// of a loop) a local is definitely assigned only if it is on every path.
+// once its address is taken a local may be assigned through a pointer at any
+// time, so it is no longer definitely unassigned but never becomes assigned.
+// operands whose evaluation order is unspecified (both sides of a binary
+// operator, the arguments of a call) must not access a local another one
+// assigns; such accesses are reported as unsequenced instead.
+// statements no path reaches are not checked
+pub fn check_locals(func: &Function, res: &Resolution) -> LocalAccesses {
+    let mut checker = InitChecker { res, stmt: func.id, racing: HashSet::new(), found: LocalAccesses::default() };
+    let mut state = InitState::default();
+    for &param in res.params_of(func.id) {
+        state.assign(param);
+    }
+    checker.block(&func.body, &mut state);
+    checker.found
+}
+
+
+// locals assigned on every path so far, and those assigned on at least one;
+// `dead` once no path gets here
+#[derive(Debug, Clone, Default)]
+struct InitState {
+    definitely: HashSet<SymbolId>,
+    possibly: HashSet<SymbolId>,
+    dead: bool,
+}
+
+
+impl InitState {
+    fn assign(&mut self, id: SymbolId) {
+        self.definitely.insert(id);
+        self.possibly.insert(id);
+    }
+
+    // the state where two paths meet; a dead path adds nothing
+    fn join(self, other: InitState) -> InitState {
+        if self.dead {
+            return other;
+        }
+        if other.dead {
+            return self;
+        }
+        InitState {
+            definitely: self.definitely.intersection(&other.definitely).copied().collect(),
+            possibly: self.possibly.union(&other.possibly).copied().collect(),
+            dead: false,
+        }
+    }
+}
+
+
+struct InitChecker<'a> {
+    res: &'a Resolution,
+    stmt: NodeId,
+    racing: HashSet<SymbolId>, // assigned by an operand unsequenced with the current one
+    found: LocalAccesses,
+}
+
+
+impl InitChecker<'_> {
+    fn block(&mut self, block: &Block, state: &mut InitState) {
+        for stmt in &block.stmts {
+            self.stmt(stmt, state);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, state: &mut InitState) {
+        if state.dead {
+            return;
+        }
+        self.stmt = stmt.id;
+        match &stmt.kind {
+            StmtKind::VarDecl { value: Some(value), .. } => {
+                self.expr(value, state);
+                if let Some(id) = self.res.declared_by(stmt.id) {
+                    state.assign(id);
+                }
+            }
+            StmtKind::VarDecl { value: None, .. } => {
+                if let Some(id) = self.res.declared_by(stmt.id) {
+                    state.definitely.remove(&id);
+                    state.possibly.remove(&id);
+                }
+            }
+            StmtKind::StaticAssert { .. } | StmtKind::Empty => {}
+            StmtKind::ExprStmt(expr) => self.expr(expr, state),
+            StmtKind::Return(expr) => {
+                if let Some(expr) = expr {
+                    self.expr(expr, state);
+                }
+                state.dead = true;
+            }
+            StmtKind::Block(block) => self.block(block, state),
+            StmtKind::If { cond, then, otherwise } => {
+                self.expr(cond, state);
+                let mut then_state = state.clone();
+                self.stmt(then, &mut then_state);
+                if let Some(otherwise) = otherwise {
+                    self.stmt(otherwise, state);
+                }
+                *state = then_state.join(std::mem::take(state));
+            }
+            // the condition and the body may see what earlier iterations
+            // assigned; the loop is left when the condition is false, before
+            // the body ran or after it
+            StmtKind::While { cond, body } => {
+                let mut assigned = assigned_in(self.res, |a| a.visit_expr(cond), true);
+                assigned.extend(assigned_in(self.res, |a| a.visit_stmt(body), true));
+                state.possibly.extend(assigned);
+                self.expr(cond, state);
+                let mut body_state = state.clone();
+                self.stmt(body, &mut body_state);
+                if always_true(cond) {
+                    state.dead = true;
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, state: &mut InitState) {
+        match &expr.kind {
+            ExprKind::Ident(_) => {
+                let Some(id) = self.res.binding(expr.id) else { return };
+                if matches!(self.res.declaration(id).symbol, Symbol::Function(_)) {
+                    return;
+                }
+                if self.racing.contains(&id) {
+                    self.race(id);
+                } else if !state.definitely.contains(&id) {
+                    self.found.uninitialized.push(UninitRead { symbol: id, stmt: self.stmt, definitely: !state.possibly.contains(&id) });
+                }
+            }
+            // a variable whose address is taken escapes: it may be assigned
+            // through the pointer from then on
+            ExprKind::Unary { op: UnaryOp::AddrOf, expr: operand } => match self.res.binding(operand.id) {
+                Some(id) => {
+                    state.possibly.insert(id);
+                }
+                None => self.expr(operand, state),
+            },
+            ExprKind::Unary { expr: operand, .. } => self.expr(operand, state),
+            ExprKind::Binary { left, right, .. } => self.unsequenced(&[left, right], state),
+            ExprKind::Call { args, .. } => self.unsequenced(&args.iter().collect::<Vec<_>>(), state),
+            ExprKind::Assign { target, value } => {
+                self.expr(value, state);
+                match (&target.kind, self.res.binding(target.id)) {
+                    (ExprKind::Ident(_), Some(id)) => {
+                        if self.racing.contains(&id) {
+                            self.race(id);
+                        }
+                        state.assign(id);
+                    }
+                    _ => self.expr(target, state),
+                }
+            }
+            ExprKind::Number(..) | ExprKind::FloatNumber(..) | ExprKind::CharLiteral(_) | ExprKind::StringLiteral(_) => {}
+        }
+    }
+
+    // one report per local and statement
+    fn race(&mut self, symbol: SymbolId) {
+        let access = Unsequenced { symbol, stmt: self.stmt };
+        if !self.found.unsequenced.contains(&access) {
+            self.found.unsequenced.push(access);
+        }
+    }
+
+    // each operand is checked against the state before any of them, with
+    // what the others assign racing it; afterwards all of them have run
+    fn unsequenced(&mut self, operands: &[&Expr], state: &mut InitState) {
+        let assigned: Vec<HashSet<SymbolId>> = operands.iter().map(|e| assigned_in(self.res, |a| a.visit_expr(e), false)).collect();
+        let outer = self.racing.clone();
+        let mut after = state.clone();
+        for (i, operand) in operands.iter().enumerate() {
+            let mut inner = state.clone();
+            for (j, others) in assigned.iter().enumerate() {
+                if i != j {
+                    self.racing.extend(others);
+                }
+            }
+            self.expr(operand, &mut inner);
+            after.definitely.extend(&inner.definitely);
+            after.possibly.extend(&inner.possibly);
+            self.racing.clone_from(&outer);
+        }
+        *state = after;
+    }
+}
+
+
+// every local assigned by what `visit` walks; with `escapes`, also every
+// local whose address is taken
+fn assigned_in(res: &Resolution, visit: impl FnOnce(&mut Assigned), escapes: bool) -> HashSet<SymbolId> {
+    let mut assigned = Assigned { res, escapes, found: HashSet::new() };
+    visit(&mut assigned);
+    assigned.found
+}
+
+
+struct Assigned<'a> {
+    res: &'a Resolution,
+    escapes: bool,
+    found: HashSet<SymbolId>,
+}
+
+
+impl Visitor for Assigned<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        let target = match &expr.kind {
+            ExprKind::Assign { target, .. } => Some(target),
+            ExprKind::Unary { op: UnaryOp::AddrOf, expr: target } if self.escapes => Some(target),
+            _ => None,
+        };
+        if let Some(id) = target.and_then(|t| self.res.binding(t.id)) {
+            self.found.insert(id);
+        }
+        walk_expr(self, expr);
+    }
+}
@@ -0,0 +1,812 @@
+// Format strings of the printf and scanf families: which library functions take
+// one and where, and a parser turning a format into the arguments its conversion
+// specifications expect, so calls with a literal format can be checked.
+// `render` and `scan` do the output of printf and the input of scanf for the
+// interpreter
+use crate::ast::Type;
+use std::fmt;
+use std::io::BufRead;
+
+
+// how a format string is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatStyle {
+    Printf,
+    Scanf,
+}
+
+
+// a variadic library function taking a format; the arguments before the format
+// (stream, buffer, size) are not checked
+#[derive(Debug, Clone, Copy)]
+pub struct FormatFunction {
+    pub name: &'static str,
+    pub style: FormatStyle,
+    pub format_index: usize, // position of the format among the arguments
+}
+
+
+pub const FORMAT_FUNCTIONS: &[FormatFunction] = &[
+    FormatFunction { name: "printf", style: FormatStyle::Printf, format_index: 0 },
+    FormatFunction { name: "fprintf", style: FormatStyle::Printf, format_index: 1 },
+    FormatFunction { name: "sprintf", style: FormatStyle::Printf, format_index: 1 },
+    FormatFunction { name: "snprintf", style: FormatStyle::Printf, format_index: 2 },
+    FormatFunction { name: "scanf", style: FormatStyle::Scanf, format_index: 0 },
+    FormatFunction { name: "fscanf", style: FormatStyle::Scanf, format_index: 1 },
+    FormatFunction { name: "sscanf", style: FormatStyle::Scanf, format_index: 1 },
+];
+
+
+pub fn format_function(name: &str) -> Option<&'static FormatFunction> {
+    FORMAT_FUNCTIONS.iter().find(|f| f.name == name)
+}
+
+
+// what one argument consumed by a format must be
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    Value(Type),     // an arithmetic value of this type, after default promotions
+    String,          // a `char *` string
+    Pointer(String), // any other pointer, by its C spelling
+}
+
+
+impl std::fmt::Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Value(ty) => write!(f, "{}", ty),
+            Expected::String => f.write_str("char *"),
+            Expected::Pointer(spelling) => f.write_str(spelling),
+        }
+    }
+}
+
+
+// one conversion specification and the arguments it consumes, in order
+// (a `*` field width or precision takes an int before the value)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    pub spec: String,
+    pub args: Vec<Expected>,
+}
+
+
+// the conversions of a format string; Err holds the first specification
+// that is incomplete or has an unknown conversion
+pub fn parse_format(format: &str, style: FormatStyle) -> Result<Vec<Conversion>, String> {
+    let mut conversions = Vec::new();
+    let mut chars = format.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        let mut args = Vec::new();
+        let mut suppressed = false;
+
+        // flags, field width and precision
+        while let Some(&(_, c)) = chars.peek() {
+            match c {
+                '-' | '+' | ' ' | '#' | '0'..='9' | '.' | '\'' => {}
+                '*' if style == FormatStyle::Printf => args.push(Expected::Value(Type::Int)),
+                '*' => suppressed = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        let length = length_modifier(&mut chars);
+        let Some((at, conv)) = chars.next() else { return Err(format[start..].to_string()) };
+        let mut end = at + conv.len_utf8();
+        if conv == '[' && style == FormatStyle::Scanf {
+            if scan_set(&mut chars).is_none() {
+                return Err(format[start..].to_string());
+            }
+            end = chars.peek().map_or(format.len(), |&(i, _)| i);
+        }
+        let spec = format[start..end].to_string();
+        if conv == '%' {
+            continue;
+        }
+        let value = match style {
+            FormatStyle::Printf => printf_arg(conv, &length),
+            FormatStyle::Scanf => scanf_arg(conv, &length),
+        };
+        match value {
+            Some(arg) => {
+                if !suppressed {
+                    args.push(arg);
+                }
+            }
+            None => return Err(spec),
+        }
+        conversions.push(Conversion { spec, args });
+    }
+    Ok(conversions)
+}
+
+
+// the integer type a length modifier selects; hh and h still pass an int
+fn int_type(length: &str, unsigned: bool) -> Option<Type> {
+    let ty = match (length, unsigned) {
+        ("" | "hh" | "h", false) => Type::Int,
+        ("" | "hh" | "h", true) => Type::UnsignedInt,
+        ("l" | "z" | "t", false) => Type::Long,
+        ("l" | "z" | "t", true) => Type::UnsignedLong,
+        ("ll" | "j" | "q", false) => Type::LongLong,
+        ("ll" | "j" | "q", true) => Type::UnsignedLongLong,
+        _ => return None,
+    };
+    Some(ty)
+}
+
+
+fn printf_arg(conv: char, length: &str) -> Option<Expected> {
+    match conv {
+        'd' | 'i' => int_type(length, false).map(Expected::Value),
+        'u' | 'o' | 'x' | 'X' => int_type(length, true).map(Expected::Value),
+        'c' if length.is_empty() => Some(Expected::Value(Type::Int)),
+        'f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A' => match length {
+            "" | "l" => Some(Expected::Value(Type::Double)),
+            "L" => Some(Expected::Value(Type::LongDouble)),
+            _ => None,
+        },
+        's' if length.is_empty() => Some(Expected::String),
+        'p' if length.is_empty() => Some(Expected::Pointer("void *".to_string())),
+        'n' => int_type(length, false).map(|ty| Expected::Pointer(format!("{} *", ty))),
+        _ => None,
+    }
+}
+
+
+// every scanf conversion stores through a pointer
+fn scanf_arg(conv: char, length: &str) -> Option<Expected> {
+    let target = match conv {
+        'd' | 'i' | 'n' => match length {
+            "hh" => "signed char".to_string(),
+            "h" => "short".to_string(),
+            _ => int_type(length, false)?.to_string(),
+        },
+        'u' | 'o' | 'x' | 'X' => match length {
+            "hh" => "unsigned char".to_string(),
+            "h" => "unsigned short".to_string(),
+            _ => int_type(length, true)?.to_string(),
+        },
+        'f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A' => match length {
+            "" => "float".to_string(),
+            "l" => "double".to_string(),
+            "L" => "long double".to_string(),
+            _ => return None,
+        },
+        's' | 'c' | '[' if length.is_empty() => return Some(Expected::String),
+        'p' if length.is_empty() => "void *".to_string(),
+        _ => return None,
+    };
+    Some(Expected::Pointer(format!("{} *", target)))
+}
+
+
+// whether an argument of type `found` matches; char, _Bool and float arguments are
+// promoted to int and double, integers of the same rank match regardless of
+// signedness, and `void *` takes any pointer
+pub fn arg_matches(expected: &Expected, found: &Type) -> bool {
+    match (expected, found) {
+        (Expected::Value(want), found) => {
+            let promoted = match found {
+                Type::Char | Type::Bool => Type::Int,
+                Type::Float => Type::Double,
+                other => other.clone(),
+            };
+            promoted == *want || (want.is_integer() && promoted.is_integer() && signed_of(&promoted) == signed_of(want))
+        }
+        (Expected::String, Type::Pointer(to)) => **to == Type::Char,
+        (Expected::Pointer(spelling), Type::Pointer(_)) => spelling == "void *" || *spelling == found.to_string(),
+        _ => false,
+    }
+}
+
+
+fn signed_of(ty: &Type) -> Type {
+    match ty {
+        Type::UnsignedInt => Type::Int,
+        Type::UnsignedLong => Type::Long,
+        Type::UnsignedLongLong => Type::LongLong,
+        other => other.clone(),
+    }
+}
+
+
+// an argument of a formatted output call at run time; integers are kept by
+// their bits and read as the conversion says, like a C varargs list
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatArg {
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+
+// one conversion specification as written
+#[derive(Debug, Default)]
+struct Spec {
+    left: bool,  // -
+    plus: bool,  // +
+    space: bool, // ' '
+    alt: bool,   // #
+    zero: bool,  // 0
+    width: usize,
+    precision: Option<usize>,
+    length: String,
+}
+
+
+// why a format could not be rendered; each names the specification as written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderError {
+    Invalid(String),         // not a conversion printf knows, or cut off by the end of the format
+    Mismatch(String),        // the argument has the wrong kind for the conversion
+    MissingArgument(String), // the arguments ran out
+}
+
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Invalid(spec) => write!(f, "invalid conversion `{}`", spec),
+            RenderError::Mismatch(spec) => write!(f, "argument does not match `{}`", spec),
+            RenderError::MissingArgument(spec) => write!(f, "too few arguments for `{}`", spec),
+        }
+    }
+}
+
+
+// the bytes printf writes for a format and its arguments, matching glibc
+pub fn render(format: &str, args: &[FormatArg]) -> Result<Vec<u8>, RenderError> {
+    let mut out = Vec::new();
+    let mut args = args.iter();
+    let mut chars = format.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            let mut buf = [0; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let mut spec = Spec::default();
+        let spec_text = |end: Option<&(usize, char)>| format[start..end.map_or(format.len(), |&(i, _)| i)].to_string();
+        let mut next = |chars: &mut Chars| {
+            args.next().ok_or_else(|| RenderError::MissingArgument(spec_text(chars.peek())))
+        };
+
+        // flags
+        while let Some(&(_, c)) = chars.peek() {
+            match c {
+                '-' => spec.left = true,
+                '+' => spec.plus = true,
+                ' ' => spec.space = true,
+                '#' => spec.alt = true,
+                '0' => spec.zero = true,
+                _ => break,
+            }
+            chars.next();
+        }
+
+        // field width; a negative `*` width means left justified
+        if chars.peek().is_some_and(|&(_, c)| c == '*') {
+            chars.next();
+            let FormatArg::Int(w) = next(&mut chars)? else { return Err(RenderError::Mismatch(spec_text(chars.peek()))) };
+            let w = *w as i32;
+            spec.left |= w < 0;
+            spec.width = w.unsigned_abs() as usize;
+        } else {
+            spec.width = digits(&mut chars);
+        }
+
+        // precision; a negative `*` precision counts as none
+        if chars.peek().is_some_and(|&(_, c)| c == '.') {
+            chars.next();
+            if chars.peek().is_some_and(|&(_, c)| c == '*') {
+                chars.next();
+                let FormatArg::Int(p) = next(&mut chars)? else { return Err(RenderError::Mismatch(spec_text(chars.peek()))) };
+                let p = *p as i32;
+                spec.precision = (p >= 0).then_some(p as usize);
+            } else {
+                spec.precision = Some(digits(&mut chars));
+            }
+        }
+
+        spec.length = length_modifier(&mut chars);
+
+        let Some((_, conv)) = chars.next() else { return Err(RenderError::Invalid(spec_text(None))) };
+        let text = spec_text(chars.peek());
+        if conv == '%' {
+            out.push(b'%');
+            continue;
+        }
+        if !"diuoxXcspfFeEgGaA".contains(conv) {
+            return Err(RenderError::Invalid(text));
+        }
+        let arg = next(&mut chars)?;
+        match (conv, arg) {
+            ('d' | 'i', FormatArg::Int(v)) => {
+                let v = match spec.length.as_str() {
+                    "hh" => *v as i8 as i64,
+                    "h" => *v as i16 as i64,
+                    "" => *v as i32 as i64,
+                    _ => *v,
+                };
+                let sign = if v < 0 { "-" } else if spec.plus { "+" } else if spec.space { " " } else { "" };
+                let body = int_body(v.unsigned_abs(), 10, false, spec.precision);
+                pad_number(&mut out, &spec, sign, &body, true);
+            }
+            ('u' | 'o' | 'x' | 'X', FormatArg::Int(v)) => {
+                let v = match spec.length.as_str() {
+                    "hh" => *v as u8 as u64,
+                    "h" => *v as u16 as u64,
+                    "" => *v as u32 as u64,
+                    _ => *v as u64,
+                };
+                let radix = match conv {
+                    'u' => 10,
+                    'o' => 8,
+                    _ => 16,
+                };
+                let mut body = int_body(v, radix, conv == 'X', spec.precision);
+                let mut prefix = "";
+                if spec.alt && conv == 'o' && !body.starts_with('0') {
+                    body.insert(0, '0');
+                } else if spec.alt && v != 0 && radix == 16 {
+                    prefix = if conv == 'X' { "0X" } else { "0x" };
+                }
+                pad_number(&mut out, &spec, prefix, &body, true);
+            }
+            ('c', FormatArg::Int(v)) => pad(&mut out, &spec, b"", &[*v as u8], false),
+            ('s', FormatArg::Str(s)) => {
+                let bytes = s.as_bytes();
+                let shown = &bytes[..spec.precision.map_or(bytes.len(), |p| p.min(bytes.len()))];
+                pad(&mut out, &spec, b"", shown, false);
+            }
+            ('p', FormatArg::Int(0)) => pad(&mut out, &spec, b"", b"(nil)", false),
+            ('p', FormatArg::Int(v)) => {
+                let body = int_body(*v as u64, 16, false, spec.precision);
+                let sign = if spec.plus { "+" } else if spec.space { " " } else { "" };
+                pad_number(&mut out, &spec, &format!("{}0x", sign), &body, true);
+            }
+            ('f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A', FormatArg::Float(f)) => {
+                let sign = if f.is_sign_negative() { "-" } else if spec.plus { "+" } else if spec.space { " " } else { "" };
+                let upper = conv.is_ascii_uppercase();
+                if !f.is_finite() {
+                    let name = if f.is_nan() { "nan" } else { "inf" };
+                    let name = if upper { name.to_uppercase() } else { name.to_string() };
+                    pad(&mut out, &spec, sign.as_bytes(), name.as_bytes(), false);
+                    continue;
+                }
+                let m = f.abs();
+                let (prefix, body) = match conv.to_ascii_lowercase() {
+                    'f' => ("", fixed(m, spec.precision.unwrap_or(6), spec.alt)),
+                    'e' => ("", exponent(m, spec.precision.unwrap_or(6), spec.alt, upper)),
+                    'g' => ("", general(m, spec.precision.unwrap_or(6), spec.alt, upper)),
+                    _ => (if upper { "0X" } else { "0x" }, hex_float(m, spec.precision, spec.alt, upper)),
+                };
+                pad_number(&mut out, &spec, &format!("{}{}", sign, prefix), &body, false);
+            }
+            _ => return Err(RenderError::Mismatch(text)),
+        }
+    }
+    Ok(out)
+}
+
+
+// the rest of a format string, with the offset of each character
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+
+// a decimal number in a specification, 0 when there is none
+fn digits(chars: &mut Chars) -> usize {
+    let mut n: usize = 0;
+    while let Some(d) = chars.peek().and_then(|&(_, c)| c.to_digit(10)) {
+        n = n.saturating_mul(10).saturating_add(d as usize);
+        chars.next();
+    }
+    n
+}
+
+
+// a length modifier of up to two letters, empty when there is none
+fn length_modifier(chars: &mut Chars) -> String {
+    let mut length = String::new();
+    while let Some(&(_, c)) = chars.peek() {
+        if !matches!(c, 'h' | 'l' | 'j' | 'z' | 't' | 'L' | 'q') || length.len() == 2 {
+            break;
+        }
+        length.push(c);
+        chars.next();
+    }
+    length
+}
+
+
+// the members of a scan set after its `[`, and whether `^` negates it; the
+// set runs to the next `]`, which may itself be the first member. None when
+// the format ends first
+fn scan_set(chars: &mut Chars) -> Option<(bool, String)> {
+    let negated = chars.next_if(|&(_, c)| c == '^').is_some();
+    let mut members = String::new();
+    loop {
+        match chars.next()? {
+            (_, ']') if !members.is_empty() => return Some((negated, members)),
+            (_, c) => members.push(c),
+        }
+    }
+}
+
+
+// the digits of an integer with at least `precision` of them; a zero
+// precision prints nothing for zero
+fn int_body(v: u64, radix: u32, upper: bool, precision: Option<usize>) -> String {
+    let digits = match (radix, upper) {
+        (8, _) => format!("{:o}", v),
+        (16, false) => format!("{:x}", v),
+        (16, true) => format!("{:X}", v),
+        _ => v.to_string(),
+    };
+    match precision {
+        Some(0) if v == 0 => String::new(),
+        Some(p) => format!("{:0>p$}", digits, p = p),
+        None => digits,
+    }
+}
+
+
+// `%f`: the alternate form keeps the decimal point
+fn fixed(m: f64, precision: usize, alt: bool) -> String {
+    let mut s = format!("{:.*}", precision, m);
+    if alt && precision == 0 {
+        s.push('.');
+    }
+    s
+}
+
+
+// `%e`: one digit before the point and an exponent of at least two digits
+fn exponent(m: f64, precision: usize, alt: bool, upper: bool) -> String {
+    let s = format!("{:.*e}", precision, m);
+    let (mantissa, exp) = s.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    let point = if alt && precision == 0 { "." } else { "" };
+    let e = if upper { 'E' } else { 'e' };
+    format!("{}{}{}{}{:02}", mantissa, point, e, if exp < 0 { '-' } else { '+' }, exp.abs())
+}
+
+
+// `%g`: `%e` for exponents below -4 or from the precision on, `%f` otherwise,
+// both without trailing zeros unless in the alternate form
+fn general(m: f64, precision: usize, alt: bool, upper: bool) -> String {
+    let p = precision.max(1);
+    let rounded = format!("{:.*e}", p - 1, m);
+    let x: i32 = rounded.split_once('e').unwrap().1.parse().unwrap();
+    let mut s = if x >= -4 && x < p as i32 {
+        fixed(m, (p as i32 - 1 - x) as usize, alt)
+    } else {
+        exponent(m, p - 1, alt, upper)
+    };
+    if !alt {
+        let split = s.find(['e', 'E']).unwrap_or(s.len());
+        let (mantissa, exp) = s.split_at(split);
+        if mantissa.contains('.') {
+            s = format!("{}{}", mantissa.trim_end_matches('0').trim_end_matches('.'), exp);
+        }
+    } else if !s.contains('.') {
+        let split = s.find(['e', 'E']).unwrap_or(s.len());
+        s.insert(split, '.');
+    }
+    s
+}
+
+
+// `%a` without the `0x`: the leading bit, the other 52 as hex digits (rounded
+// to the precision, to even on ties; the leading digit may become 2) and a
+// binary exponent
+fn hex_float(m: f64, precision: Option<usize>, alt: bool, upper: bool) -> String {
+    let bits = m.to_bits();
+    let biased = (bits >> 52) & 0x7ff;
+    let mut mantissa = bits & ((1 << 52) - 1);
+    let (mut lead, exp) = match (m == 0.0, biased) {
+        (true, _) => (0, 0),
+        (false, 0) => (0, -1022),
+        (false, b) => (1, b as i64 - 1023),
+    };
+    let digits = match precision {
+        None => format!("{:013x}", mantissa).trim_end_matches('0').to_string(),
+        Some(p) if p < 13 => {
+            let shift = 4 * (13 - p) as u32;
+            let rest = mantissa & ((1 << shift) - 1);
+            let half = 1 << (shift - 1);
+            mantissa >>= shift;
+            let odd = if p == 0 { lead & 1 == 1 } else { mantissa & 1 == 1 };
+            if rest > half || (rest == half && odd) {
+                mantissa += 1;
+                if mantissa == 1 << (4 * p) {
+                    mantissa = 0;
+                    lead += 1;
+                }
+            }
+            if p == 0 { String::new() } else { format!("{:0p$x}", mantissa, p = p) }
+        }
+        Some(p) => format!("{:013x}{}", mantissa, "0".repeat(p - 13)),
+    };
+    let point = if digits.is_empty() && !alt { "" } else { "." };
+    let s = format!("{}{}{}p{}{}", lead, point, digits, if exp < 0 { '-' } else { '+' }, exp.abs());
+    if upper { s.to_uppercase() } else { s }
+}
+
+
+// pad a number to the field width; the 0 flag pads between the sign or prefix
+// and the digits, unless left justified or an integer has a precision
+fn pad_number(out: &mut Vec<u8>, spec: &Spec, prefix: &str, body: &str, integer: bool) {
+    pad(out, spec, prefix.as_bytes(), body.as_bytes(), !integer || spec.precision.is_none());
+}
+
+
+fn pad(out: &mut Vec<u8>, spec: &Spec, prefix: &[u8], body: &[u8], zero_allowed: bool) {
+    let fill = spec.width.saturating_sub(prefix.len() + body.len());
+    if spec.left {
+        out.extend_from_slice(prefix);
+        out.extend_from_slice(body);
+        out.extend(std::iter::repeat_n(b' ', fill));
+    } else if spec.zero && zero_allowed {
+        out.extend_from_slice(prefix);
+        out.extend(std::iter::repeat_n(b'0', fill));
+        out.extend_from_slice(body);
+    } else {
+        out.extend(std::iter::repeat_n(b' ', fill));
+        out.extend_from_slice(prefix);
+        out.extend_from_slice(body);
+    }
+}
+
+
+// what a scanf call read: the values for its pointer arguments in order, and
+// its result, the number of values assigned or EOF (-1) when the input ended
+// before the first conversion
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scanned {
+    pub values: Vec<FormatArg>,
+    pub result: i32,
+}
+
+
+// the input of a scanf call; only the bytes a conversion takes are consumed
+struct Reader<'a> {
+    input: &'a mut dyn BufRead,
+    count: usize, // bytes consumed, for %n
+}
+
+
+impl Reader<'_> {
+    fn peek(&mut self) -> Result<Option<u8>, String> {
+        let buf = self.input.fill_buf().map_err(|e| format!("cannot read input: {}", e))?;
+        Ok(buf.first().copied())
+    }
+
+    fn bump(&mut self) {
+        self.input.consume(1);
+        self.count += 1;
+    }
+
+    // take the next byte if it is accepted, while `width` allows
+    fn take_if(&mut self, width: &mut usize, accept: impl Fn(u8) -> bool) -> Result<Option<u8>, String> {
+        if *width == 0 {
+            return Ok(None);
+        }
+        match self.peek()? {
+            Some(b) if accept(b) => {
+                self.bump();
+                *width -= 1;
+                Ok(Some(b))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn skip_space(&mut self) -> Result<(), String> {
+        while self.peek()?.is_some_and(|b| b.is_ascii_whitespace()) {
+            self.bump();
+        }
+        Ok(())
+    }
+}
+
+
+// read the input a scanf format describes, like glibc; Err holds an
+// unsupported specification or a read error
+pub fn scan(format: &str, input: &mut dyn BufRead) -> Result<Scanned, String> {
+    let mut reader = Reader { input, count: 0 };
+    let mut values = Vec::new();
+    let mut assigned = 0;
+    let mut converted = false;
+    // the input ending before the first conversion makes the result EOF
+    let ended = |assigned: i32, converted: bool| Ok::<i32, String>(if converted { assigned } else { -1 });
+    let mut chars = format.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        // white space matches any amount of it, other characters themselves
+        if c.is_ascii_whitespace() {
+            reader.skip_space()?;
+            continue;
+        }
+        if c != '%' || chars.peek().is_some_and(|&(_, c)| c == '%') {
+            if c == '%' {
+                chars.next();
+                reader.skip_space()?;
+            }
+            let mut buf = [0; 4];
+            for &b in c.encode_utf8(&mut buf).as_bytes() {
+                match reader.peek()? {
+                    None => return Ok(Scanned { values, result: ended(assigned, converted)? }),
+                    Some(found) if found == b => reader.bump(),
+                    Some(_) => return Ok(Scanned { values, result: assigned }),
+                }
+            }
+            continue;
+        }
+
+        let suppressed = chars.peek().is_some_and(|&(_, c)| c == '*');
+        if suppressed {
+            chars.next();
+        }
+        let width = match digits(&mut chars) {
+            0 => usize::MAX,
+            w => w,
+        };
+        // values are converted to the type they are stored into, so the length is not needed
+        length_modifier(&mut chars);
+        let Some((at, conv)) = chars.next() else { return Err(format[start..].to_string()) };
+        let mut set = None;
+        if conv == '[' {
+            let Some((negated, members)) = scan_set(&mut chars) else { return Err(format[start..].to_string()) };
+            set = Some((negated, members.into_bytes()));
+        }
+
+        if conv == 'n' {
+            if !suppressed {
+                values.push(FormatArg::Int(reader.count as i64));
+            }
+            continue;
+        }
+        if !matches!(conv, 'c' | '[') {
+            reader.skip_space()?;
+        }
+        if reader.peek()?.is_none() {
+            return Ok(Scanned { values, result: ended(assigned, converted)? });
+        }
+
+        let mut width = width;
+        let value = match conv {
+            'd' | 'i' | 'u' | 'o' | 'x' | 'X' | 'p' => scan_int(&mut reader, conv, &mut width)?,
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A' => scan_float(&mut reader, &mut width)?,
+            'c' => {
+                let n = if width == usize::MAX { 1 } else { width };
+                let mut text = Vec::new();
+                while text.len() < n {
+                    match reader.take_if(&mut width, |_| true)? {
+                        Some(b) => text.push(b),
+                        None => return Ok(Scanned { values, result: ended(assigned, converted)? }),
+                    }
+                }
+                Some(FormatArg::Str(String::from_utf8_lossy(&text).into_owned()))
+            }
+            's' | '[' => {
+                let accept = |b: u8| match &set {
+                    Some((negated, members)) => in_set(members, b) != *negated,
+                    None => !b.is_ascii_whitespace(),
+                };
+                let mut text = Vec::new();
+                while let Some(b) = reader.take_if(&mut width, accept)? {
+                    text.push(b);
+                }
+                (!text.is_empty()).then(|| FormatArg::Str(String::from_utf8_lossy(&text).into_owned()))
+            }
+            _ => return Err(format[start..at + conv.len_utf8()].to_string()),
+        };
+
+        // nothing matching the conversion is a matching failure
+        let Some(value) = value else { return Ok(Scanned { values, result: assigned }) };
+        converted = true;
+        if !suppressed {
+            values.push(value);
+            assigned += 1;
+        }
+    }
+    Ok(Scanned { values, result: assigned })
+}
+
+
+// whether a byte is in a scan set; `a-z` is a range unless `-` is first or last
+fn in_set(members: &[u8], b: u8) -> bool {
+    let mut i = 0;
+    while i < members.len() {
+        if i + 2 < members.len() && members[i + 1] == b'-' {
+            if (members[i]..=members[i + 2]).contains(&b) {
+                return true;
+            }
+            i += 3;
+        } else {
+            if members[i] == b {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+
+// an optionally signed integer in the base of the conversion; `%i` reads the
+// base from a 0x or 0 prefix, and `%x` and `%p` accept the 0x one
+fn scan_int(reader: &mut Reader, conv: char, width: &mut usize) -> Result<Option<FormatArg>, String> {
+    let negative = reader.take_if(width, |b| b == b'-' || b == b'+')? == Some(b'-');
+    let mut radix = match conv {
+        'd' | 'u' => 10,
+        'o' => 8,
+        'i' => 10,
+        _ => 16,
+    };
+    let mut seen_digit = false;
+    if matches!(conv, 'i' | 'x' | 'X' | 'p') && reader.take_if(width, |b| b == b'0')?.is_some() {
+        seen_digit = true;
+        if reader.take_if(width, |b| b == b'x' || b == b'X')?.is_some() {
+            radix = 16;
+        } else if conv == 'i' {
+            radix = 8;
+        }
+    }
+    let mut magnitude: u64 = 0;
+    while let Some(b) = reader.take_if(width, |b| (b as char).is_digit(radix))? {
+        magnitude = magnitude.wrapping_mul(radix as u64).wrapping_add((b as char).to_digit(radix).unwrap() as u64);
+        seen_digit = true;
+    }
+    if !seen_digit {
+        return Ok(None);
+    }
+    let value = if negative { magnitude.wrapping_neg() } else { magnitude };
+    Ok(Some(FormatArg::Int(value as i64)))
+}
+
+
+// a decimal floating constant with an optional sign and exponent
+fn scan_float(reader: &mut Reader, width: &mut usize) -> Result<Option<FormatArg>, String> {
+    let mut text = String::new();
+    let digit = |b: u8| b.is_ascii_digit();
+    if let Some(sign) = reader.take_if(width, |b| b == b'-' || b == b'+')? {
+        text.push(sign as char);
+    }
+    let mut mantissa_digits = 0;
+    while let Some(b) = reader.take_if(width, digit)? {
+        text.push(b as char);
+        mantissa_digits += 1;
+    }
+    if reader.take_if(width, |b| b == b'.')?.is_some() {
+        text.push('.');
+        while let Some(b) = reader.take_if(width, digit)? {
+            text.push(b as char);
+            mantissa_digits += 1;
+        }
+    }
+    if mantissa_digits == 0 {
+        return Ok(None);
+    }
+    if reader.take_if(width, |b| b == b'e' || b == b'E')?.is_some() {
+        let mut exponent = String::from("e");
+        if let Some(sign) = reader.take_if(width, |b| b == b'-' || b == b'+')? {
+            exponent.push(sign as char);
+        }
+        let mut exponent_digits = 0;
+        while let Some(b) = reader.take_if(width, digit)? {
+            exponent.push(b as char);
+            exponent_digits += 1;
+        }
+        // an exponent without digits is left out of the value
+        if exponent_digits > 0 {
+            text.push_str(&exponent);
+        }
+    }
+    Ok(text.parse().ok().map(FormatArg::Float))
+}
@@ -20,7 +20,7 @@ impl fmt::Display for Operand {
             Operand::Local(n) => write!(f, "%{}", n),
             Operand::ConstInt(i) => write!(f, "{}", i),
             Operand::ConstFloat(fl) => write!(f, "{}", fl),
-            Operand::ConstString(s) => write!(f, "\"{}\"", s),
+            Operand::ConstString(s) => write!(f, "{}", crate::printer::quote_str(s)),
         }
     }
 }
@@ -33,6 +33,9 @@ pub enum Instr {
     Call { dest: Option<String>, name: String, args: Vec<Operand> },
     Return { src: Option<Operand> },
     BinOp { dest: String, op: String, left: Operand, right: Operand },
+    Label(String),
+    Jump { target: String },
+    JumpIfZero { cond: Operand, target: String },
 }
 
 
@@ -52,6 +55,9 @@ impl fmt::Display for Instr {
                 if let Some(s) = src { write!(f, "return {}", s) } else { write!(f, "return") }
             }
             Instr::BinOp { dest, op, left, right } => write!(f, "{} = {} {} {}", dest, left, op, right),
+            Instr::Label(name) => write!(f, "{}:", name),
+            Instr::Jump { target } => write!(f, "goto {}", target),
+            Instr::JumpIfZero { cond, target } => write!(f, "ifz {} goto {}", cond, target),
         }
     }
 }
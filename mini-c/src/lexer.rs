@@ -1,43 +1,255 @@
 // import the enum from the roken.rs 
-use crate::token::Token;
+use crate::token::{NumberError, Token};
+use crate::ast::Type;
+use std::borrow::Cow;
+
+
+// a position in the original source: 1-based line and tab-aware column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourcePos {
+    pub line: usize,
+    pub col: usize,
+}
+
+
+// default distance between tab stops when computing columns
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+
+
+// where each physical source line starts (byte offsets into the normalized text),
+// so offsets can be turned back into original lines and columns
+#[derive(Debug, Clone)]
+pub struct LineMap {
+    starts: Vec<usize>,
+    tab_width: usize,
+}
+
+
+impl LineMap {
+    // original line and column of the byte at `offset` in the normalized `text`
+    pub fn position(&self, text: &str, offset: usize) -> SourcePos {
+        // the last physical line starting at or before the offset (splices start lines too)
+        let idx = self.starts.partition_point(|&s| s <= offset).saturating_sub(1);
+        let mut col = 1;
+        for c in text.get(self.starts[idx]..offset.min(text.len())).unwrap_or("").chars() {
+            if c == '\t' {
+                col = ((col - 1) / self.tab_width + 1) * self.tab_width + 1;
+            } else {
+                col += 1;
+            }
+        }
+        SourcePos { line: idx + 1, col }
+    }
+}
+
+
+// translation phases 1 and 2: drop a UTF-8 BOM, turn CRLF and lone CR into LF
+// and splice lines ending in a backslash, remembering where physical lines began.
+// the input is borrowed unchanged when none of that is needed
+pub fn normalize(input: &str, tab_width: usize) -> (Cow<'_, str>, LineMap) {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let bytes = input.as_bytes();
+    let tab_width = tab_width.max(1);
+    let mut starts = vec![0];
+
+    let clean = !bytes.contains(&b'\r') && !bytes.windows(2).any(|w| w == b"\\\n");
+    if clean {
+        starts.extend(bytes.iter().enumerate().filter(|(_, &b)| b == b'\n').map(|(i, _)| i + 1));
+        return (Cow::Borrowed(input), LineMap { starts, tab_width });
+    }
+
+    let mut text = String::with_capacity(input.len());
+    let mut iter = input.chars().peekable();
+    while let Some(c) = iter.next() {
+        match c {
+            '\r' | '\n' => {
+                if c == '\r' && iter.peek() == Some(&'\n') {
+                    iter.next();
+                }
+                text.push('\n');
+                starts.push(text.len());
+            }
+            '\\' if matches!(iter.peek(), Some('\n') | Some('\r')) => {
+                // backslash-newline disappears; the next physical line continues this one
+                if iter.next() == Some('\r') && iter.peek() == Some(&'\n') {
+                    iter.next();
+                }
+                starts.push(text.len());
+            }
+            _ => text.push(c),
+        }
+    }
+    (Cow::Owned(text), LineMap { starts, tab_width })
+}
+
+
+// what the lexer skips between tokens; lossless consumers keep it as trivia
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Bom,
+    Whitespace,
+    Newline,      // \n, \r\n or a lone \r
+    LineSplice,   // backslash-newline outside a token
+    LineComment,  // up to, not including, the newline
+    BlockComment, // including an unterminated one running to the end of input
+}
+
 
 // pub makes the Lexer struct accessible from other modules
-pub struct Lexer {
-    // entire source code as a list of characters
-    // track current index in the input
-    input: Vec<char>,
+pub struct Lexer<'a> {
+    // the (normalized) source, borrowed from the caller whenever possible;
+    // track the current byte offset and where the last token started
+    input: Cow<'a, str>,
     position: usize,
+    lines: LineMap,
+    token_start: usize,
 }
 
 
 
-impl Lexer {
+impl<'a> Lexer<'a> {
 
     // constructor for the Lexer
-    pub fn new(input: &str) -> Self {
+    pub fn new(input: &'a str) -> Self {
+        Lexer::with_tab_width(input, DEFAULT_TAB_WIDTH)
+    }
+
+    // constructor with a custom tab width for column numbers
+    pub fn with_tab_width(input: &'a str, tab_width: usize) -> Self {
+        let (input, lines) = normalize(input, tab_width);
+        Lexer {
+            input,
+            position: 0,
+            lines,
+            token_start: 0,
+        }
+    }
+
+    // constructor that keeps the input verbatim (no BOM stripping, newline
+    // normalization or line splicing), so every byte is covered by either a
+    // token or a piece of trivia from `next_trivia`
+    pub fn lossless(input: &'a str) -> Self {
+        let bytes = input.as_bytes();
+        let mut starts = vec![0];
+        for (i, &b) in bytes.iter().enumerate() {
+            if b == b'\n' || (b == b'\r' && bytes.get(i + 1) != Some(&b'\n')) {
+                starts.push(i + 1);
+            }
+        }
         Lexer {
-            input: input.chars().collect(),
+            input: Cow::Borrowed(input),
             position: 0,
+            lines: LineMap { starts, tab_width: DEFAULT_TAB_WIDTH },
+            token_start: 0,
         }
     }
 
+    // consume one piece of trivia at the current offset, if there is one;
+    // its text is available from `token_text` afterwards
+    pub fn next_trivia(&mut self) -> Option<TriviaKind> {
+        self.token_start = self.position;
+        let kind = match self.peek_char()? {
+            '\u{feff}' if self.position == 0 => {
+                self.position += '\u{feff}'.len_utf8();
+                TriviaKind::Bom
+            }
+            '\n' => {
+                self.position += 1;
+                TriviaKind::Newline
+            }
+            '\r' => {
+                self.position += 1;
+                if self.peek_byte() == Some(b'\n') {
+                    self.position += 1;
+                }
+                TriviaKind::Newline
+            }
+            '\\' if matches!(self.byte_at(self.position + 1), Some(b'\n') | Some(b'\r')) => {
+                self.position += 2;
+                if self.byte_at(self.position - 1) == Some(b'\r') && self.peek_byte() == Some(b'\n') {
+                    self.position += 1;
+                }
+                TriviaKind::LineSplice
+            }
+            '/' if self.byte_at(self.position + 1) == Some(b'/') => {
+                self.position = self.input[self.position..]
+                    .find(['\n', '\r'])
+                    .map_or(self.input.len(), |nl| self.position + nl);
+                TriviaKind::LineComment
+            }
+            '/' if self.byte_at(self.position + 1) == Some(b'*') => {
+                self.position = self.input[self.position + 2..]
+                    .find("*/")
+                    .map_or(self.input.len(), |end| self.position + 2 + end + 2);
+                TriviaKind::BlockComment
+            }
+            c if c.is_whitespace() => {
+                while let Some(c) = self.peek_char().filter(|&c| c.is_whitespace() && c != '\n' && c != '\r') {
+                    self.position += c.len_utf8();
+                }
+                TriviaKind::Whitespace
+            }
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    // original line and column where the most recently returned token starts
+    pub fn token_pos(&self) -> SourcePos {
+        self.lines.position(&self.input, self.token_start)
+    }
+
+    // source text of the most recently returned token, without copying
+    pub fn token_text(&self) -> &str {
+        &self.input[self.token_start..self.position]
+    }
+
+    // next token together with its source position
+    pub fn next_token_with_pos(&mut self) -> (Token, SourcePos) {
+        let tok = self.next_token();
+        (tok, self.token_pos())
+    }
+
+    // byte at an absolute offset
+    fn byte_at(&self, pos: usize) -> Option<u8> {
+        self.input.as_bytes().get(pos).copied()
+    }
+
+    // byte at the current offset
+    fn peek_byte(&self) -> Option<u8> {
+        self.byte_at(self.position)
+    }
+
     // returns the character if available else none -> moves based on the index
     fn next_char(&mut self) -> Option<char> {
-        if self.position < self.input.len() {
-            let ch = self.input[self.position];
-            self.position += 1;
-            Some(ch)
-        } else {
-            None
-        }
+        let ch = self.peek_char()?;
+        self.position += ch.len_utf8();
+        Some(ch)
     }
 
 
+    // decodes the character at the current offset (ascii fast path)
     fn peek_char(&self) -> Option<char> {
-        if self.position < self.input.len() {
-            Some(self.input[self.position])
+        match self.peek_byte()? {
+            b if b < 0x80 => Some(b as char),
+            _ => self.input[self.position..].chars().next(),
+        }
+    }
+
+    // consume the next character if it is `c`
+    fn eat_char(&mut self, c: char) -> bool {
+        if self.peek_char() == Some(c) {
+            self.position += c.len_utf8();
+            true
         } else {
-            None
+            false
+        }
+    }
+
+    // advance while the current byte satisfies `pred`
+    fn skip_while(&mut self, pred: fn(u8) -> bool) {
+        while self.peek_byte().is_some_and(pred) {
+            self.position += 1;
         }
     }
 
@@ -46,47 +258,33 @@ impl Lexer {
         while let Some(ch) = self.peek_char() {
             // skip normal whitespace
             if ch.is_whitespace() {
-                self.position += 1;
+                self.position += ch.len_utf8();
                 continue;
             }
 
             // skip single-line comments starting with  //
             if ch == '/' {
                 // lookahead
-                if let Some(next) = self.input.get(self.position + 1) {
-                    if *next == '/' {
+                match self.byte_at(self.position + 1) {
+                    Some(b'/') => {
                         // consume '//' and then all chars until newline
                         self.position += 2;
-                        while let Some(nc) = self.peek_char() {
-                            self.position += 1;
-                            if nc == '\n' {
-                                break;
-                            }
+                        match self.input[self.position..].find('\n') {
+                            Some(nl) => self.position += nl + 1,
+                            None => self.position = self.input.len(),
                         }
                         continue;
-                    } else if *next == '*' {
+                    }
+                    Some(b'*') => {
                         // block comment /* ... */
                         self.position += 2; // consume '/*'
-                        while let Some(_) = self.peek_char() {
-                            // look for closing */
-                            if let Some(c1) = self.peek_char() {
-                                if c1 == '*' {
-                                    // check next
-                                    if let Some(c2) = self.input.get(self.position + 1) {
-                                        if *c2 == '/' {
-                                            // consume '*/'
-                                            self.position += 2;
-                                            break;
-                                        }
-                                    }
-                                }
-                                self.position += 1;
-                            } else {
-                                break;
-                            }
+                        match self.input[self.position..].find("*/") {
+                            Some(end) => self.position += end + 2,
+                            None => self.position = self.input.len(),
                         }
                         continue;
                     }
+                    _ => {}
                 }
             }
 
@@ -97,6 +295,7 @@ impl Lexer {
     // first skip spaces, and then return the next token
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
+        self.token_start = self.position;
 
         let ch = match self.next_char() {
             Some(c) => c,
@@ -112,98 +311,425 @@ impl Lexer {
             ')' => Token::RParen,
             '{' => Token::LBrace,
             '}' => Token::RBrace,
+            '=' if self.eat_char('=') => Token::EqEq,
             '=' => Token::Assign,
             ',' => Token::Comma,
 
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '!' if self.eat_char('=') => Token::NotEq,
+            '!' => Token::Bang,
+            '&' => Token::Amp,
+            '<' if self.eat_char('=') => Token::LessEq,
+            '<' => Token::Less,
+            '>' if self.eat_char('=') => Token::GreaterEq,
+            '>' => Token::Greater,
+
+            // string literal with escapes decoded; it cannot run past the end of the line
             '"' => {
-                let mut string_val = String::new();
-                while let Some(next) = self.peek_char() {
-                    if next == '"' {
-                        self.next_char(); // consume closing quote
-                        break;
+                let mut text = String::new();
+                while let Some(c) = self.peek_char() {
+                    match c {
+                        '"' => {
+                            self.position += 1;
+                            break;
+                        }
+                        '\n' => break,
+                        '\\' => {
+                            self.position += 1;
+                            text.push(self.read_escape());
+                        }
+                        _ => {
+                            self.position += c.len_utf8();
+                            text.push(c);
+                        }
                     }
-                    string_val.push(self.next_char().unwrap());
                 }
-                Token::String(string_val)
+                Token::String(text)
             }
-            c if c.is_ascii_alphabetic() => {
-                let mut ident = c.to_string();
-                while let Some(next) = self.peek_char() {
-                    if next.is_ascii_alphanumeric() {
-                        ident.push(self.next_char().unwrap());
-                    } else {
-                        break;
+            // identifiers and keywords: C11 rules, letters may be any unicode letter
+            c if is_ident_start(c) => self.lex_ident(),
+            '\\' if matches!(self.peek_byte(), Some(b'u') | Some(b'U')) => self.lex_ident(),
+
+            // numbers are taken now (integers and floats, any base, with suffixes)
+            c if c.is_ascii_digit() => self.lex_number(),
+
+            // a float may also start with the decimal point, like .5
+            '.' if self.peek_byte().is_some_and(|n| n.is_ascii_digit()) => self.lex_number(),
+
+            // char literal like 'a' or '\n'
+            '\'' => {
+                let ch = match self.peek_char() {
+                    Some('\\') => {
+                        self.position += 1;
+                        self.read_escape()
+                    }
+                    Some(c) if c != '\n' => {
+                        self.position += c.len_utf8();
+                        c
                     }
+                    _ => '\0',
+                };
+                // consume closing quote if present
+                if self.peek_byte() == Some(b'\'') {
+                    self.position += 1;
                 }
-                match ident.as_str() {
-                    "int" => Token::Int,
-                    "float" => Token::Float,
-                    "char" => Token::Char,
-                    "void" => Token::Void,
-                    "return" => Token::Return,
-                    _ => Token::Ident(ident),
-                }
-            }
-
-            // numbers are taken now (integers and floats)
-            c if c.is_ascii_digit() => {
-                let mut number = c.to_string();
-                let mut is_float = false;
-                while let Some(next) = self.peek_char() {
-                    if next.is_ascii_digit() {
-                        number.push(self.next_char().unwrap());
-                    } else if next == '.' && !is_float {
-                        // float literal
-                        is_float = true;
-                        number.push(self.next_char().unwrap());
-                        // collect fractional part
-                        while let Some(frac) = self.peek_char() {
-                            if frac.is_ascii_digit() {
-                                number.push(self.next_char().unwrap());
-                            } else {
-                                break;
-                            }
-                        }
-                        break;
-                    } else {
-                        break;
+                Token::CharLiteral(ch)
+            }
+
+            // anything else is reported rather than ending the token stream early
+            other => Token::Unknown(other),
+        }
+    }
+
+    // identifier from the token start, then check it against the keyword table;
+    // the text is only decoded into a new buffer when it contains universal character names
+    fn lex_ident(&mut self) -> Token {
+        let start = self.token_start;
+        self.position = start;
+        let mut decoded: Option<String> = None;
+        loop {
+            match self.peek_char() {
+                Some(c) if is_ident_continue(c) => {
+                    if let Some(d) = decoded.as_mut() {
+                        d.push(c);
                     }
+                    self.position += c.len_utf8();
                 }
-                if is_float {
-                    Token::FloatNumber(number.parse::<f64>().unwrap())
-                } else {
-                    Token::Number(number.parse::<i64>().unwrap())
+                Some('\\') if matches!(self.byte_at(self.position + 1), Some(b'u') | Some(b'U')) => {
+                    // universal character name inside an identifier
+                    let before = self.position;
+                    self.position += 1;
+                    match self.read_ucn() {
+                        Some(c) => decoded.get_or_insert_with(|| self.input[start..before].to_string()).push(c),
+                        None => {
+                            self.position = before;
+                            break;
+                        }
+                    }
                 }
+                _ => break,
             }
+        }
+        if self.position == start {
+            // a backslash that does not begin a valid universal character name
+            self.position += 1;
+            return Token::Unknown('\\');
+        }
+        let ident = decoded.as_deref().unwrap_or(&self.input[start..self.position]);
+        Token::keyword(ident).unwrap_or_else(|| Token::Ident(ident.to_string()))
+    }
 
-            // char literal like 'a'
-            '\'' => {
-                // read char content; support simple escapes like '\n' or '\''
-                let ch = if let Some(next) = self.next_char() {
-                    if next == '\\' {
-                        // escaped char
-                        if let Some(escaped) = self.next_char() {
-                            escaped
-                        } else {
-                            '\0'
+    // decode an escape sequence in a char or string literal; positioned after the backslash
+    fn read_escape(&mut self) -> char {
+        let Some(c) = self.peek_char() else {
+            return '\\';
+        };
+        match c {
+            'u' | 'U' => {
+                let before = self.position;
+                self.read_ucn().unwrap_or_else(|| {
+                    self.position = before + 1;
+                    c
+                })
+            }
+            '0'..='7' => {
+                // up to three octal digits
+                let mut code = 0;
+                for _ in 0..3 {
+                    match self.peek_byte() {
+                        Some(d @ b'0'..=b'7') => {
+                            code = code * 8 + (d - b'0') as u32;
+                            self.position += 1;
                         }
-                    } else {
-                        next
+                        _ => break,
                     }
-                } else {
-                    '\0'
-                };
-                // consume closing quote if present
-                if let Some(peek) = self.peek_char() {
-                    if peek == '\'' {
-                        self.next_char();
+                }
+                char::from_u32(code).unwrap_or('\0')
+            }
+            'x' if self.byte_at(self.position + 1).is_some_and(|d| d.is_ascii_hexdigit()) => {
+                self.position += 1;
+                let mut code = 0u32;
+                while let Some(d) = self.peek_byte().and_then(|d| (d as char).to_digit(16)) {
+                    code = code.saturating_mul(16).saturating_add(d);
+                    self.position += 1;
+                }
+                char::from_u32(code).unwrap_or('\u{fffd}')
+            }
+            _ => {
+                self.position += c.len_utf8();
+                match c {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'a' => '\x07',
+                    'b' => '\x08',
+                    'f' => '\x0c',
+                    'v' => '\x0b',
+                    // \\ \' \" \? and unknown escapes stand for the character itself
+                    other => other,
+                }
+            }
+        }
+    }
+
+    // read \uXXXX or \UXXXXXXXX after the backslash; positioned on the u/U
+    fn read_ucn(&mut self) -> Option<char> {
+        let len = if self.next_char()? == 'u' { 4 } else { 8 };
+        let mut code = 0u32;
+        for _ in 0..len {
+            let digit = (self.peek_byte()? as char).to_digit(16)?;
+            code = code * 16 + digit;
+            self.position += 1;
+        }
+        char::from_u32(code)
+    }
+
+    // consume an optional exponent (e/E for decimal, p/P for hex) with its sign and digits
+    fn skip_exponent(&mut self, markers: [u8; 2]) -> bool {
+        if !self.peek_byte().is_some_and(|c| markers.contains(&c)) {
+            return false;
+        }
+        // only treat it as an exponent when digits follow, otherwise leave it alone
+        let mut look = self.position + 1;
+        if matches!(self.byte_at(look), Some(b'+') | Some(b'-')) {
+            look += 1;
+        }
+        if !self.byte_at(look).is_some_and(|c| c.is_ascii_digit()) {
+            return false;
+        }
+        self.position = look;
+        self.skip_while(|c| c.is_ascii_digit());
+        true
+    }
+
+    // numeric literal: decimal, octal (0 prefix), hex (0x), binary (0b),
+    // integer suffixes u/l/ll in any order, exponents and hex floats
+    fn lex_number(&mut self) -> Token {
+        let start = self.token_start;
+        let first = self.input.as_bytes()[start];
+
+        // hex and binary prefixes
+        if first == b'0' {
+            match self.peek_byte() {
+                Some(b'x') | Some(b'X') => {
+                    self.position += 1;
+                    let int_start = self.position;
+                    self.skip_while(|c| c.is_ascii_hexdigit());
+                    let int_end = self.position;
+                    let mut frac = int_end..int_end;
+                    let mut is_float = false;
+                    if self.peek_byte() == Some(b'.') {
+                        is_float = true;
+                        self.position += 1;
+                        let frac_start = self.position;
+                        self.skip_while(|c| c.is_ascii_hexdigit());
+                        frac = frac_start..self.position;
+                    }
+                    let exp_start = self.position;
+                    let has_exponent = self.skip_exponent([b'p', b'P']);
+                    if is_float && !has_exponent {
+                        return self.finish_number(self.position, 16, Token::InvalidNumber(NumberError::NoHexExponent));
                     }
+                    if has_exponent {
+                        let value = hex_float_value(&self.input[int_start..int_end], &self.input[frac], &self.input[exp_start..self.position]);
+                        return self.float_token(value, 16);
+                    }
+                    return self.int_token(int_start, int_end, 16);
                 }
-                Token::CharLiteral(ch)
+                Some(b'b') | Some(b'B') if matches!(self.byte_at(self.position + 1), Some(b'0') | Some(b'1')) => {
+                    self.position += 1;
+                    let digits_start = self.position;
+                    self.skip_while(|c| c == b'0' || c == b'1');
+                    return self.int_token(digits_start, self.position, 2);
+                }
+                _ => {}
             }
+        }
+
+        // decimal (or octal) digits, then an optional fraction and exponent
+        let mut is_fraction = first == b'.';
+        if !is_fraction {
+            self.skip_while(|c| c.is_ascii_digit());
+            if self.peek_byte() == Some(b'.') {
+                self.position += 1;
+                is_fraction = true;
+            }
+        }
+        if is_fraction {
+            self.skip_while(|c| c.is_ascii_digit());
+        }
+        let has_exponent = self.skip_exponent([b'e', b'E']);
 
-            // end it , this was added to handle the end of input extraction
-            _ => Token::EOF,
+        if is_fraction || has_exponent {
+            let value = self.input[start..self.position].parse::<f64>().unwrap_or(0.0);
+            return self.float_token(value, 10);
+        }
+
+        if self.position - start > 1 && first == b'0' {
+            self.int_token(start + 1, self.position, 8)
+        } else {
+            self.int_token(start, self.position, 10)
         }
     }
+
+    // build an integer token from the digits in start..end, reading the suffix and choosing the literal type
+    fn int_token(&mut self, start: usize, end: usize, radix: u32) -> Token {
+        let value = integer_value(&self.input.as_bytes()[start..end], radix);
+        let suffix = self.position;
+
+        // suffix: u and l/ll may come in either order, ll must keep its case
+        let mut unsigned = false;
+        let mut longs = 0;
+        loop {
+            match self.peek_byte() {
+                Some(b'u') | Some(b'U') if !unsigned => {
+                    unsigned = true;
+                    self.position += 1;
+                }
+                Some(l @ (b'l' | b'L')) if longs == 0 => {
+                    self.position += 1;
+                    longs = 1;
+                    if self.peek_byte() == Some(l) {
+                        self.position += 1;
+                        longs = 2;
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let token = match value {
+            Ok(value) => Token::Number(value as i64, integer_literal_type(value, radix == 10, unsigned, longs)),
+            Err(e) => Token::InvalidNumber(e),
+        };
+        self.finish_number(suffix, radix, token)
+    }
+
+    // build a floating token, reading an optional f/F or l/L suffix
+    fn float_token(&mut self, value: f64, radix: u32) -> Token {
+        let suffix = self.position;
+        let token = match self.peek_byte() {
+            Some(b'f') | Some(b'F') => {
+                self.position += 1;
+                Token::FloatNumber(value as f32 as f64, Type::Float)
+            }
+            Some(b'l') | Some(b'L') => {
+                self.position += 1;
+                Token::FloatNumber(value, Type::LongDouble)
+            }
+            _ => Token::FloatNumber(value, Type::Double),
+        };
+        self.finish_number(suffix, radix, token)
+    }
+
+    // a literal must end where its preprocessing number (6.4.8) does: anything
+    // left after the suffix starting at `suffix` is consumed with it and makes
+    // the number invalid
+    fn finish_number(&mut self, suffix: usize, radix: u32, token: Token) -> Token {
+        let end = self.position;
+        while let Some(c) = self.peek_char() {
+            let signed = matches!(c, '+' | '-') && matches!(self.byte_at(self.position - 1), Some(b'e' | b'E' | b'p' | b'P'));
+            if !(signed || c == '.' || is_ident_continue(c)) {
+                break;
+            }
+            self.position += c.len_utf8();
+        }
+        if self.position == end || matches!(token, Token::InvalidNumber(_)) {
+            return token;
+        }
+        let rest = &self.input[suffix..self.position];
+        let exponent: &[char] = if radix == 16 { &['p', 'P'] } else { &['e', 'E'] };
+        let error = match rest.chars().next() {
+            Some(c) if exponent.contains(&c) => NumberError::NoExponentDigits,
+            Some(c) if radix == 2 && c.is_ascii_digit() => NumberError::InvalidDigit(c, 2),
+            _ => NumberError::InvalidSuffix(rest.to_string()),
+        };
+        Token::InvalidNumber(error)
+    }
+}
+
+
+// a lexer is a stream of tokens that ends before EOF
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        match self.next_token() {
+            Token::EOF => None,
+            tok => Some(tok),
+        }
+    }
+}
+
+
+// value of the digits of an integer constant; a digit outside the radix is
+// reported before a value that overflows
+fn integer_value(digits: &[u8], radix: u32) -> Result<u64, NumberError> {
+    if digits.is_empty() {
+        return Err(NumberError::NoDigits(radix));
+    }
+    if let Some(&d) = digits.iter().find(|&&d| !(d as char).is_digit(radix)) {
+        return Err(NumberError::InvalidDigit(d as char, radix));
+    }
+    digits
+        .iter()
+        .try_fold(0u64, |value, &d| value.checked_mul(radix as u64)?.checked_add((d as char).to_digit(radix)? as u64))
+        .ok_or(NumberError::TooLarge)
+}
+
+
+// pick the first type in the C11 candidate list (6.4.4.1) that can hold the value,
+// with a 32-bit int and 64-bit long / long long
+fn integer_literal_type(value: u64, decimal: bool, unsigned: bool, longs: u8) -> Type {
+    let candidates: &[Type] = match (unsigned, longs, decimal) {
+        (false, 0, true) => &[Type::Int, Type::Long, Type::LongLong],
+        (false, 0, false) => &[Type::Int, Type::UnsignedInt, Type::Long, Type::UnsignedLong, Type::LongLong, Type::UnsignedLongLong],
+        (true, 0, _) => &[Type::UnsignedInt, Type::UnsignedLong, Type::UnsignedLongLong],
+        (false, 1, true) => &[Type::Long, Type::LongLong],
+        (false, 1, false) => &[Type::Long, Type::UnsignedLong, Type::LongLong, Type::UnsignedLongLong],
+        (true, 1, _) => &[Type::UnsignedLong, Type::UnsignedLongLong],
+        (false, _, true) => &[Type::LongLong],
+        (false, _, false) => &[Type::LongLong, Type::UnsignedLongLong],
+        (true, _, _) => &[Type::UnsignedLongLong],
+    };
+    for ty in candidates {
+        let max = match ty {
+            Type::Int => i32::MAX as u64,
+            Type::UnsignedInt => u32::MAX as u64,
+            Type::Long | Type::LongLong => i64::MAX as u64,
+            _ => u64::MAX,
+        };
+        if value <= max {
+            return ty.clone();
+        }
+    }
+    // too large for any signed candidate: treat it as unsigned like gcc does
+    Type::UnsignedLongLong
+}
+
+
+// value of a hex float from its integer digits, fraction digits and "p<exp>" text
+fn hex_float_value(int_digits: &str, frac_digits: &str, exp: &str) -> f64 {
+    let mut mantissa = 0f64;
+    for d in int_digits.chars().chain(frac_digits.chars()) {
+        mantissa = mantissa * 16.0 + d.to_digit(16).unwrap_or(0) as f64;
+    }
+    let exponent = exp.get(1..).and_then(|e| e.parse::<i32>().ok()).unwrap_or(0);
+    mantissa * 2f64.powi(exponent - 4 * frac_digits.len() as i32)
+}
+
+
+
+// identifiers start with an underscore or a letter (unicode letters included)
+fn is_ident_start(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+
+// and continue with letters, digits and underscores
+fn is_ident_continue(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
 }
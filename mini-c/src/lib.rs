@@ -2,9 +2,23 @@
 // modules for the mini C compilers
 
 pub mod lexer;
+pub mod preprocessor;
 pub mod token;
 pub mod parser;
+pub mod printer;
+pub mod cst;
 pub mod ast;
 pub mod codegen;
+pub mod consteval;
 pub mod semantic;
+pub mod standard;
+pub mod flow;
+pub mod format;
+pub mod prelude;
+pub mod warnings;
 pub mod symbol;
+pub mod resolve;
+pub mod ir;
+pub mod lower;
+pub mod codegen_tac;
+pub mod codegen_x64_windows;
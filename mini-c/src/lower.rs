@@ -1,86 +1,179 @@
 use crate::ast::*;
 use crate::ir::{FunctionIR, Instr, Operand};
+use crate::consteval::ConstValue;
+use crate::flow;
+use crate::semantic::SideTables;
 
 // Lower AST to IR
 // the below LowerState struct helps generate unique temporary names
-struct LowerState {
+// and collects the instructions of the function being lowered
+struct LowerState<'a> {
+    tables: &'a SideTables,
     tmp: usize,
+    labels: usize,
+    instrs: Vec<Instr>,
+    // operands of the expressions lowered so far, innermost last
+    operands: Vec<Operand>,
 }
 
 // Lower an expression to IR, appending instructions to `instrs` and returning an Operand
-impl LowerState {
-    fn new() -> Self { LowerState { tmp: 0 } }
+impl<'a> LowerState<'a> {
+    fn new(tables: &'a SideTables) -> Self { LowerState { tables, tmp: 0, labels: 0, instrs: Vec::new(), operands: Vec::new() } }
     fn gen_tmp(&mut self) -> String { let id = self.tmp; self.tmp += 1; format!("t{}", id) }
-}
-
+    fn gen_label(&mut self) -> String { let id = self.labels; self.labels += 1; format!("L{}", id) }
+    fn pop(&mut self) -> Operand { self.operands.pop().expect("lowered expression left no operand") }
+
+    // IR name of the local a name was resolved to; shadowed names get distinct ones
+    fn local_name(&self, node: NodeId, name: &str) -> String {
+        match self.tables.resolution.binding(node) {
+            Some(id) => self.tables.resolution.unique_name(id),
+            None => name.to_string(),
+        }
+    }
 
-// Lower an expression recursively
-fn lower_expr(expr: &Expr, state: &mut LowerState, instrs: &mut Vec<Instr>) -> Operand {
-    match expr {
-        Expr::Number(n) => Operand::ConstInt(*n),
-        Expr::FloatNumber(f) => Operand::ConstFloat(*f),
-        Expr::CharLiteral(c) => Operand::ConstInt(*c as i64),
-        Expr::Ident(name) => Operand::Local(name.clone()),
-        Expr::Unary { op, expr } => {
+    // lower an expression and take its result
+    fn lower_expr(&mut self, expr: &Expr) -> Operand {
+        self.visit_expr(expr);
+        self.pop()
+    }
+}
 
-            // lower sub-expression
-            let o = lower_expr(expr, state, instrs);
-            let dest = state.gen_tmp();
-            match op {
 
-                // Neg and Not are implemented as binary ops with 0 as right operand
-                crate::ast::UnaryOp::Neg => {
-                    instrs.push(Instr::BinOp { dest: dest.clone(), op: "neg".to_string(), left: o.clone(), right: Operand::ConstInt(0) });
+impl Visitor for LowerState<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::VarDecl { value: None, .. } | StmtKind::StaticAssert { .. } | StmtKind::Empty => {}
+            StmtKind::VarDecl { name, value: Some(value), .. } => {
+                let v = self.lower_expr(value);
+                let name = match self.tables.resolution.declared_by(stmt.id) {
+                    Some(id) => self.tables.resolution.unique_name(id),
+                    None => name.clone(),
+                };
+                self.instrs.push(Instr::StoreLocal { name, src: v });
+            }
+            StmtKind::ExprStmt(e) => {
+                self.lower_expr(e);
+            }
+            StmtKind::Return(e) => {
+                let src = e.as_ref().map(|e| self.lower_expr(e));
+                self.instrs.push(Instr::Return { src });
+            }
+            StmtKind::Block(_) => walk_stmt(self, stmt),
+
+            // the branch not taken is jumped over
+            StmtKind::If { cond, then, otherwise } => {
+                let cond = self.lower_expr(cond);
+                let else_label = self.gen_label();
+                self.instrs.push(Instr::JumpIfZero { cond, target: else_label.clone() });
+                self.visit_stmt(then);
+                match otherwise {
+                    Some(otherwise) => {
+                        let end = self.gen_label();
+                        self.instrs.push(Instr::Jump { target: end.clone() });
+                        self.instrs.push(Instr::Label(else_label));
+                        self.visit_stmt(otherwise);
+                        self.instrs.push(Instr::Label(end));
+                    }
+                    None => self.instrs.push(Instr::Label(else_label)),
                 }
+            }
 
-                // Not is implemented as binary ops with 0 as right operand
-                crate::ast::UnaryOp::Not => {
-                    instrs.push(Instr::BinOp { dest: dest.clone(), op: "not".to_string(), left: o.clone(), right: Operand::ConstInt(0) });
-                }
+            // the condition is tested before every iteration
+            StmtKind::While { cond, body } => {
+                let (top, end) = (self.gen_label(), self.gen_label());
+                self.instrs.push(Instr::Label(top.clone()));
+                let cond = self.lower_expr(cond);
+                self.instrs.push(Instr::JumpIfZero { cond, target: end.clone() });
+                self.visit_stmt(body);
+                self.instrs.push(Instr::Jump { target: top });
+                self.instrs.push(Instr::Label(end));
             }
-            Operand::Temp(dest)
         }
+    }
 
 
-        // Lower a binary expression
-        Expr::Binary { op, left, right } => {
-            let l = lower_expr(left, state, instrs);
-            let r = lower_expr(right, state, instrs);
-            let dest = state.gen_tmp();
-            let opname = match op {
-                crate::ast::BinaryOp::Add => "+",
-                crate::ast::BinaryOp::Sub => "-",
-                crate::ast::BinaryOp::Mul => "*",
-                crate::ast::BinaryOp::Div => "/",
-            };
-
-            // emit binary operation instruction
-            instrs.push(Instr::BinOp { dest: dest.clone(), op: opname.to_string(), left: l, right: r });
-            Operand::Temp(dest)
+    // Lower an expression: the walk lowers the operands first, leaving their
+    // results on the operand stack, then this node's result is pushed.
+    // constant expressions, folded by semantic analysis, are emitted without
+    // lowering their operands; those without a value (overflow, division by
+    // zero) are left to run time
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Some(value) = self.tables.const_of(expr) {
+            self.operands.push(match value {
+                ConstValue::Int(i) => Operand::ConstInt(i),
+                ConstValue::Float(f) => Operand::ConstFloat(f),
+            });
+            return;
         }
+        walk_expr(self, expr);
+        let result = match &expr.kind {
+            ExprKind::Number(n, _) => Operand::ConstInt(*n),
+            ExprKind::FloatNumber(f, _) => Operand::ConstFloat(*f),
+            ExprKind::CharLiteral(c) => Operand::ConstInt(*c as u8 as i8 as i64),
+            ExprKind::Ident(name) => Operand::Local(self.local_name(expr.id, name)),
+            ExprKind::Unary { op, .. } => {
+                let o = self.pop();
+                let dest = self.gen_tmp();
+                // Neg and Not are implemented as binary ops with 0 as right operand
+                let opname = match op {
+                    UnaryOp::Neg => "neg",
+                    UnaryOp::Not => "not",
+                    UnaryOp::AddrOf => "addr",
+                };
+                self.instrs.push(Instr::BinOp { dest: dest.clone(), op: opname.to_string(), left: o, right: Operand::ConstInt(0) });
+                Operand::Temp(dest)
+            }
 
 
-        // Assignment: evaluate right-hand side, store in local variable
-        Expr::Assign { name, value } => {
-            let v = lower_expr(value, state, instrs);
-            instrs.push(Instr::StoreLocal { name: name.clone(), src: v.clone() });
-            Operand::Local(name.clone())
-        }
+            // Lower a binary expression
+            ExprKind::Binary { op, .. } => {
+                let r = self.pop();
+                let l = self.pop();
+                let dest = self.gen_tmp();
+                let opname = match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Sub => "-",
+                    BinaryOp::Mul => "*",
+                    BinaryOp::Div => "/",
+                    BinaryOp::Lt => "<",
+                    BinaryOp::Gt => ">",
+                    BinaryOp::Le => "<=",
+                    BinaryOp::Ge => ">=",
+                    BinaryOp::Eq => "==",
+                    BinaryOp::Ne => "!=",
+                };
+
+                // emit binary operation instruction
+                self.instrs.push(Instr::BinOp { dest: dest.clone(), op: opname.to_string(), left: l, right: r });
+                Operand::Temp(dest)
+            }
 
 
-        // Function call: evaluate args, emit call instruction
-        Expr::Call { name, args } => {
-            let mut op_args = Vec::new();
-            for a in args {
-                op_args.push(lower_expr(a, state, instrs));
+            // Assignment: evaluate right-hand side, store in the local variable
+            // named by the target (semantic analysis only lets variables through)
+            ExprKind::Assign { .. } => {
+                let v = self.pop();
+                let name = match self.pop() {
+                    Operand::Local(name) => name,
+                    other => panic!("assignment to a non-variable {:?} reached lowering", other),
+                };
+                self.instrs.push(Instr::StoreLocal { name: name.clone(), src: v });
+                Operand::Local(name)
             }
-            let dest = state.gen_tmp();
-            instrs.push(Instr::Call { dest: Some(dest.clone()), name: name.clone(), args: op_args });
-            Operand::Temp(dest)
-        }
 
-        // String literals are not directly representable as operands; handled in codegen
-        Expr::StringLiteral(s) => Operand::ConstString(s.clone()),
+
+            // Function call: evaluate args, emit call instruction
+            ExprKind::Call { name, args } => {
+                let op_args = self.operands.split_off(self.operands.len() - args.len());
+                let dest = self.gen_tmp();
+                self.instrs.push(Instr::Call { dest: Some(dest.clone()), name: name.clone(), args: op_args });
+                Operand::Temp(dest)
+            }
+
+            // String literals are not directly representable as operands; handled in codegen
+            ExprKind::StringLiteral(s) => Operand::ConstString(s.clone()),
+        };
+        self.operands.push(result);
     }
 }
 
@@ -88,31 +181,21 @@ fn lower_expr(expr: &Expr, state: &mut LowerState, instrs: &mut Vec<Instr>) -> O
 
 
 
-// Lower a whole program
-pub fn lower_program(prog: &crate::ast::Program) -> Vec<FunctionIR> {
+// Lower a whole program, using the side tables from semantic analysis
+pub fn lower_program(prog: &crate::ast::Program, tables: &SideTables) -> Vec<FunctionIR> {
     let mut res = Vec::new();
     for func in &prog.functions {
-        let mut state = LowerState::new();
-        let mut instrs: Vec<Instr> = Vec::new();
+        let mut state = LowerState::new(tables);
         // params are locals; no explicit instructions needed
-        for stmt in &func.body.stmts {
-            match stmt {
-                crate::ast::Stmt::VarDecl { name, value, .. } => {
-                    let v = lower_expr(value, &mut state, &mut instrs);
-                    instrs.push(Instr::StoreLocal { name: name.clone(), src: v });
-                }
-                crate::ast::Stmt::ExprStmt(e) => {
-                    lower_expr(e, &mut state, &mut instrs);
-                }
-                crate::ast::Stmt::Return(e) => {
-                    let v = lower_expr(e, &mut state, &mut instrs);
-                    instrs.push(Instr::Return { src: Some(v) });
-                }
-            }
+        state.visit_function(func);
+        // reaching the closing brace of main returns 0
+        if func.name == "main" && flow::analyze_function(func).falls_off_end() {
+            state.instrs.push(Instr::Return { src: Some(Operand::ConstInt(0)) });
         }
 
         // create FunctionIR
-        let fir = FunctionIR { name: func.name.clone(), params: func.params.iter().map(|(_, n)| n.clone()).collect(), instrs };
+        let params = tables.resolution.params_of(func.id).iter().map(|&id| tables.resolution.unique_name(id)).collect();
+        let fir = FunctionIR { name: func.name.clone(), params, instrs: state.instrs };
         res.push(fir);
     }
     res
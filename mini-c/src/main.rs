@@ -1,24 +1,19 @@
-// import other files as modules here
-mod token;
-mod lexer;
-mod parser;
-mod ast;
-mod semantic;
-mod symbol;
-mod codegen;
-mod ir;
-mod lower;
-mod codegen_tac;
-mod codegen_x64_windows;
+// the compiler driver; all passes live in the mini_c library crate
 
 // imports as in python
 // 1. access CLI
-// 2. access file system
-// 3, 4. imports structs from respective files
+// 2. imports the passes from the library
+use std::collections::HashMap;
 use std::env;
 use std::fs;
-use lexer::Lexer;
-use parser::Parser;
+use mini_c::{codegen, codegen_tac, codegen_x64_windows, lower, semantic};
+use mini_c::codegen::RunConfig;
+use mini_c::parser::Parser;
+use mini_c::preprocessor::Preprocessor;
+use mini_c::semantic::AnalysisOptions;
+use mini_c::standard::{self, Standard};
+use mini_c::token::Token;
+use mini_c::warnings::{allowed_lines, WarningConfig};
 
 
 fn main() {
@@ -26,45 +21,212 @@ fn main() {
     // iterates over the CLI and stores it as a array of strings
     let args: Vec<String> = env::args().collect();
 
-    // if the command does not have a File name
-    if args.len() < 2 {
-        eprintln!("Usage: mini-c <filename>");
-        return;
+    // -D/-U/-I configure the preprocessor (in command-line order), the rest is
+    // the file name; arguments after `--` are passed to the interpreted program
+    let mut pp = Preprocessor::new();
+    let mut warning_config = WarningConfig::new();
+    let mut language = Standard::default();
+    let mut stdout = std::io::stdout();
+    let mut run_config = RunConfig { stdout: Some(&mut stdout), ..Default::default() };
+    let mut filename: Option<String> = None;
+    let mut program_args: Vec<String> = Vec::new();
+    let mut i = 1;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            program_args = args[i + 1..].to_vec();
+            break;
+        }
+        if let Some(width) = arg.strip_prefix("-ftabstop=") {
+            match width.parse::<usize>() {
+                Ok(w) if w > 0 => pp.set_tab_width(w),
+                _ => {
+                    eprintln!("Invalid tab width: {}", width);
+                    std::process::exit(1);
+                }
+            }
+            i += 1;
+            continue;
+        }
+        // signed integer overflow stops the interpreted program
+        if arg == "-ftrapv" {
+            run_config.trap_overflow = true;
+            i += 1;
+            continue;
+        }
+        // --std=c89|c90|ansi|c99|c11
+        if let Some(name) = arg.strip_prefix("--std=") {
+            match Standard::from_name(name) {
+                Some(s) => {
+                    language = s;
+                    pp.set_standard(s);
+                }
+                None => {
+                    eprintln!("Unknown language standard: {}", name);
+                    std::process::exit(1);
+                }
+            }
+            i += 1;
+            continue;
+        }
+        // -Wall, -Wextra, -W[no-]<name>, -Werror[=<name>]
+        if arg.starts_with("-W") {
+            if let Err(e) = warning_config.apply_flag(arg) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            i += 1;
+            continue;
+        }
+        let flag = ["-D", "-U", "-I"].into_iter().find(|f| arg.starts_with(f));
+        match flag {
+            Some(flag) => {
+                // the value may be attached (-DX=1) or the next argument (-D X=1)
+                let value = if arg.len() > 2 {
+                    arg[2..].to_string()
+                } else {
+                    i += 1;
+                    match args.get(i) {
+                        Some(v) => v.clone(),
+                        None => {
+                            eprintln!("Missing argument after {}", flag);
+                            std::process::exit(1);
+                        }
+                    }
+                };
+                match flag {
+                    "-D" => pp.define(&value),
+                    "-U" => pp.undefine(&value),
+                    _ => pp.add_include_path(value),
+                }
+            }
+            None => filename = Some(arg.clone()),
+        }
+        i += 1;
     }
 
-    
-    let filename = &args[1];
-    let input = fs::read_to_string(filename).expect("Couldnt read the file");
+    // if the command does not have a File name
+    let filename = match filename {
+        Some(f) => f,
+        None => {
+            eprintln!("Usage: mini-c [-D name[=value]] [-U name] [-I dir] [-ftabstop=N] [-ftrapv] [--std=c89|c99|c11] [-Wall] [-Wextra] [-W[no-]<warning>] [-Werror[=<warning>]] <filename> [-- <program arguments>]");
+            return;
+        }
+    };
 
-    // create instances of structures
-    let mut lexer = Lexer::new(&input);
-    let mut tokens = Vec::new();
+    // run the preprocessor first; the lexer sees the expanded text
+    let preprocessed = match pp.preprocess_file(&filename) {
+        Ok(p) => p,
+        Err(errs) => {
+            for e in errs {
+                eprintln!("{}", e);
+            }
+            std::process::exit(1);
+        }
+    };
+    let tokens = preprocessed.tokens_with_pos();
 
-    // extract tokens from the input until u get a EOF
-    loop {
-        let tok = lexer.next_token();
-        if tok == token::Token::EOF {
-            break;
+    // integer literals the lexer could not give a value stop compilation
+    let mut invalid = false;
+    for (tok, pos) in &tokens {
+        if let Token::InvalidNumber(e) = tok {
+            let prefix = match preprocessed.location(pos.line) {
+                Some(loc) => format!("{}:{}: ", loc.file, loc.line),
+                None => String::new(),
+            };
+            eprintln!("{}error: {}", prefix, e);
+            invalid = true;
         }
-        tokens.push(tok);
+    }
+    if invalid {
+        std::process::exit(1);
     }
 
     
-    // create a parse and call the AST
-    let mut parser = Parser::new(tokens);
+    // create a parse and call the AST; node positions locate warnings
+    let mut parser = Parser::with_positions(tokens);
     let ast = parser.parse_program();
 
-    // Run semantic analysis
-    if let Err(errs) = semantic::analyze(&ast) {
-        eprintln!("Semantic errors found:");
-        for e in errs {
-            eprintln!("{}", e);
+    // statements the parser could not read stop compilation
+    for e in parser.errors() {
+        let prefix = match e.pos.and_then(|pos| preprocessed.location(pos.line)) {
+            Some(loc) => format!("{}:{}: ", loc.file, loc.line),
+            None => String::new(),
+        };
+        eprintln!("{}error: {}", prefix, e);
+    }
+    if !parser.errors().is_empty() {
+        std::process::exit(1);
+    }
+
+    // constructs the selected standard does not have stop compilation
+    let violations = standard::violations(parser.features(), language);
+    for u in &violations {
+        let prefix = match u.pos.and_then(|pos| preprocessed.location(pos.line)) {
+            Some(loc) => format!("{}:{}: ", loc.file, loc.line),
+            None => String::new(),
+        };
+        eprintln!("{}error: {} (--std={})", prefix, u.feature.diagnostic(), language.flag());
+    }
+    if !violations.is_empty() {
+        std::process::exit(1);
+    }
+
+    // Run semantic analysis of an executable, with the library functions of the
+    // included headers declared; its side tables feed lowering and the interpreter
+    let options = AnalysisOptions { headers: preprocessed.headers.clone(), executable: true, standard: language };
+    let tables = match semantic::analyze_with(&ast, &options) {
+        Ok(tables) => tables,
+        Err(errs) => {
+            eprintln!("Semantic errors found:");
+            for e in errs {
+                eprintln!("{}", e);
+            }
+            std::process::exit(1);
+        }
+    };
+
+    // report the enabled warnings not allowed by a comment on their line, in
+    // source order; the ones -Werror turned into errors stop compilation
+    let mut allowed: HashMap<String, HashMap<usize, Vec<String>>> = HashMap::new();
+    let mut warning_errors = 0;
+    let mut found: Vec<_> = tables.warnings.iter().map(|w| (parser.positions().get(&w.node()).copied(), w)).collect();
+    found.sort_by_key(|(pos, _)| pos.map(|p| (p.line, p.col)));
+    for (pos, w) in found {
+        let name = w.name();
+        if !warning_config.is_enabled(name) {
+            continue;
         }
+        let loc = pos.and_then(|pos| preprocessed.location(pos.line));
+        let prefix = match loc {
+            Some(loc) => {
+                let lines = allowed
+                    .entry(loc.file.clone())
+                    .or_insert_with(|| fs::read_to_string(&loc.file).map(|src| allowed_lines(&src)).unwrap_or_default());
+                if lines.get(&loc.line).is_some_and(|names| names.iter().any(|n| n == name)) {
+                    continue;
+                }
+                format!("{}:{}: ", loc.file, loc.line)
+            }
+            None => String::new(),
+        };
+        if warning_config.is_error(name) {
+            warning_errors += 1;
+            eprintln!("{}error: {} [-Werror={}]", prefix, w, name);
+        } else {
+            eprintln!("{}warning: {} [-W{}]", prefix, w, name);
+        }
+        let previous = w.previous().and_then(|node| parser.positions().get(&node)).and_then(|pos| preprocessed.location(pos.line));
+        if let Some(loc) = previous {
+            eprintln!("{}:{}: note: the shadowed declaration is here", loc.file, loc.line);
+        }
+    }
+    if warning_errors > 0 {
         std::process::exit(1);
     }
 
     // Lower AST to TAC and print for inspection (Phase 4)
-    let tacs = lower::lower_program(&ast);
+    let tacs = lower::lower_program(&ast, &tables);
     println!("--- Generated TAC ---");
     for f in &tacs {
         println!("{}", f);
@@ -87,11 +249,17 @@ fn main() {
     }
     println!("--------------------------------------------");
 
-    // run the program (simple interpreter) and use returned exit code
-    match codegen::run(&ast) {
-        Ok(code) => std::process::exit(code),
+    // run the program (simple interpreter) on our stdin and stdout, and use
+    // returned exit code
+    run_config.args = std::iter::once(filename.clone()).chain(program_args).collect();
+    match codegen::run_with(&ast, &tables, &mut run_config) {
+        Ok(output) => std::process::exit(output.exit_code),
         Err(e) => {
-            eprintln!("Runtime error: {}", e);
+            let loc = e.node().and_then(|node| parser.positions().get(&node)).and_then(|pos| preprocessed.location(pos.line));
+            match loc {
+                Some(loc) => eprintln!("{}:{}: runtime error: {}", loc.file, loc.line, e),
+                None => eprintln!("Runtime error: {}", e),
+            }
             std::process::exit(1);
         }
     }
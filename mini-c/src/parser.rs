@@ -1,11 +1,35 @@
 // brings definition and functions from other types
 use crate::token::Token;
-use crate::ast::{Program, Function, Block, Stmt, Expr, Type};
+use crate::lexer::SourcePos;
+use crate::standard::{Feature, FeatureUse};
+use crate::ast::{Program, Function, Block, Stmt, StmtKind, Expr, ExprKind, NodeId, Type, UnaryOp, BinaryOp};
+use std::collections::HashMap;
+use std::fmt;
 
 // holds all tokens and pointer access
 pub struct Parser {
     tokens: Vec<Token>,
     position: usize,
+    next_id: u32,
+    token_pos: Vec<SourcePos>,              // where each token starts, when known
+    node_pos: HashMap<NodeId, SourcePos>,   // where each node's first token starts
+    features: Vec<FeatureUse>,              // constructs only some standards allow
+    errors: Vec<ParseError>,                // in source order
+}
+
+
+// a construct the parser could not read; compilation stops after parsing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub pos: Option<SourcePos>, // None without token positions
+}
+
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
 }
 
 // new -> creates new parse
@@ -16,7 +40,73 @@ pub struct Parser {
 // parse the statements into one function
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, position: 0 }
+        Parser { tokens, position: 0, next_id: 0, token_pos: Vec::new(), node_pos: HashMap::new(), features: Vec::new(), errors: Vec::new() }
+    }
+
+    // a parser that also records where every node starts, see `positions`
+    pub fn with_positions(tokens: Vec<(Token, SourcePos)>) -> Self {
+        let (tokens, token_pos) = tokens.into_iter().unzip();
+        Parser { tokens, position: 0, next_id: 0, token_pos, node_pos: HashMap::new(), features: Vec::new(), errors: Vec::new() }
+    }
+
+    // source position of the nodes parsed so far; empty without token positions
+    pub fn positions(&self) -> &HashMap<NodeId, SourcePos> {
+        &self.node_pos
+    }
+
+    // every use of a standard-dependent feature parsed so far, whatever the
+    // standard; see standard::violations
+    pub fn features(&self) -> &[FeatureUse] {
+        &self.features
+    }
+
+    // the errors found so far; a program parsed with errors must not be compiled
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    // record an error at the current token
+    fn error(&mut self, message: &str) {
+        let pos = self.token_pos.get(self.position).copied();
+        self.errors.push(ParseError { message: message.to_string(), pos });
+    }
+
+    // skip the rest of a statement after an error: past the next `;`, or up to
+    // the `}` closing the enclosing block
+    fn recover(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.current_token() {
+                Token::EOF => return,
+                Token::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                Token::RBrace if depth == 0 => return,
+                Token::RBrace => depth -= 1,
+                Token::LBrace => depth += 1,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    // parse a statement, reporting an error and skipping it if it is malformed
+    fn parse_statement_or_recover(&mut self) -> Option<Stmt> {
+        let reported = self.errors.len();
+        let stmt = self.parse_statement();
+        if stmt.is_none() {
+            if self.errors.len() == reported {
+                self.error("expected an expression");
+            }
+            self.recover();
+        }
+        stmt
+    }
+
+    // record a feature used by the construct starting at token index `start`
+    fn note(&mut self, feature: Feature, start: usize) {
+        self.features.push(FeatureUse { feature, pos: self.token_pos.get(start).copied() });
     }
 
     fn current_token(&self) -> &Token {
@@ -29,6 +119,21 @@ impl Parser {
         }
     }
     
+    // a fresh id for a node whose first token is at index `start`;
+    // children get theirs before their parent
+    fn node_id(&mut self, start: usize) -> NodeId {
+        let id = NodeId(self.next_id);
+        self.next_id += 1;
+        if let Some(&pos) = self.token_pos.get(start) {
+            self.node_pos.insert(id, pos);
+        }
+        id
+    }
+
+    fn expr(&mut self, kind: ExprKind, start: usize) -> Expr {
+        Expr { id: self.node_id(start), kind }
+    }
+
     // move to the next token
     fn advance(&mut self){
         if self.position<self.tokens.len(){
@@ -36,59 +141,128 @@ impl Parser {
         }
     }
 
+    // parse a type specifier list such as `unsigned long int`; None if no type starts here
+    fn parse_type(&mut self) -> Option<Type> {
+        let start = self.position;
+        let mut base: Option<Token> = None;
+        let mut longs = 0;
+        let mut unsigned = false;
+        let mut seen = false;
+        loop {
+            match self.current_token() {
+                Token::Unsigned => unsigned = true,
+                Token::Signed => {}
+                Token::Long => longs += 1,
+                t @ (Token::Int | Token::Char | Token::Bool | Token::Float | Token::Double | Token::Void) if base.is_none() => {
+                    base = Some(t.clone());
+                }
+                _ => break,
+            }
+            seen = true;
+            self.advance();
+        }
+        if !seen {
+            return None;
+        }
+        if longs >= 2 {
+            self.note(Feature::LongLong, start);
+        }
+        if base == Some(Token::Bool) {
+            self.note(Feature::Bool, start);
+        }
+
+        let ty = match (base, longs, unsigned) {
+            (Some(Token::Void), _, _) => Type::Void,
+            (Some(Token::Char), _, _) => Type::Char,
+            (Some(Token::Bool), _, _) => Type::Bool,
+            (Some(Token::Float), _, _) => Type::Float,
+            (Some(Token::Double), 0, _) => Type::Double,
+            (Some(Token::Double), _, _) => Type::LongDouble,
+            (_, 0, false) => Type::Int,
+            (_, 0, true) => Type::UnsignedInt,
+            (_, 1, false) => Type::Long,
+            (_, 1, true) => Type::UnsignedLong,
+            (_, _, false) => Type::LongLong,
+            (_, _, true) => Type::UnsignedLongLong,
+        };
+        Some(ty)
+    }
+
+    // take a declared name; keywords are accepted too so semantic analysis can reject them clearly
+    fn take_name(&mut self) -> Option<String> {
+        let name = match self.current_token() {
+            Token::Ident(name) => name.clone(),
+            tok => tok.keyword_text()?.to_string(),
+        };
+        self.advance();
+        Some(name)
+    }
+
     // parse the whole program
     pub fn parse_program(&mut self) -> Program {
         let mut functions = Vec::new();
+        let mut static_asserts = Vec::new();
 
         while *self.current_token() != Token::EOF {
-            if let Some(func) = self.parse_function() {
+            let (before, reported) = (self.position, self.errors.len());
+            if *self.current_token() == Token::StaticAssert {
+                if let Some(stmt) = self.parse_statement_or_recover() {
+                    static_asserts.push(stmt);
+                }
+            } else if let Some(func) = self.parse_function() {
                 functions.push(func);
             }
             
             else{
-                break;
+                if self.errors.len() == reported {
+                    self.error("expected a function definition");
+                }
+                if self.position == before {
+                    self.advance();
+                }
+                self.recover();
             }
         }
 
-        Program { functions }
+        Program { functions, static_asserts }
     }
 
 
     // parse one function
     fn parse_function(&mut self) -> Option<Function> {
-        // Expect: <type> <ident>() { <body> }
-        let return_type = match self.current_token() {
-            Token::Int => { self.advance(); Type::Int }
-            Token::Float => { self.advance(); Type::Float }
-            Token::Char => { self.advance(); Type::Char }
-            Token::Void => { self.advance(); Type::Void }
-            _ => return None,
-        };
-
-        let name = if let Token::Ident(name) = self.current_token().clone() {
-            self.advance();
-            name
-        } else {
-            return None;
+        let start = self.position;
+        // Expect: <type> <ident>() { <body> }; C89 lets the type default to int
+        let return_type = match self.parse_type() {
+            Some(ty) => ty,
+            None if matches!(self.current_token(), Token::Ident(_)) && self.tokens.get(self.position + 1) == Some(&Token::LParen) => {
+                self.note(Feature::ImplicitInt, start);
+                Type::Int
+            }
+            None => return None,
         };
+        let name = self.take_name()?;
 
         let mut params: Vec<(Type, String)> = Vec::new();
+        let mut prototyped = false;
         if *self.current_token() == Token::LParen {
             self.advance();
+            prototyped = *self.current_token() != Token::RParen;
             // parse parameters until RParen
             while *self.current_token() != Token::RParen && *self.current_token() != Token::EOF {
-                // expect type
-                let ptype = match self.current_token() {
-                    Token::Int => Type::Int,
-                    Token::Float => Type::Float,
-                    Token::Char => Type::Char,
-                    _ => { self.advance(); continue; }
+                // expect type; `(void)` means no parameters
+                let mut ptype = match self.parse_type() {
+                    Some(Type::Void) if *self.current_token() != Token::Star => continue,
+                    Some(t) => t,
+                    None => { self.advance(); continue; }
                 };
-                self.advance();
-                // expect ident
-                if let Token::Ident(pname) = self.current_token().clone() {
-                    params.push((ptype, pname.clone()));
+                // pointer declarators, as in `char **argv`
+                while *self.current_token() == Token::Star {
                     self.advance();
+                    ptype = Type::Pointer(Box::new(ptype));
+                }
+                // expect ident
+                if let Some(pname) = self.take_name() {
+                    params.push((ptype, pname));
                 }
                 // skip comma
                 if *self.current_token() == Token::Comma {
@@ -99,149 +273,324 @@ impl Parser {
                 self.advance();
             }
         }
-        if *self.current_token() == Token::LBrace {
-            self.advance();
+        if *self.current_token() != Token::LBrace {
+            self.error("expected '{' before the function body");
+            return None;
         }
+        self.advance();
+        let body = self.parse_block();
 
-        let mut stmts = Vec::new();
+        Some(Function { 
+            id: self.node_id(start),
+            name, 
+            return_type,
+            params, 
+            prototyped,
+            body,
+        })
+    }
+
+
+    // the statements of a block after its `{`, and the closing `}`;
+    // before C99 all declarations of a block come first
+    fn parse_block(&mut self) -> Block {
+        let mut stmts: Vec<Stmt> = Vec::new();
         while *self.current_token() != Token::RBrace && *self.current_token() != Token::EOF {
-            if let Some(stmt) = self.parse_statement() {
-                stmts.push(stmt);
-            } else {
-                break;
+            let stmt_start = self.position;
+            let Some(stmt) = self.parse_statement_or_recover() else { continue };
+            let is_decl = |s: &Stmt| matches!(s.kind, StmtKind::VarDecl { .. } | StmtKind::StaticAssert { .. });
+            if is_decl(&stmt) && stmts.iter().any(|s| !is_decl(s)) {
+                self.note(Feature::MixedDeclaration, stmt_start);
             }
+            stmts.push(stmt);
         }
 
         if *self.current_token() == Token::RBrace {
             self.advance();
+        } else {
+            self.error("expected '}' at the end of the input");
         }
+        Block { stmts }
+    }
 
-        Some(Function { 
-            name, 
-            return_type,
-            params, 
-            body: Block { stmts } 
-        })
+
+    // the parenthesized condition of an `if` or a `while`
+    fn parse_condition(&mut self, keyword: &str) -> Option<Expr> {
+        if *self.current_token() != Token::LParen {
+            self.error(&format!("expected '(' after '{}'", keyword));
+            return None;
+        }
+        self.advance();
+        let cond = self.parse_expr()?;
+        if *self.current_token() != Token::RParen {
+            self.error(&format!("expected ')' after the condition of '{}'", keyword));
+            return None;
+        }
+        self.advance();
+        Some(cond)
+    }
+
+
+    // the statement an `if`, `else` or `while` controls, which cannot be a
+    // declaration
+    fn parse_substatement(&mut self) -> Option<Box<Stmt>> {
+        let t = self.current_token();
+        if (is_type_start(t) && *t != Token::Void) || *t == Token::Const || *t == Token::StaticAssert {
+            self.error("expected a statement, found a declaration");
+            return None;
+        }
+        self.parse_statement().map(Box::new)
     }
 
 
     // parse the statements into one function
     fn parse_statement(&mut self) -> Option<Stmt> {
-        match self.current_token() {
+        let start = self.position;
+        let kind = match self.current_token() {
+            // compound statements end with the statement they contain
+            Token::LBrace => {
+                self.advance();
+                let block = self.parse_block();
+                return Some(Stmt { id: self.node_id(start), kind: StmtKind::Block(block) });
+            }
+            Token::If => {
+                self.advance();
+                let cond = self.parse_condition("if")?;
+                let then = self.parse_substatement()?;
+                let mut otherwise = None;
+                if *self.current_token() == Token::Else {
+                    self.advance();
+                    otherwise = Some(self.parse_substatement()?);
+                }
+                return Some(Stmt { id: self.node_id(start), kind: StmtKind::If { cond, then, otherwise } });
+            }
+            Token::While => {
+                self.advance();
+                let cond = self.parse_condition("while")?;
+                let body = self.parse_substatement()?;
+                return Some(Stmt { id: self.node_id(start), kind: StmtKind::While { cond, body } });
+            }
+            Token::Semicolon => StmtKind::Empty,
             Token::Return => {
                 self.advance();
-                match self.current_token().clone() {
-                    Token::Number(n) => {
-                        self.advance();
-                        if *self.current_token() == Token::Semicolon { self.advance(); }
-                        return Some(Stmt::Return(Expr::Number(n)));
-                    }
-                    Token::FloatNumber(f) => {
-                        self.advance();
-                        if *self.current_token() == Token::Semicolon { self.advance(); }
-                        return Some(Stmt::Return(Expr::FloatNumber(f)));
-                    }
-                    Token::CharLiteral(c) => {
+                if *self.current_token() == Token::Semicolon {
+                    StmtKind::Return(None)
+                } else {
+                    StmtKind::Return(Some(self.parse_expr()?))
+                }
+            }
+            // _Static_assert(condition, "message")
+            Token::StaticAssert => {
+                self.note(Feature::StaticAssert, start);
+                self.advance();
+                if *self.current_token() != Token::LParen {
+                    self.error("expected '(' after _Static_assert");
+                    return None;
+                }
+                self.advance();
+                let cond = self.parse_expr()?;
+                let mut message = None;
+                if *self.current_token() == Token::Comma {
+                    self.advance();
+                    while let Token::String(more) = self.current_token() {
+                        message.get_or_insert_with(String::new).push_str(more);
                         self.advance();
-                        if *self.current_token() == Token::Semicolon { self.advance(); }
-                        return Some(Stmt::Return(Expr::CharLiteral(c)));
                     }
-                    _ => {}
                 }
+                let Some(message) = message else {
+                    self.error("expected ',' and a string literal in _Static_assert");
+                    return None;
+                };
+                if *self.current_token() != Token::RParen {
+                    self.error("expected ')' after the _Static_assert message");
+                    return None;
+                }
+                self.advance();
+                StmtKind::StaticAssert { cond, message }
             }
-            Token::Int | Token::Float | Token::Char => {
-                // Variable declaration: <type> name = value;
-                let ty = match self.current_token() {
-                    Token::Int => Type::Int,
-                    Token::Float => Type::Float,
-                    Token::Char => Type::Char,
-                    _ => Type::Int,
+            t if (is_type_start(t) && *t != Token::Void) || *t == Token::Const => {
+                // Variable declaration: [const] <type> [*...] name [= value];
+                let is_const = *t == Token::Const;
+                if is_const {
+                    self.advance();
+                }
+                let mut ty = self.parse_type().unwrap_or(Type::Int);
+                while *self.current_token() == Token::Star {
+                    self.advance();
+                    ty = Type::Pointer(Box::new(ty));
+                }
+                let Some(name) = self.take_name() else {
+                    self.error("expected a name in the declaration");
+                    return None;
                 };
+                let value = if *self.current_token() == Token::Assign {
+                    self.advance();
+                    Some(self.parse_expr()?)
+                } else {
+                    None
+                };
+                StmtKind::VarDecl { ty, name, value, is_const }
+            }
+            _ => StmtKind::ExprStmt(self.parse_expr()?),
+        };
+        if *self.current_token() != Token::Semicolon {
+            self.error("expected ';'");
+            return None;
+        }
+        self.advance();
+        Some(Stmt { id: self.node_id(start), kind })
+    }
+
+
+    // expressions, loosest binding first:
+    // assignment (right associative), == !=, < > <= >=, + -, * /, unary - !, then primaries
+    // any expression may stand left of `=`; whether it can be assigned to is
+    // decided by semantic analysis
+    fn parse_expr(&mut self) -> Option<Expr> {
+        let start = self.position;
+        let target = self.parse_equality()?;
+        if *self.current_token() != Token::Assign {
+            return Some(target);
+        }
+        self.advance();
+        let value = self.parse_expr()?;
+        Some(self.expr(ExprKind::Assign { target: Box::new(target), value: Box::new(value) }, start))
+    }
+
+    fn parse_equality(&mut self) -> Option<Expr> {
+        let start = self.position;
+        let mut left = self.parse_relational()?;
+        loop {
+            let op = match self.current_token() {
+                Token::EqEq => BinaryOp::Eq,
+                Token::NotEq => BinaryOp::Ne,
+                _ => return Some(left),
+            };
+            self.advance();
+            let right = self.parse_relational()?;
+            left = self.expr(ExprKind::Binary { op, left: Box::new(left), right: Box::new(right) }, start);
+        }
+    }
+
+    fn parse_relational(&mut self) -> Option<Expr> {
+        let start = self.position;
+        let mut left = self.parse_additive()?;
+        loop {
+            let op = match self.current_token() {
+                Token::Less => BinaryOp::Lt,
+                Token::Greater => BinaryOp::Gt,
+                Token::LessEq => BinaryOp::Le,
+                Token::GreaterEq => BinaryOp::Ge,
+                _ => return Some(left),
+            };
+            self.advance();
+            let right = self.parse_additive()?;
+            left = self.expr(ExprKind::Binary { op, left: Box::new(left), right: Box::new(right) }, start);
+        }
+    }
+
+    fn parse_additive(&mut self) -> Option<Expr> {
+        let start = self.position;
+        let mut left = self.parse_term()?;
+        loop {
+            let op = match self.current_token() {
+                Token::Plus => BinaryOp::Add,
+                Token::Minus => BinaryOp::Sub,
+                _ => return Some(left),
+            };
+            self.advance();
+            let right = self.parse_term()?;
+            left = self.expr(ExprKind::Binary { op, left: Box::new(left), right: Box::new(right) }, start);
+        }
+    }
+
+    fn parse_term(&mut self) -> Option<Expr> {
+        let start = self.position;
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.current_token() {
+                Token::Star => BinaryOp::Mul,
+                Token::Slash => BinaryOp::Div,
+                _ => return Some(left),
+            };
+            self.advance();
+            let right = self.parse_unary()?;
+            left = self.expr(ExprKind::Binary { op, left: Box::new(left), right: Box::new(right) }, start);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Option<Expr> {
+        let start = self.position;
+        let op = match self.current_token() {
+            Token::Minus => UnaryOp::Neg,
+            Token::Bang => UnaryOp::Not,
+            Token::Amp => UnaryOp::AddrOf,
+            _ => return self.parse_primary(),
+        };
+        self.advance();
+        let expr = self.parse_unary()?;
+        Some(self.expr(ExprKind::Unary { op, expr: Box::new(expr) }, start))
+    }
+
+    // literals, names, calls and parenthesized expressions
+    fn parse_primary(&mut self) -> Option<Expr> {
+        let start = self.position;
+        let kind = match self.current_token().clone() {
+            Token::Number(n, ty) => {
+                if matches!(ty, Type::LongLong | Type::UnsignedLongLong) {
+                    self.note(Feature::LongLong, start);
+                }
+                ExprKind::Number(n, ty)
+            }
+            Token::FloatNumber(f, ty) => ExprKind::FloatNumber(f, ty),
+            Token::CharLiteral(c) => ExprKind::CharLiteral(c),
+            Token::String(mut s) => {
+                // adjacent string literals are concatenated
                 self.advance();
-                if let Token::Ident(name) = self.current_token().clone() {
+                while let Token::String(more) = self.current_token() {
+                    s.push_str(more);
                     self.advance();
-                    if *self.current_token() == Token::Assign {
-                        self.advance();
-                        match self.current_token().clone() {
-                            Token::Number(n) => {
-                                self.advance();
-                                if *self.current_token() == Token::Semicolon { self.advance(); }
-                                return Some(Stmt::VarDecl { ty, name, value: Expr::Number(n) });
-                            }
-                            Token::FloatNumber(f) => {
-                                self.advance();
-                                if *self.current_token() == Token::Semicolon { self.advance(); }
-                                return Some(Stmt::VarDecl { ty, name, value: Expr::FloatNumber(f) });
-                            }
-                            Token::CharLiteral(c) => {
-                                self.advance();
-                                if *self.current_token() == Token::Semicolon { self.advance(); }
-                                return Some(Stmt::VarDecl { ty, name, value: Expr::CharLiteral(c) });
-                            }
-                            Token::Ident(var_name) => {
-                                self.advance();
-                                if *self.current_token() == Token::Semicolon { self.advance(); }
-                                return Some(Stmt::VarDecl { ty, name, value: Expr::Ident(var_name.clone()) });
-                            }
-                            _ => {}
-                        }
-                    }
                 }
+                return Some(self.expr(ExprKind::StringLiteral(s), start));
             }
             Token::Ident(name) => {
-                // Function call: name(...);
-                let func_name = name.clone();
                 self.advance();
-                if *self.current_token() == Token::LParen {
-                    self.advance();
-                    let mut args = Vec::new();
-                    
-                    // Parse arguments
-                    while *self.current_token() != Token::RParen && *self.current_token() != Token::EOF {
-                        match self.current_token() {
-                            Token::String(s) => {
-                                args.push(Expr::StringLiteral(s.clone()));
-                                self.advance();
-                            }
-                            Token::Ident(var_name) => {
-                                args.push(Expr::Ident(var_name.clone()));
-                                self.advance();
-                            }
-                            Token::Number(n) => {
-                                args.push(Expr::Number(*n));
-                                self.advance();
-                            }
-                            Token::FloatNumber(f) => {
-                                args.push(Expr::FloatNumber(*f));
-                                self.advance();
-                            }
-                            Token::CharLiteral(c) => {
-                                args.push(Expr::CharLiteral(*c));
-                                self.advance();
-                            }
-                            Token::Comma => {
-                                self.advance(); // skip comma
-                            }
-                            _ => {
-                                self.advance(); // skip unknown tokens
-                            }
+                if *self.current_token() != Token::LParen {
+                    return Some(self.expr(ExprKind::Ident(name), start));
+                }
+                self.advance();
+                let mut args = Vec::new();
+                if *self.current_token() != Token::RParen {
+                    loop {
+                        args.push(self.parse_expr()?);
+                        if *self.current_token() != Token::Comma {
+                            break;
                         }
-                    }
-                    
-                    if *self.current_token() == Token::RParen {
-                        self.advance();
-                    }
-                    if *self.current_token() == Token::Semicolon {
                         self.advance();
                     }
-                    return Some(Stmt::ExprStmt(Expr::Call { 
-                        name: func_name, 
-                        args 
-                    }));
                 }
+                if *self.current_token() == Token::RParen {
+                    self.advance();
+                }
+                return Some(self.expr(ExprKind::Call { name, args }, start));
             }
-            _ => {}
-        }
-        None
+            Token::LParen => {
+                self.advance();
+                let inner = self.parse_expr()?;
+                if *self.current_token() == Token::RParen {
+                    self.advance();
+                }
+                return Some(inner);
+            }
+            _ => return None,
+        };
+        self.advance();
+        Some(self.expr(kind, start))
     }
 }
+
+
+// whether a token can begin a type specifier
+pub(crate) fn is_type_start(tok: &Token) -> bool {
+    matches!(tok, Token::Int | Token::Char | Token::Bool | Token::Float | Token::Double | Token::Void | Token::Long | Token::Unsigned | Token::Signed)
+}
@@ -0,0 +1,81 @@
+// Prototypes of the standard library functions each built-in header declares.
+// semantic analysis registers those of the included headers in the global
+// scope, so calls to them are checked like calls to the program's own
+// functions. `const` is dropped from pointer parameters, `FILE *` is `void *`
+// with the standard streams returned by `__stream` (the header defines `stdin`
+// as `(__stream(0))`), and functions using other types mini-c cannot express
+// are left out
+use crate::ast::Type;
+use crate::symbol::FunctionSig;
+
+
+// the functions declared by a header, in no particular order; empty for
+// headers that only define macros
+pub fn header_functions(header: &str) -> Vec<FunctionSig> {
+    use Type::*;
+    let string = || Pointer(Box::new(Char));
+    let void_ptr = || Pointer(Box::new(Void));
+    let size = || UnsignedLong;
+    let fixed = |name: &str, return_type: Type, params: Vec<Type>| FunctionSig {
+        name: name.to_string(),
+        return_type,
+        params_types: params,
+        prototyped: true,
+        variadic: false,
+    };
+    let variadic = |name: &str, return_type: Type, params: Vec<Type>| FunctionSig { variadic: true, ..fixed(name, return_type, params) };
+
+    match header {
+        "stdio.h" => vec![
+            variadic("printf", Int, vec![string()]),
+            variadic("sprintf", Int, vec![string(), string()]),
+            variadic("snprintf", Int, vec![string(), size(), string()]),
+            variadic("scanf", Int, vec![string()]),
+            variadic("sscanf", Int, vec![string(), string()]),
+            fixed("puts", Int, vec![string()]),
+            fixed("putchar", Int, vec![Int]),
+            fixed("getchar", Int, vec![]),
+            fixed("fgets", string(), vec![string(), Int, void_ptr()]),
+            fixed("__stream", void_ptr(), vec![Int]),
+        ],
+        "stdlib.h" => vec![
+            fixed("abs", Int, vec![Int]),
+            fixed("labs", Long, vec![Long]),
+            fixed("atoi", Int, vec![string()]),
+            fixed("atol", Long, vec![string()]),
+            fixed("atof", Double, vec![string()]),
+            fixed("rand", Int, vec![]),
+            fixed("srand", Void, vec![UnsignedInt]),
+            fixed("exit", Void, vec![Int]),
+            fixed("abort", Void, vec![]),
+            fixed("malloc", void_ptr(), vec![size()]),
+            fixed("free", Void, vec![void_ptr()]),
+        ],
+        "string.h" => vec![
+            fixed("strlen", size(), vec![string()]),
+            fixed("strcmp", Int, vec![string(), string()]),
+            fixed("strncmp", Int, vec![string(), string(), size()]),
+            fixed("strcpy", string(), vec![string(), string()]),
+            fixed("strcat", string(), vec![string(), string()]),
+            fixed("strchr", string(), vec![string(), Int]),
+        ],
+        "math.h" => {
+            let unary = ["sqrt", "fabs", "sin", "cos", "tan", "exp", "log", "log10", "floor", "ceil"];
+            let binary = ["pow", "fmod", "atan2"];
+            let mut functions: Vec<FunctionSig> = unary.iter().map(|name| fixed(name, Double, vec![Double])).collect();
+            functions.extend(binary.iter().map(|name| fixed(name, Double, vec![Double, Double])));
+            functions
+        }
+        "ctype.h" => ["isalpha", "isdigit", "isalnum", "isspace", "isupper", "islower", "isprint", "ispunct", "toupper", "tolower"]
+            .iter()
+            .map(|name| fixed(name, Int, vec![Int]))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+
+// the functions declared by all of the included headers
+pub fn prelude(headers: &[String]) -> Vec<FunctionSig> {
+    headers.iter().flat_map(|header| header_functions(header)).collect()
+}
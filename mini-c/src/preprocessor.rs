@@ -0,0 +1,1238 @@
+// A C preprocessor that runs before the lexer.
+// It works on preprocessing tokens, expands macros (hide-set algorithm), handles
+// conditional inclusion and #include, and remembers where every output line came from.
+use crate::lexer::{normalize, Lexer, SourcePos, TriviaKind, DEFAULT_TAB_WIDTH};
+use crate::standard::{Feature, Standard};
+use crate::token::Token;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+
+// where an output line of the preprocessed text came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLoc {
+    pub file: String,
+    pub line: usize,
+}
+
+
+// an error found while preprocessing, with its original location
+#[derive(Debug, Clone)]
+pub struct PreprocessError {
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+
+// display as file:line:col: message
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.file, self.line, self.col, self.message)
+    }
+}
+
+
+// the result of preprocessing: the text for the lexer and a line map back to the sources
+#[derive(Debug, Clone)]
+pub struct Preprocessed {
+    pub text: String,
+    pub lines: Vec<SourceLoc>,  // lines[i] is the origin of output line i + 1
+    pub headers: Vec<String>,   // standard headers included with #include <...>
+}
+
+
+// helpers on the preprocessed output
+impl Preprocessed {
+    // original location of a (1-based) line of the preprocessed text
+    pub fn location(&self, line: usize) -> Option<&SourceLoc> {
+        line.checked_sub(1).and_then(|i| self.lines.get(i))
+    }
+
+    // lex the preprocessed text into the token stream the parser expects
+    pub fn tokens(&self) -> Vec<Token> {
+        Lexer::new(&self.text).collect()
+    }
+
+    // the same tokens with where each starts in the preprocessed text;
+    // `location` maps their lines back to the sources
+    pub fn tokens_with_pos(&self) -> Vec<(Token, SourcePos)> {
+        let mut lexer = Lexer::new(&self.text);
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.next_token_with_pos() {
+                (Token::EOF, _) => return tokens,
+                tok => tokens.push(tok),
+            }
+        }
+    }
+}
+
+
+// kinds of preprocessing tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PPKind {
+    Ident,
+    Number,
+    Str,
+    Char,
+    Punct,
+    Other,
+    EndOfFile,  // marker pushed after the tokens of an included file
+}
+
+
+// a preprocessing token with its origin
+#[derive(Debug, Clone)]
+struct PPToken {
+    kind: PPKind,
+    text: String,
+    file: Rc<str>,
+    line: usize,
+    col: usize,
+    at_bol: bool,             // first token on a source line (directives start here)
+    space: String,            // whitespace before the token, comments replaced by spaces
+    hideset: Rc<Vec<String>>, // macros that must not expand this token again
+}
+
+
+// a macro definition; params is None for object-like macros
+#[derive(Debug, Clone)]
+struct Macro {
+    params: Option<Vec<String>>,
+    variadic: bool,
+    body: Vec<PPToken>,
+}
+
+
+// state of one #if/#ifdef group
+struct Cond {
+    taken: bool,   // some branch of this group was already included
+    in_else: bool,
+    file: Rc<str>,
+    line: usize,
+}
+
+
+// the preprocessor itself; configure it, then preprocess a file or a string
+pub struct Preprocessor {
+    include_paths: Vec<PathBuf>,
+    macros: HashMap<String, Macro>,
+    once: HashSet<PathBuf>,
+    conds: Vec<Cond>,
+    include_stack: Vec<(PathBuf, usize)>,  // current file and the #if depth when it was entered
+    headers: Vec<String>,
+    errors: Vec<PreprocessError>,
+    tab_width: usize,
+    standard: Standard,
+}
+
+
+// how deep #include may nest before we assume a cycle
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+
+// punctuators, longest first so the tokenizer can take the longest match
+const PUNCTUATORS: &[&str] = &[
+    "<<=", ">>=", "...", "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||",
+    "*=", "/=", "%=", "+=", "-=", "&=", "^=", "|=", "##",
+];
+
+
+impl Default for Preprocessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl Preprocessor {
+
+    // create a preprocessor with the standard predefined macros
+    pub fn new() -> Self {
+        let mut pp = Preprocessor {
+            include_paths: Vec::new(),
+            macros: HashMap::new(),
+            once: HashSet::new(),
+            conds: Vec::new(),
+            include_stack: Vec::new(),
+            headers: Vec::new(),
+            errors: Vec::new(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            standard: Standard::default(),
+        };
+        pp.define("__STDC__=1");
+        pp.define("__STDC_HOSTED__=1");
+        pp.set_standard(Standard::default());
+        pp.define("__mini_c__=1");
+        pp
+    }
+
+    // add a directory searched by #include (the -I flag)
+    pub fn add_include_path<P: AsRef<Path>>(&mut self, dir: P) {
+        self.include_paths.push(dir.as_ref().to_path_buf());
+    }
+
+    // define a macro from a -D argument: NAME, NAME=VALUE or NAME(args)=VALUE
+    pub fn define(&mut self, spec: &str) {
+        let line = match spec.split_once('=') {
+            Some((name, value)) => format!("{} {}", name, value),
+            None => format!("{} 1", spec),
+        };
+        let toks = tokenize(&line, &Rc::from("<command line>"), self.tab_width);
+        self.define_macro(toks);
+    }
+
+    // distance between tab stops used for diagnostic columns
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.tab_width = width;
+    }
+
+    // the standard to preprocess for (the --std flag): it decides
+    // __STDC_VERSION__ and whether `//` comments are allowed
+    pub fn set_standard(&mut self, standard: Standard) {
+        self.standard = standard;
+        match standard.version_macro() {
+            Some(version) => self.define(&format!("__STDC_VERSION__={}", version)),
+            None => self.undefine("__STDC_VERSION__"),
+        }
+    }
+
+    // remove a macro definition (the -U flag)
+    pub fn undefine(&mut self, name: &str) {
+        self.macros.remove(name);
+    }
+
+    // whether a macro is currently defined
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name)
+    }
+
+    // preprocess a file on disk
+    pub fn preprocess_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Preprocessed, Vec<PreprocessError>> {
+        let path = path.as_ref();
+        let source = match fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => {
+                return Err(vec![PreprocessError {
+                    file: path.display().to_string(),
+                    line: 0,
+                    col: 0,
+                    message: format!("cannot read file: {}", e),
+                }]);
+            }
+        };
+        self.run(&source, path)
+    }
+
+    // preprocess source text; `file_name` is used for __FILE__, diagnostics and "..." includes
+    pub fn preprocess_str(&mut self, source: &str, file_name: &str) -> Result<Preprocessed, Vec<PreprocessError>> {
+        self.run(source, Path::new(file_name))
+    }
+
+
+    // drive the whole preprocessing of one translation unit
+    fn run(&mut self, source: &str, path: &Path) -> Result<Preprocessed, Vec<PreprocessError>> {
+        self.errors.clear();
+        self.conds.clear();
+        self.headers.clear();
+        self.include_stack = vec![(path.to_path_buf(), 0)];
+
+        let file: Rc<str> = Rc::from(path.display().to_string());
+        self.check_comments(source, &file);
+        let mut input: VecDeque<PPToken> = tokenize(source, &file, self.tab_width).into();
+        let mut out: Vec<PPToken> = Vec::new();
+
+        while let Some(tok) = input.pop_front() {
+            if tok.kind == PPKind::EndOfFile {
+                self.leave_file(&tok);
+                continue;
+            }
+            if tok.at_bol && tok.text == "#" && tok.kind == PPKind::Punct {
+                self.directive(tok, &mut input);
+                continue;
+            }
+            if self.expand_macro(&tok, &mut input) {
+                continue;
+            }
+            out.push(tok);
+        }
+
+        // every #if must be closed by the end of the main file
+        for cond in std::mem::take(&mut self.conds) {
+            self.errors.push(PreprocessError {
+                file: cond.file.to_string(),
+                line: cond.line,
+                col: 1,
+                message: "unterminated conditional directive".to_string(),
+            });
+        }
+
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
+        Ok(render(&out, std::mem::take(&mut self.headers)))
+    }
+
+
+    // record an error at a token
+    fn error(&mut self, at: &PPToken, message: String) {
+        self.errors.push(PreprocessError { file: at.file.to_string(), line: at.line, col: at.col, message });
+    }
+
+
+    // `//` comments are an error before C99
+    fn check_comments(&mut self, source: &str, file: &Rc<str>) {
+        if Feature::LineComment.allowed_in(self.standard) {
+            return;
+        }
+        let mut lexer = Lexer::lossless(source);
+        loop {
+            while let Some(kind) = lexer.next_trivia() {
+                if kind == TriviaKind::LineComment {
+                    let pos = lexer.token_pos();
+                    let message = format!("{} (--std={})", Feature::LineComment.diagnostic(), self.standard.flag());
+                    self.errors.push(PreprocessError { file: file.to_string(), line: pos.line, col: pos.col, message });
+                }
+            }
+            if lexer.next_token() == Token::EOF {
+                return;
+            }
+        }
+    }
+
+
+    // end of an included file: its conditionals must be balanced
+    fn leave_file(&mut self, marker: &PPToken) {
+        if let Some((_, depth)) = self.include_stack.pop() {
+            while self.conds.len() > depth {
+                let cond = self.conds.pop().unwrap();
+                self.errors.push(PreprocessError {
+                    file: cond.file.to_string(),
+                    line: cond.line,
+                    col: 1,
+                    message: format!("unterminated conditional directive in '{}'", marker.file),
+                });
+            }
+        }
+    }
+
+
+    // handle a directive; `hash` is the '#' at the start of the line
+    fn directive(&mut self, hash: PPToken, input: &mut VecDeque<PPToken>) {
+        let mut line = take_line(input);
+        if line.is_empty() {
+            return;  // the null directive
+        }
+        let name_tok = line.remove(0);
+        match name_tok.text.as_str() {
+            "define" => self.define_macro(line),
+            "undef" => {
+                match line.first() {
+                    Some(t) if t.kind == PPKind::Ident => { self.macros.remove(&t.text); }
+                    _ => self.error(&name_tok, "macro name missing in #undef".to_string()),
+                }
+            }
+            "include" => self.include(&name_tok, line, input),
+            "if" => {
+                let value = self.eval_condition(&name_tok, line);
+                self.push_cond(&hash, value, input);
+            }
+            "ifdef" | "ifndef" => {
+                let defined = match line.first() {
+                    Some(t) if t.kind == PPKind::Ident => self.is_defined(&t.text),
+                    _ => {
+                        self.error(&name_tok, format!("macro name missing in #{}", name_tok.text));
+                        false
+                    }
+                };
+                self.push_cond(&hash, defined == (name_tok.text == "ifdef"), input);
+            }
+            "elif" => {
+                match self.conds.last() {
+                    None => self.error(&name_tok, "#elif without #if".to_string()),
+                    Some(c) if c.in_else => self.error(&name_tok, "#elif after #else".to_string()),
+                    Some(c) if c.taken => skip_group(input),
+                    Some(_) => {
+                        if self.eval_condition(&name_tok, line) {
+                            self.conds.last_mut().unwrap().taken = true;
+                        } else {
+                            skip_group(input);
+                        }
+                    }
+                }
+            }
+            "else" => {
+                match self.conds.last_mut() {
+                    None => self.error(&name_tok, "#else without #if".to_string()),
+                    Some(c) if c.in_else => self.error(&name_tok, "#else after #else".to_string()),
+                    Some(c) => {
+                        c.in_else = true;
+                        if c.taken {
+                            skip_group(input);
+                        } else {
+                            c.taken = true;
+                        }
+                    }
+                }
+            }
+            "endif" => {
+                if self.conds.pop().is_none() {
+                    self.error(&name_tok, "#endif without #if".to_string());
+                }
+            }
+            "pragma" => {
+                if line.first().is_some_and(|t| t.text == "once") {
+                    if let Some((path, _)) = self.include_stack.last() {
+                        let key = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+                        self.once.insert(key);
+                    }
+                }
+                // other pragmas are accepted and ignored
+            }
+            "error" => {
+                let message = line.iter().map(|t| format!("{}{}", t.space, t.text)).collect::<String>();
+                self.error(&name_tok, format!("#error {}", message.trim()));
+            }
+            "warning" | "line" | "ident" => {}
+            other => {
+                if name_tok.kind == PPKind::Number {
+                    return;  // GNU line marker: # 12 "file"
+                }
+                self.error(&name_tok, format!("invalid preprocessing directive #{}", other));
+            }
+        }
+    }
+
+
+    // open a conditional group, skipping it when the condition is false
+    fn push_cond(&mut self, at: &PPToken, value: bool, input: &mut VecDeque<PPToken>) {
+        self.conds.push(Cond { taken: value, in_else: false, file: at.file.clone(), line: at.line });
+        if !value {
+            skip_group(input);
+        }
+    }
+
+
+    // #define NAME body / #define NAME(params) body
+    fn define_macro(&mut self, mut line: Vec<PPToken>) {
+        if line.is_empty() || line[0].kind != PPKind::Ident {
+            if let Some(t) = line.first().cloned() {
+                self.error(&t, "macro names must be identifiers".to_string());
+            }
+            return;
+        }
+        let name_tok = line.remove(0);
+        let mut params = None;
+        let mut variadic = false;
+
+        // a '(' directly after the name makes a function-like macro
+        if line.first().is_some_and(|t| t.text == "(" && t.space.is_empty()) {
+            line.remove(0);
+            let mut names = Vec::new();
+            loop {
+                if line.is_empty() {
+                    self.error(&name_tok, "missing ')' in macro parameter list".to_string());
+                    return;
+                }
+                let t = line.remove(0);
+                match (t.kind, t.text.as_str()) {
+                    (_, ")") if names.is_empty() => break,
+                    (PPKind::Ident, _) => names.push(t.text.clone()),
+                    (_, "...") => {
+                        variadic = true;
+                        names.push("__VA_ARGS__".to_string());
+                    }
+                    _ => {
+                        self.error(&t, format!("unexpected '{}' in macro parameter list", t.text));
+                        return;
+                    }
+                }
+                match line.first().map(|t| t.text.as_str()) {
+                    Some(",") if !variadic => { line.remove(0); }
+                    Some(")") => { line.remove(0); break; }
+                    _ => {
+                        self.error(&name_tok, "expected ',' or ')' in macro parameter list".to_string());
+                        return;
+                    }
+                }
+            }
+            params = Some(names);
+        }
+
+        if let Some(first) = line.first_mut() {
+            first.space.clear();
+        }
+        if line.first().is_some_and(|t| t.text == "##") || line.last().is_some_and(|t| t.text == "##") {
+            self.error(&name_tok, "'##' cannot appear at either end of a macro expansion".to_string());
+            return;
+        }
+        self.macros.insert(name_tok.text, Macro { params, variadic, body: line });
+    }
+
+
+    // #include "file" or #include <file>
+    fn include(&mut self, at: &PPToken, line: Vec<PPToken>, input: &mut VecDeque<PPToken>) {
+        // computed includes: expand macros first
+        let line = if line.first().is_some_and(|t| t.kind == PPKind::Ident) {
+            self.expand_list(line)
+        } else {
+            line
+        };
+
+        let (name, system) = match line.first() {
+            Some(t) if t.kind == PPKind::Str => (t.text.trim_matches('"').to_string(), false),
+            Some(t) if t.text == "<" => {
+                let mut name = String::new();
+                let mut closed = false;
+                for t in &line[1..] {
+                    if t.text == ">" {
+                        closed = true;
+                        break;
+                    }
+                    name.push_str(&t.space);
+                    name.push_str(&t.text);
+                }
+                if !closed {
+                    self.error(at, "missing '>' in #include".to_string());
+                    return;
+                }
+                (name, true)
+            }
+            _ => {
+                self.error(at, "#include expects \"FILENAME\" or <FILENAME>".to_string());
+                return;
+            }
+        };
+
+        if self.include_stack.len() > MAX_INCLUDE_DEPTH {
+            self.error(at, format!("#include nested too deeply (including '{}')", name));
+            return;
+        }
+
+        // "file" looks next to the including file first, then both forms use -I paths
+        let mut candidates = Vec::new();
+        if !system {
+            if let Some((current, _)) = self.include_stack.last() {
+                let dir = current.parent().unwrap_or(Path::new(""));
+                candidates.push(dir.join(&name));
+            }
+        }
+        for dir in &self.include_paths {
+            candidates.push(dir.join(&name));
+        }
+
+        if let Some(path) = candidates.into_iter().find(|p| p.is_file()) {
+            let key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+            if self.once.contains(&key) {
+                return;
+            }
+            match fs::read_to_string(&path) {
+                Ok(source) => {
+                    let file: Rc<str> = Rc::from(path.display().to_string());
+                    self.push_file(source.as_str(), file, path, input);
+                }
+                Err(e) => self.error(at, format!("cannot read '{}': {}", path.display(), e)),
+            }
+            return;
+        }
+
+        // fall back to the built-in standard headers
+        if let Some(source) = builtin_header(&name) {
+            let path = PathBuf::from(format!("<{}>", name));
+            if self.once.contains(&path) {
+                return;
+            }
+            self.once.insert(path.clone());
+            if !self.headers.contains(&name) {
+                self.headers.push(name.clone());
+            }
+            let file: Rc<str> = Rc::from(path.display().to_string());
+            self.push_file(source, file, path, input);
+            return;
+        }
+
+        self.error(at, format!("'{}' file not found", name));
+    }
+
+
+    // queue the tokens of an included file in front of the remaining input
+    fn push_file(&mut self, source: &str, file: Rc<str>, path: PathBuf, input: &mut VecDeque<PPToken>) {
+        self.check_comments(source, &file);
+        let mut toks = tokenize(source, &file, self.tab_width);
+        toks.push(PPToken {
+            kind: PPKind::EndOfFile,
+            text: String::new(),
+            file,
+            line: 0,
+            col: 0,
+            at_bol: true,
+            space: String::new(),
+            hideset: Rc::new(Vec::new()),
+        });
+        self.include_stack.push((path, self.conds.len()));
+        for t in toks.into_iter().rev() {
+            input.push_front(t);
+        }
+    }
+
+
+    // try to expand `tok` as a macro; on success the expansion is pushed back onto `input`
+    fn expand_macro(&mut self, tok: &PPToken, input: &mut VecDeque<PPToken>) -> bool {
+        if tok.kind != PPKind::Ident || tok.hideset.contains(&tok.text) {
+            return false;
+        }
+
+        // dynamic builtins
+        match tok.text.as_str() {
+            "__LINE__" => {
+                input.push_front(PPToken { kind: PPKind::Number, text: tok.line.to_string(), at_bol: false, ..tok.clone() });
+                return true;
+            }
+            "__FILE__" => {
+                let text = format!("\"{}\"", tok.file.replace('\\', "\\\\").replace('"', "\\\""));
+                input.push_front(PPToken { kind: PPKind::Str, text, at_bol: false, ..tok.clone() });
+                return true;
+            }
+            _ => {}
+        }
+
+        let mac = match self.macros.get(&tok.text) {
+            Some(m) => m.clone(),
+            None => return false,
+        };
+
+        let (body, hideset) = match &mac.params {
+            None => {
+                let mut hs = (*tok.hideset).clone();
+                hs.push(tok.text.clone());
+                (self.substitute(&mac, &[], &[]), hs)
+            }
+            Some(params) => {
+                // a function-like macro name not followed by '(' is just an identifier
+                if !input.front().is_some_and(|t| t.text == "(" && t.kind == PPKind::Punct) {
+                    return false;
+                }
+                input.pop_front();
+                let (args, rparen) = match self.read_args(tok, params, mac.variadic, input) {
+                    Some(r) => r,
+                    None => return true,
+                };
+                let mut hs: Vec<String> = tok.hideset.iter().filter(|n| rparen.hideset.contains(n)).cloned().collect();
+                hs.push(tok.text.clone());
+                (self.substitute(&mac, params, &args), hs)
+            }
+        };
+
+        // expansion tokens take the location of the macro name
+        let hideset = Rc::new(hideset);
+        let mut expanded = Vec::with_capacity(body.len());
+        for (i, t) in body.into_iter().enumerate() {
+            let mut hs = (*hideset).clone();
+            for n in t.hideset.iter() {
+                if !hs.contains(n) {
+                    hs.push(n.clone());
+                }
+            }
+            expanded.push(PPToken {
+                file: tok.file.clone(),
+                line: tok.line,
+                col: tok.col,
+                at_bol: false,
+                space: if i == 0 { tok.space.clone() } else if t.space.is_empty() { String::new() } else { " ".to_string() },
+                hideset: Rc::new(hs),
+                ..t
+            });
+        }
+        for t in expanded.into_iter().rev() {
+            input.push_front(t);
+        }
+        true
+    }
+
+
+    // read the arguments of a function-like macro call, after the '('
+    fn read_args(&mut self, name: &PPToken, params: &[String], variadic: bool, input: &mut VecDeque<PPToken>) -> Option<(Vec<Vec<PPToken>>, PPToken)> {
+        let mut args: Vec<Vec<PPToken>> = vec![Vec::new()];
+        let mut depth = 0;
+        loop {
+            let t = match input.pop_front() {
+                Some(t) if t.kind != PPKind::EndOfFile => t,
+                other => {
+                    if let Some(t) = other {
+                        input.push_front(t);
+                    }
+                    self.error(name, format!("unterminated argument list invoking macro '{}'", name.text));
+                    return None;
+                }
+            };
+            match t.text.as_str() {
+                "(" => depth += 1,
+                ")" if depth == 0 => {
+                    // `F()` passes one empty argument, which is zero arguments for F()
+                    if params.is_empty() && args.len() == 1 && args[0].is_empty() {
+                        args.clear();
+                    }
+                    if variadic && args.len() == params.len() - 1 {
+                        args.push(Vec::new());
+                    }
+                    if args.len() != params.len() {
+                        self.error(name, format!("macro '{}' requires {} arguments, but {} given", name.text, params.len(), args.len()));
+                        return None;
+                    }
+                    return Some((args, t));
+                }
+                ")" => depth -= 1,
+                "," if depth == 0 && !(variadic && args.len() == params.len()) => {
+                    args.push(Vec::new());
+                    continue;
+                }
+                _ => {}
+            }
+            let mut t = t;
+            t.at_bol = false;
+            args.last_mut().unwrap().push(t);
+        }
+    }
+
+
+    // replace parameters in a macro body, handling # and ##
+    fn substitute(&mut self, mac: &Macro, params: &[String], args: &[Vec<PPToken>]) -> Vec<PPToken> {
+        let arg_of = |t: &PPToken| -> Option<&Vec<PPToken>> {
+            if t.kind != PPKind::Ident {
+                return None;
+            }
+            params.iter().position(|p| *p == t.text).map(|i| &args[i])
+        };
+
+        let body = &mac.body;
+        let mut out: Vec<PPToken> = Vec::new();
+        let mut i = 0;
+        while i < body.len() {
+            let t = &body[i];
+            let next = body.get(i + 1);
+
+            // "#param" becomes a string literal of the raw argument
+            if t.text == "#" && mac.params.is_some() {
+                if let Some(arg) = next.and_then(&arg_of) {
+                    let mut s = stringize(arg, t);
+                    s.space = t.space.clone();
+                    out.push(s);
+                    i += 2;
+                    continue;
+                }
+            }
+
+            // "x ## y": paste the last output token with the next operand
+            if t.text == "##" {
+                if let Some(n) = next {
+                    let operand: Vec<PPToken> = match arg_of(n) {
+                        Some(arg) => arg.clone(),
+                        None => vec![n.clone()],
+                    };
+                    let mut rest = operand.into_iter();
+                    if let Some(first) = rest.next() {
+                        match out.pop() {
+                            Some(left) => {
+                                let pasted = self.paste(&left, &first);
+                                out.push(pasted);
+                            }
+                            None => out.push(first),
+                        }
+                    }
+                    out.extend(rest);
+                }
+                i += 2;
+                continue;
+            }
+
+            if let Some(arg) = arg_of(t) {
+                // operands of ## are not macro-expanded
+                let raw = next.is_some_and(|n| n.text == "##");
+                let mut toks = if raw { arg.clone() } else { self.expand_list(arg.clone()) };
+                if raw && toks.is_empty() {
+                    // an empty left operand of ## is a placemarker
+                    toks.push(PPToken { kind: PPKind::Other, text: String::new(), ..t.clone() });
+                }
+                if let Some(first) = toks.first_mut() {
+                    first.space = t.space.clone();
+                }
+                out.extend(toks);
+                i += 1;
+                continue;
+            }
+
+            out.push(t.clone());
+            i += 1;
+        }
+        out
+    }
+
+
+    // glue two tokens together; the result must be a single token
+    fn paste(&mut self, left: &PPToken, right: &PPToken) -> PPToken {
+        if left.text.is_empty() {
+            return PPToken { space: left.space.clone(), ..right.clone() };
+        }
+        if right.text.is_empty() {
+            return left.clone();
+        }
+        let text = format!("{}{}", left.text, right.text);
+        let toks = tokenize(&text, &left.file, self.tab_width);
+        if toks.len() != 1 {
+            self.error(left, format!("pasting \"{}\" and \"{}\" does not give a valid preprocessing token", left.text, right.text));
+            return left.clone();
+        }
+        let t = toks.into_iter().next().unwrap();
+        PPToken { kind: t.kind, text: t.text, ..left.clone() }
+    }
+
+
+    // fully macro-expand a token list (macro arguments, #if lines), no directives inside
+    fn expand_list(&mut self, toks: Vec<PPToken>) -> Vec<PPToken> {
+        let mut input: VecDeque<PPToken> = toks.into();
+        let mut out = Vec::new();
+        while let Some(tok) = input.pop_front() {
+            if !self.expand_macro(&tok, &mut input) {
+                out.push(tok);
+            }
+        }
+        out
+    }
+
+
+    // evaluate the controlling expression of #if / #elif
+    fn eval_condition(&mut self, at: &PPToken, line: Vec<PPToken>) -> bool {
+        // resolve `defined X` and `defined(X)` before expanding anything
+        let mut toks = Vec::new();
+        let mut i = 0;
+        while i < line.len() {
+            let t = &line[i];
+            if t.kind == PPKind::Ident && t.text == "defined" {
+                let (name, used) = match line.get(i + 1) {
+                    Some(p) if p.text == "(" => match (line.get(i + 2), line.get(i + 3)) {
+                        (Some(n), Some(r)) if n.kind == PPKind::Ident && r.text == ")" => (Some(n.text.clone()), 4),
+                        _ => (None, 1),
+                    },
+                    Some(n) if n.kind == PPKind::Ident => (Some(n.text.clone()), 2),
+                    _ => (None, 1),
+                };
+                match name {
+                    Some(name) => {
+                        let value = self.is_defined(&name) || name == "__LINE__" || name == "__FILE__";
+                        toks.push(PPToken { kind: PPKind::Number, text: (value as i64).to_string(), ..t.clone() });
+                    }
+                    None => self.error(t, "operator 'defined' requires an identifier".to_string()),
+                }
+                i += used;
+                continue;
+            }
+            toks.push(t.clone());
+            i += 1;
+        }
+
+        // remaining identifiers after expansion evaluate to 0
+        let toks: Vec<PPToken> = self
+            .expand_list(toks)
+            .into_iter()
+            .map(|t| if t.kind == PPKind::Ident { PPToken { kind: PPKind::Number, text: "0".to_string(), ..t } } else { t })
+            .collect();
+
+        if toks.is_empty() {
+            self.error(at, format!("#{} with no expression", at.text));
+            return false;
+        }
+        let mut parser = CondParser { toks: &toks, pos: 0 };
+        let result = parser.expr().and_then(|v| {
+            if parser.pos < toks.len() {
+                Err(format!("unexpected '{}' in preprocessor expression", toks[parser.pos].text))
+            } else {
+                Ok(v)
+            }
+        });
+        match result {
+            Ok(v) => v != 0,
+            Err(msg) => {
+                self.error(at, msg);
+                false
+            }
+        }
+    }
+}
+
+
+// split source text into preprocessing tokens (after CRLF/BOM/splice normalization)
+fn tokenize(source: &str, file: &Rc<str>, tab_width: usize) -> Vec<PPToken> {
+    let (text, lines) = normalize(source, tab_width);
+    let chars: Vec<char> = text.chars().collect();
+    let offsets: Vec<usize> = text.char_indices().map(|(b, _)| b).collect();
+    let mut toks = Vec::new();
+    let mut i = 0;
+    let mut at_bol = true;
+    let mut space = String::new();
+    let empty_hideset: Rc<Vec<String>> = Rc::new(Vec::new());
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        // whitespace and comments become the spacing of the next token
+        if c == '\n' {
+            i += 1;
+            at_bol = true;
+            space.clear();
+            continue;
+        }
+        if c.is_whitespace() {
+            space.push(c);
+            i += 1;
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            space.push_str("  ");
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    space.clear();
+                } else {
+                    space.push(if chars[i] == '\t' { '\t' } else { ' ' });
+                }
+                i += 1;
+            }
+            space.push_str("  ");
+            i = (i + 2).min(chars.len());
+            continue;
+        }
+
+        let start = i;
+        let kind;
+        if is_ident_char(c) && !c.is_ascii_digit() {
+            while i < chars.len() && (is_ident_char(chars[i]) || (chars[i] == '\\' && matches!(chars.get(i + 1), Some('u') | Some('U')))) {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            let word: String = chars[start..i].iter().collect();
+            // encoding prefixes glue onto string and character literals
+            if matches!(word.as_str(), "L" | "u" | "U" | "u8") && matches!(chars.get(i), Some('"') | Some('\'')) {
+                let quote = chars[i];
+                i = scan_quoted(&chars, i);
+                kind = if quote == '"' { PPKind::Str } else { PPKind::Char };
+            } else {
+                kind = PPKind::Ident;
+            }
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            // pp-number: digits, letters, dots and signed exponents
+            i += 1;
+            while i < chars.len() {
+                let n = chars[i];
+                let signed_exponent = matches!(n, '+' | '-') && matches!(chars[i - 1], 'e' | 'E' | 'p' | 'P');
+                if signed_exponent || n.is_ascii_alphanumeric() || n == '.' || n == '_' {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            kind = PPKind::Number;
+        } else if c == '"' || c == '\'' {
+            i = scan_quoted(&chars, i);
+            kind = if c == '"' { PPKind::Str } else { PPKind::Char };
+        } else if let Some(p) = PUNCTUATORS.iter().find(|p| chars[i..].starts_with(&p.chars().collect::<Vec<_>>())) {
+            i += p.chars().count();
+            kind = PPKind::Punct;
+        } else if c.is_ascii_punctuation() {
+            i += 1;
+            kind = PPKind::Punct;
+        } else {
+            i += 1;
+            kind = PPKind::Other;
+        }
+
+        let pos = lines.position(&text, offsets[start]);
+        toks.push(PPToken {
+            kind,
+            text: chars[start..i].iter().collect(),
+            file: file.clone(),
+            line: pos.line,
+            col: pos.col,
+            at_bol,
+            space: std::mem::take(&mut space),
+            hideset: empty_hideset.clone(),
+        });
+        at_bol = false;
+    }
+    toks
+}
+
+
+// end index of a string or char literal starting at `start`; stops at a newline if unterminated
+fn scan_quoted(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() && chars[i] != '\n' {
+        if chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if chars[i] == quote {
+            return i + 1;
+        }
+        i += 1;
+    }
+    i.min(chars.len())
+}
+
+
+// characters that may appear in identifiers
+fn is_ident_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+
+// the rest of a directive line (until the next token at the beginning of a line)
+fn take_line(input: &mut VecDeque<PPToken>) -> Vec<PPToken> {
+    let mut line = Vec::new();
+    while input.front().is_some_and(|t| !t.at_bol) {
+        line.push(input.pop_front().unwrap());
+    }
+    line
+}
+
+
+// skip a false group up to the matching #elif, #else or #endif (left in the input)
+fn skip_group(input: &mut VecDeque<PPToken>) {
+    let mut depth = 0;
+    while let Some(tok) = input.front() {
+        if tok.kind == PPKind::EndOfFile {
+            return;
+        }
+        if tok.at_bol && tok.text == "#" {
+            if let Some(name) = input.get(1).filter(|t| !t.at_bol) {
+                match name.text.as_str() {
+                    "if" | "ifdef" | "ifndef" => depth += 1,
+                    "elif" | "else" if depth == 0 => return,
+                    "endif" if depth == 0 => return,
+                    "endif" => depth -= 1,
+                    _ => {}
+                }
+            }
+        }
+        input.pop_front();
+    }
+}
+
+
+// turn macro argument tokens into a string literal for the # operator
+fn stringize(arg: &[PPToken], at: &PPToken) -> PPToken {
+    let mut s = String::new();
+    for (i, t) in arg.iter().enumerate() {
+        if i > 0 && !t.space.is_empty() {
+            s.push(' ');
+        }
+        if matches!(t.kind, PPKind::Str | PPKind::Char) {
+            s.push_str(&t.text.replace('\\', "\\\\").replace('"', "\\\""));
+        } else {
+            s.push_str(&t.text);
+        }
+    }
+    PPToken { kind: PPKind::Str, text: format!("\"{}\"", s), ..at.clone() }
+}
+
+
+// join the output tokens back into text, one line per original source line
+fn render(toks: &[PPToken], headers: Vec<String>) -> Preprocessed {
+    let mut text = String::new();
+    let mut lines: Vec<SourceLoc> = Vec::new();
+    let mut current: Option<(Rc<str>, usize)> = None;
+
+    for t in toks {
+        let same_line = current.as_ref().is_some_and(|(f, l)| Rc::ptr_eq(f, &t.file) && *l == t.line);
+        if !same_line {
+            if current.is_some() {
+                text.push('\n');
+            }
+            lines.push(SourceLoc { file: t.file.to_string(), line: t.line });
+            current = Some((t.file.clone(), t.line));
+        }
+        text.push_str(&t.space);
+        text.push_str(&t.text);
+    }
+    if current.is_some() {
+        text.push('\n');
+    }
+    Preprocessed { text, lines, headers }
+}
+
+
+// contents of the standard headers we provide ourselves
+fn builtin_header(name: &str) -> Option<&'static str> {
+    let text = match name {
+        "stdio.h" => "#define EOF (-1)\n#define NULL 0\n#define BUFSIZ 8192\n#define stdin (__stream(0))\n#define stdout (__stream(1))\n#define stderr (__stream(2))\n",
+        "stdlib.h" => "#define NULL 0\n#define EXIT_SUCCESS 0\n#define EXIT_FAILURE 1\n#define RAND_MAX 2147483647\n",
+        "string.h" | "stddef.h" => "#define NULL 0\n",
+        "math.h" => "#define M_PI 3.14159265358979323846\n#define M_E 2.7182818284590452354\n",
+        "ctype.h" => "",
+        "stdbool.h" => "#define bool _Bool\n#define true 1\n#define false 0\n#define __bool_true_false_are_defined 1\n",
+        "limits.h" => concat!(
+            "#define CHAR_BIT 8\n",
+            "#define SCHAR_MIN (-128)\n#define SCHAR_MAX 127\n#define UCHAR_MAX 255\n",
+            "#define CHAR_MIN SCHAR_MIN\n#define CHAR_MAX SCHAR_MAX\n",
+            "#define SHRT_MIN (-32768)\n#define SHRT_MAX 32767\n#define USHRT_MAX 65535\n",
+            "#define INT_MIN (-2147483647 - 1)\n#define INT_MAX 2147483647\n#define UINT_MAX 4294967295U\n",
+            "#define LONG_MIN (-9223372036854775807L - 1)\n#define LONG_MAX 9223372036854775807L\n#define ULONG_MAX 18446744073709551615UL\n",
+            "#define LLONG_MIN (-9223372036854775807LL - 1)\n#define LLONG_MAX 9223372036854775807LL\n#define ULLONG_MAX 18446744073709551615ULL\n",
+        ),
+        _ => return None,
+    };
+    Some(text)
+}
+
+
+// recursive-descent evaluator for #if expressions (intmax_t arithmetic)
+struct CondParser<'a> {
+    toks: &'a [PPToken],
+    pos: usize,
+}
+
+
+impl CondParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.toks.get(self.pos).map(|t| t.text.as_str())
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.peek() == Some(text) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // conditional: a ? b : c
+    fn expr(&mut self) -> Result<i64, String> {
+        let cond = self.binary(0)?;
+        if self.eat("?") {
+            let then = self.expr()?;
+            if !self.eat(":") {
+                return Err("expected ':' in preprocessor expression".to_string());
+            }
+            let other = self.expr()?;
+            return Ok(if cond != 0 { then } else { other });
+        }
+        Ok(cond)
+    }
+
+    // binary operators by precedence level, lowest first
+    fn binary(&mut self, level: usize) -> Result<i64, String> {
+        const LEVELS: &[&[&str]] = &[
+            &["||"], &["&&"], &["|"], &["^"], &["&"], &["==", "!="], &["<", ">", "<=", ">="],
+            &["<<", ">>"], &["+", "-"], &["*", "/", "%"],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = self.peek().filter(|p| LEVELS[level].contains(p)).map(|p| p.to_string()) {
+            self.pos += 1;
+            let right = self.binary(level + 1)?;
+            left = match op.as_str() {
+                "||" => ((left != 0) || (right != 0)) as i64,
+                "&&" => ((left != 0) && (right != 0)) as i64,
+                "|" => left | right,
+                "^" => left ^ right,
+                "&" => left & right,
+                "==" => (left == right) as i64,
+                "!=" => (left != right) as i64,
+                "<" => (left < right) as i64,
+                ">" => (left > right) as i64,
+                "<=" => (left <= right) as i64,
+                ">=" => (left >= right) as i64,
+                "<<" => left.wrapping_shl(right as u32),
+                ">>" => left.wrapping_shr(right as u32),
+                "+" => left.wrapping_add(right),
+                "-" => left.wrapping_sub(right),
+                "*" => left.wrapping_mul(right),
+                _ => {
+                    if right == 0 {
+                        return Err("division by zero in preprocessor expression".to_string());
+                    }
+                    if op == "/" { left.wrapping_div(right) } else { left.wrapping_rem(right) }
+                }
+            };
+        }
+        Ok(left)
+    }
+
+    // unary operators, parentheses and literals
+    fn unary(&mut self) -> Result<i64, String> {
+        let tok = match self.toks.get(self.pos) {
+            Some(t) => t,
+            None => return Err("unexpected end of preprocessor expression".to_string()),
+        };
+        self.pos += 1;
+        match tok.text.as_str() {
+            "+" => self.unary(),
+            "-" => Ok(self.unary()?.wrapping_neg()),
+            "~" => Ok(!self.unary()?),
+            "!" => Ok((self.unary()? == 0) as i64),
+            "(" => {
+                let v = self.expr()?;
+                if !self.eat(")") {
+                    return Err("expected ')' in preprocessor expression".to_string());
+                }
+                Ok(v)
+            }
+            _ if tok.kind == PPKind::Number => parse_pp_int(&tok.text).ok_or_else(|| format!("invalid integer constant '{}' in preprocessor expression", tok.text)),
+            _ if tok.kind == PPKind::Char => Ok(char_value(&tok.text)),
+            other => Err(format!("unexpected '{}' in preprocessor expression", other)),
+        }
+    }
+}
+
+
+// value of an integer pp-number, ignoring u/l suffixes
+fn parse_pp_int(text: &str) -> Option<i64> {
+    let digits = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    let (digits, radix) = if let Some(h) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        (h, 16)
+    } else if let Some(b) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        (b, 2)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (&digits[1..], 8)
+    } else {
+        (digits, 10)
+    };
+    u64::from_str_radix(digits, radix).ok().map(|v| v as i64)
+}
+
+
+// value of a character constant such as 'a' or '\n'
+fn char_value(text: &str) -> i64 {
+    let inner = text.trim_start_matches(['L', 'u', 'U', '8']).trim_matches('\'');
+    let mut chars = inner.chars();
+    match chars.next() {
+        Some('\\') => match chars.next() {
+            Some('n') => 10,
+            Some('t') => 9,
+            Some('r') => 13,
+            Some('a') => 7,
+            Some('b') => 8,
+            Some('f') => 12,
+            Some('v') => 11,
+            Some('x') => i64::from_str_radix(chars.as_str(), 16).unwrap_or(0),
+            Some(d) if d.is_digit(8) => i64::from_str_radix(&inner[1..], 8).unwrap_or(0),
+            Some(c) => c as i64,
+            None => 0,
+        },
+        Some(c) => c as i64,
+        None => 0,
+    }
+}
@@ -0,0 +1,303 @@
+// unparser: turns an AST (parsed or synthesized) back into canonical mini-c source.
+// parentheses are only emitted where precedence or associativity needs them, so
+// printing and parsing again gives back the same tree
+use crate::ast::{BinaryOp, Block, Expr, ExprKind, Function, Program, Stmt, StmtKind, Type, UnaryOp};
+
+
+// indentation used for statements inside a function body
+const INDENT: &str = "    ";
+
+
+// binding strength of each expression form; higher binds tighter
+const PREC_ASSIGN: u8 = 1;
+const PREC_EQUALITY: u8 = 2;
+const PREC_RELATIONAL: u8 = 3;
+const PREC_ADD: u8 = 4;
+const PREC_MUL: u8 = 5;
+const PREC_UNARY: u8 = 6;
+const PREC_PRIMARY: u8 = 7;
+
+
+// the whole program: file-scope assertions, one per line, then the
+// functions separated by a blank line
+pub fn print_program(program: &Program) -> String {
+    let functions = program.functions.iter().map(print_function).collect::<Vec<_>>().join("\n");
+    let asserts: String = program.static_asserts.iter().map(|stmt| print_stmt(stmt) + "\n").collect();
+    asserts + &functions
+}
+
+
+// one function definition; an empty prototyped parameter list is spelled `(void)`
+pub fn print_function(func: &Function) -> String {
+    let params = if !func.prototyped {
+        String::new()
+    } else if func.params.is_empty() {
+        "void".to_string()
+    } else {
+        func.params.iter().map(|(ty, name)| format!("{} {}", ty, name)).collect::<Vec<_>>().join(", ")
+    };
+    format!("{} {}({}) {}\n", func.return_type, func.name, params, print_block(&func.body))
+}
+
+
+// a braced block with one statement per line, nested ones indented further
+pub fn print_block(block: &Block) -> String {
+    let mut out = String::from("{\n");
+    for stmt in &block.stmts {
+        out.push_str(&indent(&print_stmt(stmt)));
+        out.push('\n');
+    }
+    out.push('}');
+    out
+}
+
+
+fn indent(text: &str) -> String {
+    text.lines().map(|line| format!("{}{}", INDENT, line)).collect::<Vec<_>>().join("\n")
+}
+
+
+// the statement controlled by an `if`, `else` or `while`: a block stays on
+// the same line, anything else goes on its own
+fn print_body(stmt: &Stmt) -> String {
+    match &stmt.kind {
+        StmtKind::Block(_) => format!(" {}", print_stmt(stmt)),
+        _ => format!("\n{}", indent(&print_stmt(stmt))),
+    }
+}
+
+
+// whether an `else` right after the statement would belong to an `if` in it
+fn ends_in_open_if(stmt: &Stmt) -> bool {
+    match &stmt.kind {
+        StmtKind::If { otherwise: None, .. } => true,
+        StmtKind::If { otherwise: Some(last), .. } | StmtKind::While { body: last, .. } => ends_in_open_if(last),
+        _ => false,
+    }
+}
+
+
+// one line standing for a statement in diagnostics: the head of an `if` or a
+// `while`, the whole statement otherwise
+pub fn print_stmt_head(stmt: &Stmt) -> String {
+    match &stmt.kind {
+        StmtKind::If { cond, .. } => format!("if ({})", print_expr(cond)),
+        StmtKind::While { cond, .. } => format!("while ({})", print_expr(cond)),
+        StmtKind::Block(_) => "{ ... }".to_string(),
+        _ => print_stmt(stmt),
+    }
+}
+
+
+pub fn print_stmt(stmt: &Stmt) -> String {
+    match &stmt.kind {
+        StmtKind::VarDecl { ty, name, value, is_const } => {
+            let qualifier = if *is_const { "const " } else { "" };
+            match value {
+                Some(value) => format!("{}{} {} = {};", qualifier, ty, name, print_expr(value)),
+                None => format!("{}{} {};", qualifier, ty, name),
+            }
+        }
+        StmtKind::ExprStmt(expr) => format!("{};", print_expr(expr)),
+        StmtKind::Return(Some(expr)) => format!("return {};", print_expr(expr)),
+        StmtKind::Return(None) => "return;".to_string(),
+        StmtKind::StaticAssert { cond, message } => format!("_Static_assert({}, {});", print_expr(cond), quote_str(message)),
+        StmtKind::Empty => ";".to_string(),
+        StmtKind::Block(block) => print_block(block),
+        StmtKind::If { cond, then, otherwise: None } => format!("if ({}){}", print_expr(cond), print_body(then)),
+        // an `else` after an `if` without one would be read as its `else`,
+        // so that `if` goes in braces
+        StmtKind::If { cond, then, otherwise: Some(otherwise) } => {
+            let then = if ends_in_open_if(then) { format!(" {{\n{}\n}}", indent(&print_stmt(then))) } else { print_body(then) };
+            let separator = if then.ends_with('}') { " " } else { "\n" };
+            let otherwise = match otherwise.kind {
+                StmtKind::If { .. } => format!(" {}", print_stmt(otherwise)),
+                _ => print_body(otherwise),
+            };
+            format!("if ({}){}{}else{}", print_expr(cond), then, separator, otherwise)
+        }
+        StmtKind::While { cond, body } => format!("while ({}){}", print_expr(cond), print_body(body)),
+    }
+}
+
+
+pub fn print_expr(expr: &Expr) -> String {
+    let mut out = String::new();
+    write_expr(&mut out, expr, PREC_ASSIGN);
+    out
+}
+
+
+fn precedence(expr: &Expr) -> u8 {
+    match &expr.kind {
+        ExprKind::Assign { .. } => PREC_ASSIGN,
+        ExprKind::Binary { op: BinaryOp::Eq | BinaryOp::Ne, .. } => PREC_EQUALITY,
+        ExprKind::Binary { op: BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Ge, .. } => PREC_RELATIONAL,
+        ExprKind::Binary { op: BinaryOp::Add | BinaryOp::Sub, .. } => PREC_ADD,
+        ExprKind::Binary { op: BinaryOp::Mul | BinaryOp::Div, .. } => PREC_MUL,
+        ExprKind::Unary { .. } => PREC_UNARY,
+        _ => PREC_PRIMARY,
+    }
+}
+
+
+// print `expr` in a context that needs at least precedence `min`
+fn write_expr(out: &mut String, expr: &Expr, min: u8) {
+    let parens = precedence(expr) < min;
+    if parens {
+        out.push('(');
+    }
+    match &expr.kind {
+        ExprKind::Number(n, ty) => out.push_str(&int_literal(*n, ty)),
+        ExprKind::FloatNumber(f, ty) => out.push_str(&float_literal(*f, ty)),
+        ExprKind::CharLiteral(c) => out.push_str(&quote_char(*c)),
+        ExprKind::StringLiteral(s) => out.push_str(&quote_str(s)),
+        ExprKind::Ident(name) => out.push_str(name),
+        ExprKind::Unary { op, expr: operand } => {
+            out.push(match op {
+                UnaryOp::Neg => '-',
+                UnaryOp::Not => '!',
+                UnaryOp::AddrOf => '&',
+            });
+            // keep `- -x` from reading as a decrement
+            let mut inner = String::new();
+            write_expr(&mut inner, operand, PREC_UNARY);
+            if inner.starts_with('-') {
+                inner = format!("({})", inner);
+            }
+            out.push_str(&inner);
+        }
+        ExprKind::Binary { op, left, right } => {
+            // left associative: the right operand must bind strictly tighter
+            let prec = precedence(expr);
+            write_expr(out, left, prec);
+            out.push_str(match op {
+                BinaryOp::Add => " + ",
+                BinaryOp::Sub => " - ",
+                BinaryOp::Mul => " * ",
+                BinaryOp::Div => " / ",
+                BinaryOp::Lt => " < ",
+                BinaryOp::Gt => " > ",
+                BinaryOp::Le => " <= ",
+                BinaryOp::Ge => " >= ",
+                BinaryOp::Eq => " == ",
+                BinaryOp::Ne => " != ",
+            });
+            write_expr(out, right, prec + 1);
+        }
+        ExprKind::Assign { target, value } => {
+            // right associative: only the value may be another assignment
+            write_expr(out, target, PREC_EQUALITY);
+            out.push_str(" = ");
+            write_expr(out, value, PREC_ASSIGN);
+        }
+        ExprKind::Call { name, args } => {
+            out.push_str(name);
+            out.push('(');
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_expr(out, arg, PREC_ASSIGN);
+            }
+            out.push(')');
+        }
+    }
+    if parens {
+        out.push(')');
+    }
+}
+
+
+fn is_unsigned(ty: &Type) -> bool {
+    matches!(ty, Type::UnsignedInt | Type::UnsignedLong | Type::UnsignedLongLong)
+}
+
+
+// integer literal with the suffix that makes the lexer pick `ty` again
+fn int_literal(n: i64, ty: &Type) -> String {
+    let suffix = match ty {
+        Type::UnsignedInt => "u",
+        Type::Long => "l",
+        Type::UnsignedLong => "ul",
+        Type::LongLong => "ll",
+        Type::UnsignedLongLong => "ull",
+        _ => "",
+    };
+    if is_unsigned(ty) {
+        let modulus = ty.int_range().map_or(1i128 << 64, |(_, max)| max + 1);
+        format!("{}{}", (n as i128).rem_euclid(modulus), suffix)
+    } else if n >= 0 {
+        format!("{}{}", n, suffix)
+    } else if ty.int_range().is_some_and(|(min, _)| n as i128 == min) {
+        // C has no negative literals and the minimum has no positive counterpart
+        format!("(-{}{} - 1)", -(n as i128) - 1, suffix)
+    } else {
+        format!("(-{}{})", -(n as i128), suffix)
+    }
+}
+
+
+// shortest text that reads back as the same value, always with a `.` or exponent;
+// negative, infinite and NaN values are written as parenthesized expressions
+fn float_literal(f: f64, ty: &Type) -> String {
+    let literal = |text: String| match ty {
+        Type::Float => text + "f",
+        Type::LongDouble => text + "l",
+        _ => text,
+    };
+    if f.is_nan() {
+        return format!("({} / {})", literal("0.0".to_string()), literal("0.0".to_string()));
+    }
+    let sign = if f.is_sign_negative() { "-" } else { "" };
+    if f.is_infinite() {
+        return format!("({}{} / {})", sign, literal("1.0".to_string()), literal("0.0".to_string()));
+    }
+    let mut text = format!("{:?}", f.abs());
+    if !text.contains(['.', 'e', 'E']) {
+        text.push_str(".0");
+    }
+    if f.is_sign_negative() {
+        format!("(-{})", literal(text))
+    } else {
+        literal(text)
+    }
+}
+
+
+// escape one character of a char or string literal; octal escapes are used for
+// other control characters because they cannot swallow the characters after them
+fn escape_char(c: char, quote: char, out: &mut String) {
+    match c {
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        '\\' => out.push_str("\\\\"),
+        c if c == quote => {
+            out.push('\\');
+            out.push(c);
+        }
+        c if c.is_control() && (c as u32) < 0o400 => out.push_str(&format!("\\{:03o}", c as u32)),
+        c => out.push(c),
+    }
+}
+
+
+// a C string literal, quotes included
+pub fn quote_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        escape_char(c, '"', &mut out);
+    }
+    out.push('"');
+    out
+}
+
+
+// a C character literal, quotes included
+pub fn quote_char(c: char) -> String {
+    let mut out = String::from("'");
+    escape_char(c, '\'', &mut out);
+    out.push('\'');
+    out
+}
@@ -0,0 +1,239 @@
+// Name resolution: every declaration gets a SymbolId and every use of a name
+// (identifier or callee) is bound to one, following C scoping.
+// unlike SymbolTable, the whole scope tree is kept for later queries
+use crate::ast::*;
+use crate::symbol::{FunctionSig, Symbol};
+use std::collections::HashMap;
+
+
+// identity of one declaration, unique within a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(pub u32);
+
+
+// identity of a scope; the global scope is always ScopeId(0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScopeId(pub u32);
+
+
+impl ScopeId {
+    pub const GLOBAL: ScopeId = ScopeId(0);
+}
+
+
+// a declared symbol, where it lives and the node that declares it
+// (the function for functions and params, the statement for locals)
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    pub symbol: Symbol,
+    pub scope: ScopeId,
+    pub node: NodeId,
+}
+
+
+// one scope of the tree; `owner` is the function whose body it is
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub parent: Option<ScopeId>,
+    pub children: Vec<ScopeId>,
+    pub owner: Option<NodeId>,
+    pub symbols: Vec<SymbolId>,         // declared here, in order
+    names: HashMap<String, SymbolId>,   // visible name -> first declaration of it here
+}
+
+
+// result of resolving a program
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    pub declarations: Vec<Declaration>,         // indexed by SymbolId
+    pub scopes: Vec<Scope>,                     // indexed by ScopeId
+    pub bindings: HashMap<NodeId, SymbolId>,    // use site -> what it refers to
+    pub unresolved: Vec<(NodeId, String)>,      // uses with no visible declaration
+    declared_by: HashMap<NodeId, Vec<SymbolId>>, // function / statement -> what it declares
+}
+
+
+impl Resolution {
+    pub fn declaration(&self, id: SymbolId) -> &Declaration {
+        &self.declarations[id.0 as usize]
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id.0 as usize]
+    }
+
+    // the symbol a use site refers to
+    pub fn binding(&self, node: NodeId) -> Option<SymbolId> {
+        self.bindings.get(&node).copied()
+    }
+
+    // every use of a symbol, in source order
+    pub fn references(&self, id: SymbolId) -> Vec<NodeId> {
+        let mut uses: Vec<NodeId> = self.bindings.iter().filter(|(_, &s)| s == id).map(|(&n, _)| n).collect();
+        uses.sort();
+        uses
+    }
+
+    // the function symbol of a function, or the variable declared by a statement
+    pub fn declared_by(&self, node: NodeId) -> Option<SymbolId> {
+        self.declared_by.get(&node).and_then(|ids| ids.first().copied())
+    }
+
+    // the parameter symbols of a function, in order
+    pub fn params_of(&self, func: NodeId) -> &[SymbolId] {
+        self.declared_by.get(&func).map_or(&[], |ids| ids.get(1..).unwrap_or(&[]))
+    }
+
+    // what `name` means in `scope`, searching outwards
+    pub fn lookup(&self, scope: ScopeId, name: &str) -> Option<SymbolId> {
+        let mut current = Some(scope);
+        while let Some(id) = current {
+            let scope = self.scope(id);
+            if let Some(&sym) = scope.names.get(name) {
+                return Some(sym);
+            }
+            current = scope.parent;
+        }
+        None
+    }
+
+    // the function whose body (or parameter list) a scope belongs to
+    pub fn function_of(&self, scope: ScopeId) -> Option<NodeId> {
+        let mut current = Some(scope);
+        while let Some(id) = current {
+            let scope = self.scope(id);
+            if scope.owner.is_some() {
+                return scope.owner;
+            }
+            current = scope.parent;
+        }
+        None
+    }
+
+    // a name for the symbol that no other object of the same function shares:
+    // its own name unless it is shadowed or reused, then suffixed with its id
+    pub fn unique_name(&self, id: SymbolId) -> String {
+        let decl = self.declaration(id);
+        let name = decl.symbol.name();
+        let func = self.function_of(decl.scope);
+        let shared = self.declarations.iter().enumerate().any(|(i, d)| {
+            i != id.0 as usize && d.symbol.name() == name && !matches!(d.symbol, Symbol::Function(_)) && self.function_of(d.scope) == func
+        });
+        if shared {
+            format!("{}.{}", name, id.0)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+
+// resolve every name in the program
+pub fn resolve(program: &Program) -> Resolution {
+    let mut resolver = Resolver {
+        res: Resolution {
+            declarations: Vec::new(),
+            scopes: vec![Scope { parent: None, children: Vec::new(), owner: None, symbols: Vec::new(), names: HashMap::new() }],
+            bindings: HashMap::new(),
+            unresolved: Vec::new(),
+            declared_by: HashMap::new(),
+        },
+        current: ScopeId::GLOBAL,
+    };
+    resolver.visit_program(program);
+    resolver.res
+}
+
+
+struct Resolver {
+    res: Resolution,
+    current: ScopeId,
+}
+
+
+impl Resolver {
+    // add a declaration to the current scope; a redeclaration in the same scope
+    // gets its own id but later uses keep seeing the first one
+    fn declare(&mut self, symbol: Symbol, node: NodeId) -> SymbolId {
+        let id = SymbolId(self.res.declarations.len() as u32);
+        let scope = &mut self.res.scopes[self.current.0 as usize];
+        scope.names.entry(symbol.name().to_string()).or_insert(id);
+        scope.symbols.push(id);
+        self.res.declared_by.entry(node).or_default().push(id);
+        self.res.declarations.push(Declaration { symbol, scope: self.current, node });
+        id
+    }
+
+    fn enter_scope(&mut self, owner: Option<NodeId>) {
+        let id = ScopeId(self.res.scopes.len() as u32);
+        self.res.scopes.push(Scope { parent: Some(self.current), children: Vec::new(), owner, symbols: Vec::new(), names: HashMap::new() });
+        self.res.scopes[self.current.0 as usize].children.push(id);
+        self.current = id;
+    }
+
+    fn leave_scope(&mut self) {
+        self.current = self.res.scope(self.current).parent.unwrap_or(ScopeId::GLOBAL);
+    }
+
+    fn bind(&mut self, node: NodeId, name: &str) {
+        match self.res.lookup(self.current, name) {
+            Some(id) => {
+                self.res.bindings.insert(node, id);
+            }
+            None => self.res.unresolved.push((node, name.to_string())),
+        }
+    }
+}
+
+
+impl Visitor for Resolver {
+    // functions are visible everywhere, so they are all declared before any body
+    fn visit_program(&mut self, program: &Program) {
+        for func in &program.functions {
+            let sig = FunctionSig {
+                name: func.name.clone(),
+                return_type: func.return_type.clone(),
+                params_types: func.params.iter().map(|(t, _)| t.clone()).collect(),
+                prototyped: func.prototyped,
+                variadic: false,
+            };
+            self.declare(Symbol::Function(sig), func.id);
+        }
+        walk_program(self, program);
+    }
+
+    // params share the scope of the function body
+    fn visit_function(&mut self, func: &Function) {
+        self.enter_scope(Some(func.id));
+        for (ty, name) in &func.params {
+            self.declare(Symbol::Param { name: name.clone(), ty: ty.clone() }, func.id);
+        }
+        walk_function(self, func);
+        self.leave_scope();
+    }
+
+    // a local is in scope from its declarator on, its own initializer included,
+    // to the end of the block declaring it
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::VarDecl { ty, name, is_const, .. } => {
+                self.declare(Symbol::Variable { name: name.clone(), ty: ty.clone(), is_const: *is_const }, stmt.id);
+                walk_stmt(self, stmt);
+            }
+            StmtKind::Block(_) => {
+                self.enter_scope(None);
+                walk_stmt(self, stmt);
+                self.leave_scope();
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Ident(name) | ExprKind::Call { name, .. } => self.bind(expr.id, name),
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
@@ -1,21 +1,173 @@
 // A simple semantic analysis pass for Mini-C.
 use crate::ast::*;
-use crate::symbol::{SymbolTable, FunctionSig};
+use crate::consteval::{self, ConstError};
+use crate::flow;
+use crate::format;
+use crate::prelude;
+use crate::printer::{print_expr, print_stmt_head};
+use crate::resolve::{resolve, Resolution};
+use crate::standard::Standard;
+use crate::symbol::{SymbolTable, FunctionSig, Symbol};
+use crate::token::Token;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
+pub use crate::consteval::ConstValue;
+
 
 
 // An enumeration of possible semantic errors.
 #[derive(Debug, Clone)]
 pub enum SemanticError {
     DuplicateFunction { name: String },
+    ConflictingTypes { name: String, library: String },
+    MissingMain,
+    InvalidMain { found: String },
     DuplicateParam { func: String, name: String },
     DuplicateVariable { func: String, name: String },
     UndeclaredVariable { func: String, name: String },
     WrongArgCount { func: String, name: String, expected: usize, found: usize },
+    TooFewArgs { func: String, name: String, min: usize, found: usize },
     TypeMismatch { func: String, expected: Type, found: Type },
     ReturnTypeMismatch { func: String, expected: Type, found: Type },
-    // future: add TypeMismatch, ReturnMissing, etc.
+    ReservedKeyword { func: String, name: String },
+    ReturnMissing { func: String, expected: Type },
+    ReturnValueInVoid { func: String },
+    ReturnWithoutValue { func: String, expected: Type },
+    ArgTypeMismatch { func: String, name: String, index: usize, expected: Type, found: Type },
+    NotAFunction { func: String, name: String },
+    VoidValueUsed { func: String, name: String },
+    NotAnLvalue { func: String, expr: String },
+    AddressOfNonVariable { func: String, expr: String },
+    AssignToConst { func: String, name: String },
+    AssignToFunction { func: String, name: String },
+    UninitializedRead { func: String, name: String, decl: String, used: String },
+    StaticAssertFailed { message: String, expr: String, evaluated: String },
+    StaticAssertNotConstant { expr: String },
+    StaticAssertUndefined { expr: String, error: ConstError },
+}
+
+
+// problems that do not stop compilation; `node` is where to report them
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticWarning {
+    UnreachableCode { func: String, stmt: String, node: NodeId },
+    MaybeUninitialized { func: String, name: String, decl: String, used: String, node: NodeId },
+    Unsequenced { func: String, name: String, stmt: String, node: NodeId },
+    UnusedVariable { func: String, name: String, node: NodeId },
+    UnusedParameter { func: String, name: String, node: NodeId },
+    UnusedFunction { name: String, node: NodeId },
+    Shadow { func: String, name: String, shadowed: String, node: NodeId, previous: NodeId },
+    Conversion { func: String, from: Type, to: Type, node: NodeId },
+    TautologicalCompare { func: String, expr: String, always: bool, node: NodeId },
+    ImplicitFunctionDeclaration { func: String, name: String, node: NodeId },
+    Overflow { func: String, expr: String, ty: Type, node: NodeId },
+    DivisionByZero { func: String, expr: String, node: NodeId },
+    FormatInvalid { func: String, name: String, spec: String, node: NodeId },
+    FormatArgCount { func: String, name: String, expected: usize, found: usize, node: NodeId },
+    FormatArgType { func: String, name: String, spec: String, index: usize, expected: String, found: String, node: NodeId },
+}
+
+
+impl SemanticWarning {
+    // the name of the warning in -W flags and allow comments (see warnings::WARNINGS)
+    pub fn name(&self) -> &'static str {
+        match self {
+            SemanticWarning::UnreachableCode { .. } => "unreachable-code",
+            SemanticWarning::MaybeUninitialized { .. } => "maybe-uninitialized",
+            SemanticWarning::Unsequenced { .. } => "unsequenced",
+            SemanticWarning::UnusedVariable { .. } => "unused-variable",
+            SemanticWarning::UnusedParameter { .. } => "unused-parameter",
+            SemanticWarning::UnusedFunction { .. } => "unused-function",
+            SemanticWarning::Shadow { .. } => "shadow",
+            SemanticWarning::Conversion { .. } => "conversion",
+            SemanticWarning::TautologicalCompare { .. } => "tautological-compare",
+            SemanticWarning::ImplicitFunctionDeclaration { .. } => "implicit-function-declaration",
+            SemanticWarning::Overflow { .. } => "overflow",
+            SemanticWarning::DivisionByZero { .. } => "div-by-zero",
+            SemanticWarning::FormatInvalid { .. }
+            | SemanticWarning::FormatArgCount { .. }
+            | SemanticWarning::FormatArgType { .. } => "format",
+        }
+    }
+
+    pub fn node(&self) -> NodeId {
+        match self {
+            SemanticWarning::UnreachableCode { node, .. }
+            | SemanticWarning::MaybeUninitialized { node, .. }
+            | SemanticWarning::Unsequenced { node, .. }
+            | SemanticWarning::UnusedVariable { node, .. }
+            | SemanticWarning::UnusedParameter { node, .. }
+            | SemanticWarning::UnusedFunction { node, .. }
+            | SemanticWarning::Shadow { node, .. }
+            | SemanticWarning::Conversion { node, .. }
+            | SemanticWarning::TautologicalCompare { node, .. }
+            | SemanticWarning::ImplicitFunctionDeclaration { node, .. }
+            | SemanticWarning::Overflow { node, .. }
+            | SemanticWarning::DivisionByZero { node, .. }
+            | SemanticWarning::FormatInvalid { node, .. }
+            | SemanticWarning::FormatArgCount { node, .. }
+            | SemanticWarning::FormatArgType { node, .. } => *node,
+        }
+    }
+
+    // the earlier declaration the warning is about, to point at as well
+    pub fn previous(&self) -> Option<NodeId> {
+        match self {
+            SemanticWarning::Shadow { previous, .. } => Some(*previous),
+            _ => None,
+        }
+    }
+}
+
+
+// what an expression designates when it appears left of `=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lvalue {
+    Modifiable, // a local or parameter
+    Const,      // an object declared `const`
+    Function,   // a function designator
+    NotLvalue,  // a computed value such as `a + 1` or `f()`
+}
+
+
+// what analysis learned about the nodes of a program, keyed by their ids,
+// so later passes look results up instead of recomputing them
+#[derive(Debug, Clone)]
+pub struct SideTables {
+    pub types: HashMap<NodeId, Type>,        // every expression whose type is known
+    pub resolution: Resolution,              // what identifiers, assignment targets and callees refer to
+    pub consts: HashMap<NodeId, ConstValue>, // expressions made only of literals and arithmetic
+    pub warnings: Vec<SemanticWarning>,
+}
+
+
+impl SideTables {
+    pub fn type_of(&self, expr: &Expr) -> Option<&Type> {
+        self.types.get(&expr.id)
+    }
+
+    pub fn symbol_of(&self, expr: &Expr) -> Option<&Symbol> {
+        let id = self.resolution.binding(expr.id)?;
+        Some(&self.resolution.declaration(id).symbol)
+    }
+
+    pub fn const_of(&self, expr: &Expr) -> Option<ConstValue> {
+        self.consts.get(&expr.id).copied()
+    }
+
+    // what an expression designates as the target of an assignment; names that
+    // did not resolve count as modifiable so they are only reported once
+    pub fn lvalue_of(&self, expr: &Expr) -> Lvalue {
+        match &expr.kind {
+            ExprKind::Ident(_) => match self.symbol_of(expr) {
+                Some(Symbol::Variable { is_const: true, .. }) => Lvalue::Const,
+                Some(Symbol::Variable { .. } | Symbol::Param { .. }) | None => Lvalue::Modifiable,
+                Some(Symbol::Function(_)) => Lvalue::Function,
+            },
+            _ => Lvalue::NotLvalue,
+        }
+    }
 }
 
 
@@ -28,25 +180,91 @@ impl fmt::Display for SemanticError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             SemanticError::DuplicateFunction { name } => write!(f, "Duplicate function '{}'.", name),
+            SemanticError::MissingMain => write!(f, "No 'main' function: an executable needs an entry point."),
+            SemanticError::InvalidMain { found } => write!(f, "'main' must be declared as `int main(void)` or `int main(int argc, char **argv)`, found `{}`.", found),
+            SemanticError::ConflictingTypes { name, library } => write!(f, "Conflicting types for '{}': the standard library declares `{}`.", name, library),
             SemanticError::DuplicateParam { func, name } => write!(f, "Duplicate parameter '{}' in function '{}'.", name, func),
             SemanticError::DuplicateVariable { func, name } => write!(f, "Duplicate variable '{}' in function '{}'.", name, func),
             SemanticError::UndeclaredVariable { func, name } => write!(f, "Undeclared variable '{}' in function '{}'.", name, func),
             SemanticError::WrongArgCount { func, name, expected, found } => write!(f, "Wrong argument count for call to '{}' in function '{}': expected {}, found {}.", name, func, expected, found),
+            SemanticError::TooFewArgs { func, name, min, found } => write!(f, "Too few arguments for call to '{}' in function '{}': expected at least {}, found {}.", name, func, min, found),
             SemanticError::TypeMismatch { func, expected, found } => write!(f, "Type mismatch in function '{}': expected {:?}, found {:?}.", func, expected, found),
             SemanticError::ReturnTypeMismatch { func, expected, found } => write!(f, "Return type mismatch in function '{}': expected {:?}, found {:?}.", func, expected, found),
+            SemanticError::ReservedKeyword { func, name } => write!(f, "'{}' is a reserved keyword and cannot be used as an identifier (in function '{}').", name, func),
+            SemanticError::ReturnMissing { func, expected } => write!(f, "Control reaches the end of non-void function '{}' without returning a value of type {}.", func, expected),
+            SemanticError::ReturnValueInVoid { func } => write!(f, "Return with a value in void function '{}'.", func),
+            SemanticError::ReturnWithoutValue { func, expected } => write!(f, "Return without a value in function '{}' returning {}.", func, expected),
+            SemanticError::ArgTypeMismatch { func, name, index, expected, found } => write!(f, "Argument {} of call to '{}' in function '{}' has type {}, expected {}.", index, name, func, found, expected),
+            SemanticError::NotAFunction { func, name } => write!(f, "Called object '{}' in function '{}' is not a function.", name, func),
+            SemanticError::VoidValueUsed { func, name } => write!(f, "Result of void function '{}' used as a value in function '{}'.", name, func),
+            SemanticError::NotAnLvalue { func, expr } => write!(f, "Cannot assign to '{}' in function '{}': not an lvalue.", expr, func),
+            SemanticError::AddressOfNonVariable { func, expr } => write!(f, "Cannot take the address of '{}' in function '{}': only variables have one.", expr, func),
+            SemanticError::AssignToConst { func, name } => write!(f, "Cannot assign to const variable '{}' in function '{}'.", name, func),
+            SemanticError::AssignToFunction { func, name } => write!(f, "Cannot assign to function '{}' in function '{}'.", name, func),
+            SemanticError::StaticAssertFailed { message, expr, evaluated } => write!(f, "Static assertion failed: \"{}\": `{}` evaluates to {}.", message, expr, evaluated),
+            SemanticError::StaticAssertNotConstant { expr } => write!(f, "Static assertion condition `{}` is not an integer constant expression.", expr),
+            SemanticError::StaticAssertUndefined { expr, error } => write!(f, "Static assertion condition `{}` is not an integer constant expression: {}.", expr, error),
+            SemanticError::UninitializedRead { func, name, decl, used } => write!(f, "Variable '{}' is read before it is assigned in function '{}': declared by `{}`, read in `{}`.", name, func, decl, used),
+        }
+    }
+}
+
+
+impl fmt::Display for SemanticWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemanticWarning::UnreachableCode { func, stmt, .. } => write!(f, "Unreachable code in function '{}': `{}` follows a return.", func, stmt),
+            SemanticWarning::UnusedVariable { func, name, .. } => write!(f, "Unused variable '{}' in function '{}'.", name, func),
+            SemanticWarning::UnusedParameter { func, name, .. } => write!(f, "Unused parameter '{}' in function '{}'.", name, func),
+            SemanticWarning::UnusedFunction { name, .. } => write!(f, "Function '{}' is never called.", name),
+            SemanticWarning::Shadow { func, name, shadowed, .. } => write!(f, "Declaration of '{}' in function '{}' shadows {}.", name, func, shadowed),
+            SemanticWarning::Conversion { func, from, to, .. } => write!(f, "Implicit conversion from {} to {} in function '{}' may change the value.", from, to, func),
+            SemanticWarning::TautologicalCompare { func, expr, always, .. } => write!(f, "Comparison `{}` in function '{}' is always {}.", expr, func, always),
+            SemanticWarning::MaybeUninitialized { func, name, decl, used, .. } => write!(f, "Variable '{}' may be read before it is assigned in function '{}': declared by `{}`, read in `{}`.", name, func, decl, used),
+            SemanticWarning::Unsequenced { func, name, stmt, .. } => write!(f, "Unsequenced accesses to '{}' in function '{}': `{}` assigns it in one operand and uses it in another, which is undefined behaviour.", name, func, stmt),
+            SemanticWarning::ImplicitFunctionDeclaration { func, name, .. } => write!(f, "Implicit declaration of function '{}' in function '{}' is not allowed since C99.", name, func),
+            SemanticWarning::Overflow { func, expr, ty, .. } => write!(f, "Integer overflow in `{}` of type {} in function '{}'.", expr, ty, func),
+            SemanticWarning::DivisionByZero { func, expr, .. } => write!(f, "Division by zero in `{}` in function '{}'.", expr, func),
+            SemanticWarning::FormatInvalid { func, name, spec, .. } => write!(f, "Invalid conversion `{}` in the format of call to '{}' in function '{}'.", spec, name, func),
+            SemanticWarning::FormatArgCount { func, name, expected, found, .. } => write!(f, "Format of call to '{}' in function '{}' expects {} arguments, found {}.", name, func, expected, found),
+            SemanticWarning::FormatArgType { func, name, spec, index, expected, found, .. } => write!(f, "Conversion `{}` in call to '{}' in function '{}' expects {}, but argument {} has type {}.", spec, name, func, expected, index, found),
         }
     }
 }
 
 
 
-// Analyze the program for semantic errors
-pub fn analyze(program: &Program) -> SemResult<()> {
+// what the analyzed program is for
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisOptions {
+    pub headers: Vec<String>, // standard headers included, whose library functions are declared (see prelude)
+    pub executable: bool,     // the program must define `main`
+    pub standard: Standard,   // C89 still allows calls to undeclared functions
+}
+
+
+// Analyze the program for semantic errors; on success the side tables describe every node
+pub fn analyze(program: &Program) -> SemResult<SideTables> {
+    analyze_with(program, &AnalysisOptions::default())
+}
+
+
+pub fn analyze_with(program: &Program, options: &AnalysisOptions) -> SemResult<SideTables> {
     let mut errors: Vec<SemanticError> = Vec::new();
     let mut symbols = SymbolTable::new();
 
     // collect function signatures and check duplicate params
     for func in &program.functions {
+        // names must not be keywords
+        if Token::is_keyword(&func.name) {
+            errors.push(SemanticError::ReservedKeyword { func: func.name.clone(), name: func.name.clone() });
+        }
+        for (_, pname) in &func.params {
+            if Token::is_keyword(pname) {
+                errors.push(SemanticError::ReservedKeyword { func: func.name.clone(), name: pname.clone() });
+            }
+        }
+
         // check duplicate params within the function -> just compare parameter names
         for i in 0..func.params.len() {
             for j in (i + 1)..func.params.len() {
@@ -62,6 +280,8 @@ pub fn analyze(program: &Program) -> SemResult<()> {
             name: func.name.clone(),
             return_type: func.return_type.clone(),
             params_types: func.params.iter().map(|(t, _)| t.clone()).collect(),
+            prototyped: func.prototyped,
+            variadic: false,
         };
 
 
@@ -71,174 +291,599 @@ pub fn analyze(program: &Program) -> SemResult<()> {
         }
     }
 
-    // analyze each function body using proper scopes
-    for func in &program.functions {
-        symbols.enter_scope();
-        // declare params in the new function scope
-        for (t, pname) in &func.params {
-            if let Err(_) = symbols.declare_param(pname, t.clone()) {
-                errors.push(SemanticError::DuplicateParam { func: func.name.clone(), name: pname.clone() });
+    // the program may define a library function itself, but only with the
+    // library's signature; `()` leaves the parameters open
+    for lib in prelude::prelude(&options.headers) {
+        match symbols.find_global_function(&lib.name) {
+            Some(own) => {
+                if own.return_type != lib.return_type || (own.prototyped && own.params_types != lib.params_types) {
+                    errors.push(SemanticError::ConflictingTypes { name: lib.name.clone(), library: lib.to_string() });
+                }
+            }
+            None => {
+                let _ = symbols.declare_global_function(lib);
             }
         }
+    }
 
-        // walk statements and use symbol table for locals
-        for stmt in &func.body.stmts {
-            analyze_stmt(stmt, &mut symbols, &mut errors, &func.name);
+    // the entry point takes no arguments or the command line, and returns int
+    match symbols.find_global_function("main") {
+        Some(main) => {
+            let argv = Type::Pointer(Box::new(Type::Pointer(Box::new(Type::Char))));
+            let valid_params = main.params_types.is_empty() || main.params_types == [Type::Int, argv];
+            if main.return_type != Type::Int || !valid_params {
+                errors.push(SemanticError::InvalidMain { found: main.to_string() });
+            }
         }
+        None if options.executable => errors.push(SemanticError::MissingMain),
+        None => {}
+    }
 
+    // analyze each function body using proper scopes
+    let tables = SideTables { types: HashMap::new(), resolution: resolve(program), consts: HashMap::new(), warnings: Vec::new() };
+    let mut analyzer = Analyzer { symbols, errors, func_name: String::new(), return_type: Type::Void, standard: options.standard, implicitly_declared: HashSet::new(), const_errors: None, tables };
+    analyzer.visit_program(program);
 
-        symbols.leave_scope();
+    // every function but main should be called from somewhere
+    for func in &program.functions {
+        let res = &analyzer.tables.resolution;
+        if func.name != "main" && res.declared_by(func.id).is_some_and(|id| res.references(id).is_empty()) {
+            analyzer.tables.warnings.push(SemanticWarning::UnusedFunction { name: func.name.clone(), node: func.id });
+        }
     }
 
     // return all errors found
-    if errors.is_empty() {
-        Ok(())
+    if analyzer.errors.is_empty() {
+        Ok(analyzer.tables)
     } else {
-        Err(errors)
+        Err(analyzer.errors)
     }
 }
 
 
-// Analyze a statement for semantic errors.
-fn analyze_stmt(stmt: &Stmt, symbols: &mut SymbolTable, errors: &mut Vec<SemanticError>, func_name: &str) {
-    match stmt {
-        Stmt::VarDecl { ty, name, value } => {
-            // check duplicate in current scope
-            if let Err(_) = symbols.declare_local_var(name, ty.clone()) {
-                errors.push(SemanticError::DuplicateVariable { func: func_name.to_string(), name: name.clone() });
-            } else {
-                analyze_expr(value, symbols, errors, func_name);
-                // type check initializer
-                if let Some(vt) = expr_type(value, symbols) {
-                    if vt != *ty {
-                        errors.push(SemanticError::TypeMismatch { func: func_name.to_string(), expected: ty.clone(), found: vt });
+// walks the function bodies, keeping the scopes, the errors found so far
+// and the side tables being filled in
+struct Analyzer {
+    symbols: SymbolTable,
+    errors: Vec<SemanticError>,
+    func_name: String,
+    return_type: Type,
+    standard: Standard,
+    implicitly_declared: HashSet<String>, // undeclared callees, reported at their first call
+    const_errors: Option<Vec<ConstError>>, // collected instead of warned about inside a static assertion
+    tables: SideTables,
+}
+
+
+impl Visitor for Analyzer {
+    // each function body gets its own scope holding the params; once the body
+    // is checked, its control flow is too
+    fn visit_function(&mut self, func: &Function) {
+        self.func_name = func.name.clone();
+        self.return_type = func.return_type.clone();
+        self.symbols.enter_scope();
+        for (t, pname) in &func.params {
+            if self.symbols.declare_param(pname, t.clone()).is_err() {
+                self.errors.push(SemanticError::DuplicateParam { func: func.name.clone(), name: pname.clone() });
+            }
+        }
+        walk_function(self, func);
+        self.symbols.leave_scope();
+        self.check_flow(func);
+        self.check_usage(func);
+    }
+
+
+    // Analyze a statement for semantic errors.
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        let func_name = self.func_name.clone();
+        match &stmt.kind {
+            StmtKind::VarDecl { ty, name, value, is_const } => {
+                if Token::is_keyword(name) {
+                    self.errors.push(SemanticError::ReservedKeyword { func: func_name.clone(), name: name.clone() });
+                }
+                // check duplicate in current scope
+                if self.symbols.declare_local_var(name, ty.clone(), *is_const).is_err() {
+                    self.errors.push(SemanticError::DuplicateVariable { func: func_name, name: name.clone() });
+                } else if let Some(value) = value {
+                    self.visit_expr(value);
+                    // type check initializer, once it is known to have a value
+                    let vt = if self.check_value(value) { self.tables.type_of(value).cloned() } else { None };
+                    if let Some(vt) = vt {
+                        if !compatible(ty, &vt) {
+                            self.errors.push(SemanticError::TypeMismatch { func: func_name, expected: ty.clone(), found: vt });
+                        } else {
+                            self.check_conversion(ty, value);
+                        }
                     }
                 }
             }
-        }
 
 
-        // expression statement: analyze expression
-        Stmt::ExprStmt(expr) => analyze_expr(expr, symbols, errors, func_name),
-        Stmt::Return(expr) => {
-            analyze_expr(expr, symbols, errors, func_name);
-            // check return type against function signature
-            if let Some(sig) = symbols.find_global_function(func_name) {
-                if let Some(rt) = expr_type(expr, symbols) {
-                    if rt != sig.return_type {
-                        errors.push(SemanticError::ReturnTypeMismatch { func: func_name.to_string(), expected: sig.return_type.clone(), found: rt });
+            // return: a value exactly when the function has a non-void return type,
+            // and one of a compatible type
+            StmtKind::Return(expr) => {
+                walk_stmt(self, stmt);
+                let expected = self.return_type.clone();
+                match expr {
+                    Some(_) if expected == Type::Void => self.errors.push(SemanticError::ReturnValueInVoid { func: func_name }),
+                    Some(expr) if !self.check_value(expr) => {}
+                    Some(expr) => {
+                        if let Some(rt) = self.tables.type_of(expr) {
+                            if !compatible(&expected, rt) {
+                                self.errors.push(SemanticError::ReturnTypeMismatch { func: func_name, expected, found: rt.clone() });
+                            } else {
+                                self.check_conversion(&expected, expr);
+                            }
+                        }
                     }
+                    None if expected != Type::Void => self.errors.push(SemanticError::ReturnWithoutValue { func: func_name, expected }),
+                    None => {}
                 }
             }
-        }
-    }
-}
 
+            // expression statement: analyze expression
+            StmtKind::ExprStmt(_) => walk_stmt(self, stmt),
 
+            StmtKind::Empty => {}
 
-// Analyze an expression for semantic errors.
-fn analyze_expr(expr: &Expr, symbols: &SymbolTable, errors: &mut Vec<SemanticError>, func_name: &str) {
-    match expr {
-    Expr::Number(_) => {}
-    Expr::FloatNumber(_) => {}
-    Expr::CharLiteral(_) => {}
-    Expr::StringLiteral(_) => {}
+            // a block is a scope of its own
+            StmtKind::Block(_) => {
+                self.symbols.enter_scope();
+                walk_stmt(self, stmt);
+                self.symbols.leave_scope();
+            }
 
+            // a condition must have a value
+            StmtKind::If { cond, .. } | StmtKind::While { cond, .. } => {
+                self.visit_expr(cond);
+                self.check_value(cond);
+                for sub in stmt.substatements() {
+                    self.visit_stmt(sub);
+                }
+            }
 
-        // identifier: check declared
-        Expr::Ident(name) => {
-            if symbols.lookup(name).is_none() {
-                errors.push(SemanticError::UndeclaredVariable { func: func_name.to_string(), name: name.clone() });
+            // undefined arithmetic in the condition leaves it without a value,
+            // which makes the assertion an error rather than a warning
+            StmtKind::StaticAssert { cond, message } => {
+                self.const_errors = Some(Vec::new());
+                walk_stmt(self, stmt);
+                let errors = self.const_errors.take().unwrap_or_default();
+                match errors.into_iter().next() {
+                    Some(error) => self.errors.push(SemanticError::StaticAssertUndefined { expr: print_expr(cond), error }),
+                    None => self.check_static_assert(cond, message),
+                }
             }
         }
+    }
 
 
-        // unary operation: analyze sub-expression
-        Expr::Unary { op: _, expr } => analyze_expr(expr, symbols, errors, func_name),
-        Expr::Binary { op: _, left, right } => {
-            analyze_expr(left, symbols, errors, func_name);
-            analyze_expr(right, symbols, errors, func_name);
+    // Analyze an expression for semantic errors; children are done first,
+    // so their entries in the side tables are available here
+    fn visit_expr(&mut self, expr: &Expr) {
+        // identifiers must be declared
+        if let ExprKind::Ident(name) = &expr.kind {
+            if self.tables.resolution.binding(expr.id).is_none() {
+                self.errors.push(SemanticError::UndeclaredVariable { func: self.func_name.clone(), name: name.clone() });
+            }
         }
 
+        walk_expr(self, expr);
 
-        // assignment: check variable declared, analyze value
-        Expr::Assign { name, value } => {
-            // check variable declared
-            if symbols.lookup(name).is_none() {
-                errors.push(SemanticError::UndeclaredVariable { func: func_name.to_string(), name: name.clone() });
+        // operands must have a value
+        match &expr.kind {
+            ExprKind::Unary { expr: operand, .. } | ExprKind::Assign { value: operand, .. } => {
+                self.check_value(operand);
             }
-            analyze_expr(value, symbols, errors, func_name);
+            ExprKind::Binary { left, right, .. } => {
+                self.check_value(left);
+                self.check_value(right);
+            }
+            ExprKind::Call { args, .. } => {
+                for arg in args {
+                    self.check_value(arg);
+                }
+            }
+            _ => {}
         }
 
+        if let ExprKind::Call { name, args } = &expr.kind {
+            self.check_call(name, args, expr);
+        }
+        if let ExprKind::Assign { target, value } = &expr.kind {
+            self.check_assignment(target, value);
+        }
+        if let ExprKind::Unary { op: UnaryOp::AddrOf, expr: operand } = &expr.kind {
+            if !matches!(self.tables.lvalue_of(operand), Lvalue::Modifiable | Lvalue::Const) {
+                self.errors.push(SemanticError::AddressOfNonVariable { func: self.func_name.clone(), expr: print_expr(operand) });
+            }
+        }
+        if let ExprKind::Binary { op, left, right } = &expr.kind {
+            if op.is_comparison() {
+                self.check_comparison(expr, *op, left, right);
+            }
+        }
 
+        if let Some(ty) = self.expr_type(expr) {
+            self.tables.types.insert(expr.id, ty);
+        }
 
-        
-    Expr::Call { name, args: _args } => {
-            // analyze args
-            for a in _args {
-                analyze_expr(a, symbols, errors, func_name);
+        // constants are folded from their operands' values; an operation
+        // without a value is reported and leaves the expression to run time
+        let folded = consteval::eval_node(expr, &|e| self.tables.type_of(e).cloned(), |e| self.tables.const_of(e));
+        let func = self.func_name.clone();
+        match folded {
+            Ok(Some(value)) => {
+                self.tables.consts.insert(expr.id, value);
             }
-            // check arity if function known
-            if let Some(sig) = symbols.find_global_function(name) {
-                if sig.params_types.len() != 0 && sig.params_types.len() != _args.len() {
-                    errors.push(SemanticError::WrongArgCount { func: func_name.to_string(), name: name.clone(), expected: sig.params_types.len(), found: _args.len() });
+            Ok(None) => {}
+            Err(error) => match (&mut self.const_errors, error) {
+                (Some(errors), error) => errors.push(error),
+                (None, ConstError::Overflow { expr: text, ty }) => {
+                    self.tables.warnings.push(SemanticWarning::Overflow { func, expr: text, ty, node: expr.id });
                 }
-            }
+                (None, ConstError::DivisionByZero { expr: text }) => {
+                    self.tables.warnings.push(SemanticWarning::DivisionByZero { func, expr: text, node: expr.id });
+                }
+            },
         }
     }
 }
 
 
-
-// Determine the type of an expression where possible. Returns None for unknown
-fn expr_type(expr: &Expr, symbols: &SymbolTable) -> Option<Type> {
-    // Determine the type of an expression where possible. Returns None for unknown
-    match expr {
-        Expr::Number(_) => Some(Type::Int),
-        Expr::FloatNumber(_) => Some(Type::Float),
-        Expr::CharLiteral(_) => Some(Type::Char),
-        Expr::StringLiteral(_) => None,
-        Expr::Ident(name) => {
-            if let Some(sym) = symbols.lookup(name) {
-                match sym {
-                    crate::symbol::Symbol::Variable { name: _, ty } => Some(ty.clone()),
-                    crate::symbol::Symbol::Param { name: _, ty } => Some(ty.clone()),
-                    crate::symbol::Symbol::Function(_) => None,
+impl Analyzer {
+    // calls to a prototyped function must match its parameters in number and,
+    // up to implicit conversion, in type; `()` functions accept any arguments.
+    // callees the program does not declare may be library functions
+    fn check_call(&mut self, name: &str, args: &[Expr], call: &Expr) {
+        let func = self.func_name.clone();
+        let sig = match self.tables.symbol_of(call) {
+            Some(Symbol::Function(sig)) => sig.clone(),
+            Some(_) => {
+                self.errors.push(SemanticError::NotAFunction { func, name: name.to_string() });
+                return;
+            }
+            None => match self.symbols.find_global_function(name) {
+                Some(sig) => sig,
+                None => {
+                    if self.standard >= Standard::C99 && self.implicitly_declared.insert(name.to_string()) {
+                        self.tables.warnings.push(SemanticWarning::ImplicitFunctionDeclaration { func, name: name.to_string(), node: call.id });
+                    }
+                    return self.check_format_call(name, args, call);
+                }
+            },
+        };
+        if !sig.prototyped {
+            return;
+        }
+        let expected = sig.params_types.len();
+        if sig.variadic && args.len() < expected {
+            self.errors.push(SemanticError::TooFewArgs { func, name: name.to_string(), min: expected, found: args.len() });
+            return;
+        }
+        if !sig.variadic && args.len() != expected {
+            self.errors.push(SemanticError::WrongArgCount { func, name: name.to_string(), expected, found: args.len() });
+            return;
+        }
+        for (i, (arg, expected)) in args.iter().zip(&sig.params_types).enumerate() {
+            match self.tables.type_of(arg) {
+                Some(found) if *found != Type::Void && !compatible(expected, found) => {
+                    self.errors.push(SemanticError::ArgTypeMismatch { func: func.clone(), name: name.to_string(), index: i + 1, expected: expected.clone(), found: found.clone() });
                 }
+                _ => self.check_conversion(expected, arg),
+            }
+        }
+        if sig.variadic {
+            self.check_format_call(name, args, call);
+        }
+    }
+
+
+    // a printf- or scanf-like library function: the arguments after a literal format must be as many as its conversions take, each of the type
+    // its conversion expects; other formats are not checked
+    fn check_format_call(&mut self, name: &str, args: &[Expr], call: &Expr) {
+        let Some(callee) = format::format_function(name) else { return };
+        let func = self.func_name.clone();
+        if args.len() <= callee.format_index {
+            self.errors.push(SemanticError::TooFewArgs { func, name: name.to_string(), min: callee.format_index + 1, found: args.len() });
+            return;
+        }
+        let ExprKind::StringLiteral(text) = &args[callee.format_index].kind else { return };
+        let conversions = match format::parse_format(text, callee.style) {
+            Ok(conversions) => conversions,
+            Err(spec) => {
+                self.tables.warnings.push(SemanticWarning::FormatInvalid { func, name: name.to_string(), spec, node: call.id });
+                return;
+            }
+        };
+
+        let values = &args[callee.format_index + 1..];
+        let expected: Vec<_> = conversions.iter().flat_map(|c| c.args.iter().map(move |e| (&c.spec, e))).collect();
+        if expected.len() != values.len() {
+            let (expected, found) = (expected.len(), values.len());
+            self.tables.warnings.push(SemanticWarning::FormatArgCount { func: func.clone(), name: name.to_string(), expected, found, node: call.id });
+        }
+        for (i, ((spec, want), arg)) in expected.into_iter().zip(values).enumerate() {
+            // untyped and void arguments were already reported
+            let found = match self.tables.type_of(arg) {
+                Some(ty) if *ty != Type::Void => ty,
+                _ => continue,
+            };
+            if !format::arg_matches(want, found) {
+                let found = found.to_string();
+                let index = callee.format_index + 2 + i;
+                let (spec, expected) = (spec.clone(), want.to_string());
+                self.tables.warnings.push(SemanticWarning::FormatArgType { func: func.clone(), name: name.to_string(), spec, index, expected, found, node: arg.id });
+            }
+        }
+    }
+
+
+    // the condition of a static assertion must be an integer constant and not
+    // zero; a failed comparison shows the values of both sides
+    fn check_static_assert(&mut self, cond: &Expr, message: &str) {
+        let expr = print_expr(cond);
+        match self.tables.const_of(cond) {
+            Some(ConstValue::Int(0)) => {
+                let literal = |v: ConstValue| {
+                    Box::new(Expr::new(match v {
+                        ConstValue::Int(i) => ExprKind::Number(i, Type::Int),
+                        ConstValue::Float(f) => ExprKind::FloatNumber(f, Type::Double),
+                    }))
+                };
+                let evaluated = match &cond.kind {
+                    ExprKind::Binary { op, left, right } if op.is_comparison() => {
+                        match (self.tables.const_of(left), self.tables.const_of(right)) {
+                            (Some(l), Some(r)) => print_expr(&Expr::new(ExprKind::Binary { op: *op, left: literal(l), right: literal(r) })),
+                            _ => "0".to_string(),
+                        }
+                    }
+                    _ => "0".to_string(),
+                };
+                // comparing literals shows nothing new
+                let evaluated = if evaluated == expr { "0".to_string() } else { evaluated };
+                self.errors.push(SemanticError::StaticAssertFailed { message: message.to_string(), expr, evaluated });
+            }
+            Some(ConstValue::Int(_)) => {}
+            _ => self.errors.push(SemanticError::StaticAssertNotConstant { expr }),
+        }
+    }
+
+
+    // the target must be a modifiable lvalue, and the value must fit its type
+    fn check_assignment(&mut self, target: &Expr, value: &Expr) {
+        let func = self.func_name.clone();
+        let name = match &target.kind {
+            ExprKind::Ident(name) => name.clone(),
+            _ => String::new(),
+        };
+        let error = match self.tables.lvalue_of(target) {
+            Lvalue::Modifiable => None,
+            Lvalue::Const => Some(SemanticError::AssignToConst { func: func.clone(), name }),
+            Lvalue::Function => Some(SemanticError::AssignToFunction { func: func.clone(), name }),
+            Lvalue::NotLvalue => Some(SemanticError::NotAnLvalue { func: func.clone(), expr: print_expr(target) }),
+        };
+        if let Some(error) = error {
+            self.errors.push(error);
+            return;
+        }
+        if let (Some(expected), Some(found)) = (self.tables.type_of(target), self.tables.type_of(value)) {
+            if *found != Type::Void && !compatible(expected, found) {
+                self.errors.push(SemanticError::TypeMismatch { func, expected: expected.clone(), found: found.clone() });
             } else {
-                None
+                let expected = expected.clone();
+                self.check_conversion(&expected, value);
             }
         }
+    }
 
 
-        // type is type of sub-expression
-        Expr::Unary { .. } => None,
-        Expr::Binary { left, right, .. } => {
-            let l = expr_type(left, symbols);
-            let r = expr_type(right, symbols);
-            if l == r { l } else { None }
+    // converting to a narrower type of the same family may change the value;
+    // constants that come through unchanged are fine, and so is any
+    // conversion to _Bool, which keeps whether the value is zero
+    fn check_conversion(&mut self, to: &Type, value: &Expr) {
+        let Some(from) = self.tables.type_of(value).cloned() else { return };
+        if !compatible(to, &from) || to.bits() >= from.bits() || *to == Type::Bool {
+            return;
         }
+        let exact = match (self.tables.const_of(value), to.int_range()) {
+            (Some(ConstValue::Int(v)), Some((lo, hi))) => (lo..=hi).contains(&(v as i128)),
+            (Some(ConstValue::Float(f)), None) => *to != Type::Float || f.is_nan() || f as f32 as f64 == f,
+            _ => false,
+        };
+        if !exact {
+            self.tables.warnings.push(SemanticWarning::Conversion { func: self.func_name.clone(), from, to: to.clone(), node: value.id });
+        }
+    }
 
 
-        // type is variable's type if known
-        Expr::Assign { name, value } => {
-            if let Some(sym) = symbols.lookup(name) {
-                if let crate::symbol::Symbol::Variable { name: _, ty } = sym {
-                    return Some(ty.clone());
+    // a comparison whose result follows from its operands' types alone: the
+    // same variable on both sides, or a constant at or beyond the range of the
+    // other operand's type (such as an unsigned value against 0)
+    fn check_comparison(&mut self, expr: &Expr, op: BinaryOp, left: &Expr, right: &Expr) {
+        let tables = &self.tables;
+        let integer = |e: &Expr| tables.type_of(e).is_some_and(|t| t.is_integer());
+        let always = if matches!(left.kind, ExprKind::Ident(_)) && left == right && integer(left) {
+            Some(matches!(op, BinaryOp::Eq | BinaryOp::Le | BinaryOp::Ge))
+        } else {
+            // the constant is converted to the common type; a signed operand
+            // converted to unsigned no longer covers one range, so it is not checked
+            let decided = |op: BinaryOp, x: &Expr, constant: &Expr, k: i64| {
+                let ty = tables.type_of(x)?;
+                let common = Type::common(ty, tables.type_of(constant)?).filter(|t| t.is_integer())?;
+                if common.is_unsigned() && !ty.is_unsigned() {
+                    return None;
                 }
+                compare_in_range(op, ty.int_range()?, common.exact(k))
+            };
+            match (tables.const_of(left), tables.const_of(right)) {
+                (None, Some(ConstValue::Int(k))) => decided(op, left, right, k),
+                (Some(ConstValue::Int(k)), None) => decided(flip(op), right, left, k),
+                _ => None,
             }
-            expr_type(value, symbols)
+        };
+        if let Some(always) = always {
+            self.tables.warnings.push(SemanticWarning::TautologicalCompare { func: self.func_name.clone(), expr: print_expr(expr), always, node: expr.id });
+        }
+    }
+
+
+    // locals and params that are never referred to, and those hiding a
+    // declaration of an enclosing scope
+    fn check_usage(&mut self, func: &Function) {
+        let res = &self.tables.resolution;
+        let mut found = Vec::new();
+        let stmts = statements(&func.body);
+        let locals = stmts.iter().filter_map(|s| Some((res.declared_by(s.id)?, s.id)));
+        let params = res.params_of(func.id).iter().map(|&id| (id, func.id));
+        for (id, node) in params.chain(locals) {
+            let decl = res.declaration(id);
+            let (func, name) = (func.name.clone(), decl.symbol.name().to_string());
+            if let Some(outer) = res.scope(decl.scope).parent.and_then(|parent| res.lookup(parent, &name)) {
+                let previous = res.declaration(outer);
+                let shadowed = match &previous.symbol {
+                    Symbol::Function(_) => "a function".to_string(),
+                    Symbol::Param { .. } => "a parameter".to_string(),
+                    Symbol::Variable { .. } => {
+                        let stmt = stmts.iter().find(|s| s.id == previous.node);
+                        format!("a local variable declared by `{}`", stmt.map(|s| print_stmt_head(s)).unwrap_or_default())
+                    }
+                };
+                found.push(SemanticWarning::Shadow { func: func.clone(), name: name.clone(), shadowed, node, previous: previous.node });
+            }
+            if res.references(id).is_empty() {
+                found.push(match decl.symbol {
+                    Symbol::Param { .. } => SemanticWarning::UnusedParameter { func, name, node },
+                    _ => SemanticWarning::UnusedVariable { func, name, node },
+                });
+            }
+        }
+        self.tables.warnings.extend(found);
+    }
+
+
+    // whether an already analyzed expression has a value; a call to a void
+    // function does not, and using it as one is reported
+    fn check_value(&mut self, expr: &Expr) -> bool {
+        if self.tables.type_of(expr) != Some(&Type::Void) {
+            return true;
+        }
+        if let ExprKind::Call { name, .. } = &expr.kind {
+            self.errors.push(SemanticError::VoidValueUsed { func: self.func_name.clone(), name: name.clone() });
         }
+        false
+    }
 
 
+    // a non-void function must not fall off its end (except `main`, which
+    // returns 0 there); statements after a return are reported but allowed.
+    // reading a local no path has assigned yet is an error, reading one only
+    // some paths have assigned is a warning, and so is accessing one that an
+    // unsequenced operand assigns
+    fn check_flow(&mut self, func: &Function) {
+        let flow = flow::analyze_function(func);
+        if flow.falls_off_end() && func.return_type != Type::Void && func.name != "main" {
+            self.errors.push(SemanticError::ReturnMissing { func: func.name.clone(), expected: func.return_type.clone() });
+        }
+        let stmts = statements(&func.body);
+        for stmt in stmts.iter().filter(|s| flow.unreachable.contains(&s.id)) {
+            self.tables.warnings.push(SemanticWarning::UnreachableCode { func: func.name.clone(), stmt: print_stmt_head(stmt), node: stmt.id });
+        }
 
-        // type is return type of function if known
-        Expr::Call { name, .. } => {
-            if let Some(sig) = symbols.find_global_function(name) {
-                Some(sig.return_type.clone())
+        let stmt_text = |id: NodeId| stmts.iter().find(|s| s.id == id).map(|s| print_stmt_head(s)).unwrap_or_default();
+        let accesses = flow::check_locals(func, &self.tables.resolution);
+        for read in accesses.uninitialized {
+            let decl = self.tables.resolution.declaration(read.symbol);
+            let (name, decl, used) = (decl.symbol.name().to_string(), stmt_text(decl.node), stmt_text(read.stmt));
+            let func = func.name.clone();
+            if read.definitely {
+                self.errors.push(SemanticError::UninitializedRead { func, name, decl, used });
             } else {
-                None
+                self.tables.warnings.push(SemanticWarning::MaybeUninitialized { func, name, decl, used, node: read.stmt });
             }
         }
+        for access in accesses.unsequenced {
+            let name = self.tables.resolution.declaration(access.symbol).symbol.name().to_string();
+            let stmt = stmt_text(access.stmt);
+            self.tables.warnings.push(SemanticWarning::Unsequenced { func: func.name.clone(), name, stmt, node: access.stmt });
+        }
+    }
+
+
+    // Determine the type of an expression where possible. Returns None for unknown
+    fn expr_type(&self, expr: &Expr) -> Option<Type> {
+        let tables = &self.tables;
+        match &expr.kind {
+            ExprKind::Number(_, ty) => Some(ty.clone()),
+            ExprKind::FloatNumber(_, ty) => Some(ty.clone()),
+            ExprKind::CharLiteral(_) => Some(Type::Char),
+            ExprKind::StringLiteral(_) => Some(Type::Pointer(Box::new(Type::Char))),
+            ExprKind::Ident(_) => match tables.symbol_of(expr)? {
+                Symbol::Variable { ty, .. } | Symbol::Param { ty, .. } => Some(ty.clone()),
+                Symbol::Function(_) => None,
+            },
+
+
+            // negation gives the promoted operand type, logical not gives int
+            ExprKind::Unary { op: UnaryOp::Neg, expr: operand } => tables.type_of(operand).map(Type::promoted),
+            ExprKind::Unary { op: UnaryOp::Not, .. } => Some(Type::Int),
+            ExprKind::Unary { op: UnaryOp::AddrOf, expr: operand } => Some(Type::Pointer(Box::new(tables.type_of(operand)?.clone()))),
+            ExprKind::Binary { op, .. } if op.is_comparison() => Some(Type::Int),
+            ExprKind::Binary { left, right, .. } => Type::common(tables.type_of(left)?, tables.type_of(right)?),
+
+
+            // an assignment has the type of the object assigned to, whether
+            // that is a local or a parameter
+            ExprKind::Assign { target, .. } => tables.type_of(target).cloned(),
+
+
+            // type is return type of function if known
+            ExprKind::Call { name, .. } => match tables.symbol_of(expr) {
+                Some(Symbol::Function(sig)) => Some(sig.return_type.clone()),
+                Some(_) => None,
+                None => self.symbols.find_global_function(name).map(|sig| sig.return_type),
+            },
+        }
+    }
+}
+
+
+
+// Whether a value of type `found` may initialize or be returned as `expected`.
+// Literals of different widths/signedness convert within the integer and floating families.
+// any pointer converts to and from `void *`
+fn compatible(expected: &Type, found: &Type) -> bool {
+    expected == found
+        || (expected.is_integer() && found.is_integer())
+        || (expected.is_floating() && found.is_floating())
+        || matches!((expected, found), (Type::Pointer(a), Type::Pointer(b)) if **a == Type::Void || **b == Type::Void)
+}
+
+
+// the comparison with its operands swapped: `k < x` is `x > k`
+fn flip(op: BinaryOp) -> BinaryOp {
+    match op {
+        BinaryOp::Lt => BinaryOp::Gt,
+        BinaryOp::Gt => BinaryOp::Lt,
+        BinaryOp::Le => BinaryOp::Ge,
+        BinaryOp::Ge => BinaryOp::Le,
+        other => other,
+    }
+}
+
+
+// the result of `x op k` if it is the same for every x in `range`
+fn compare_in_range(op: BinaryOp, (lo, hi): (i128, i128), k: i128) -> Option<bool> {
+    let outside = k < lo || k > hi;
+    let at = |x: i128| match op {
+        BinaryOp::Lt => x < k,
+        BinaryOp::Gt => x > k,
+        BinaryOp::Le => x <= k,
+        _ => x >= k,
+    };
+    match op {
+        BinaryOp::Eq => outside.then_some(false),
+        BinaryOp::Ne => outside.then_some(true),
+        _ => (at(lo) == at(hi)).then(|| at(lo)),
     }
 }
@@ -0,0 +1,120 @@
+// Language standards (--std) and the features that depend on them.
+// the parser records every use of such a feature whatever the standard, like
+// semantic analysis collects every warning; the driver then reports the uses
+// the selected standard does not allow
+use crate::lexer::SourcePos;
+use std::fmt;
+
+
+// the ISO C revisions mini-c can check against, oldest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Standard {
+    C89,
+    C99,
+    #[default]
+    C11,
+}
+
+
+impl Standard {
+    // the standard named by a --std value
+    pub fn from_name(name: &str) -> Option<Standard> {
+        match name {
+            "c89" | "c90" | "ansi" => Some(Standard::C89),
+            "c99" => Some(Standard::C99),
+            "c11" => Some(Standard::C11),
+            _ => None,
+        }
+    }
+
+    // the --std value naming it
+    pub fn flag(self) -> &'static str {
+        match self {
+            Standard::C89 => "c89",
+            Standard::C99 => "c99",
+            Standard::C11 => "c11",
+        }
+    }
+
+    // the value of __STDC_VERSION__, which C89 does not define
+    pub fn version_macro(self) -> Option<&'static str> {
+        match self {
+            Standard::C89 => None,
+            Standard::C99 => Some("199901L"),
+            Standard::C11 => Some("201112L"),
+        }
+    }
+}
+
+
+impl fmt::Display for Standard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Standard::C89 => "C89",
+            Standard::C99 => "C99",
+            Standard::C11 => "C11",
+        })
+    }
+}
+
+
+// a construct only some standards allow. C99's declarations in `for` loops
+// and designated initializers are not listed: both wait on the grammar, which
+// has no `for` statement (only `while`) and no initializer lists yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    LineComment,      // `// ...`
+    LongLong,         // `long long` and the `ll` literal suffix
+    MixedDeclaration, // a declaration after a statement in a block
+    ImplicitInt,      // a function definition without a return type
+    StaticAssert,     // `_Static_assert`
+    Bool,             // the `_Bool` type
+}
+
+
+impl Feature {
+    // the first standard with the feature
+    fn introduced(self) -> Standard {
+        match self {
+            Feature::LineComment | Feature::LongLong | Feature::MixedDeclaration | Feature::Bool => Standard::C99,
+            Feature::ImplicitInt => Standard::C89,
+            Feature::StaticAssert => Standard::C11,
+        }
+    }
+
+    // implicit int is the one feature a later standard removed
+    pub fn allowed_in(self, std: Standard) -> bool {
+        match self {
+            Feature::ImplicitInt => std < Standard::C99,
+            _ => std >= self.introduced(),
+        }
+    }
+
+    // the diagnostic for a use the selected standard does not allow
+    pub fn diagnostic(self) -> String {
+        match self {
+            Feature::LineComment => format!("`//` comments require {}", self.introduced()),
+            Feature::LongLong => format!("`long long` requires {}", self.introduced()),
+            Feature::MixedDeclaration => format!("declarations after statements require {}", self.introduced()),
+            Feature::ImplicitInt => "implicit int return types are not allowed since C99".to_string(),
+            Feature::StaticAssert => format!("`_Static_assert` requires {}", self.introduced()),
+            Feature::Bool => format!("`_Bool` requires {}", self.introduced()),
+        }
+    }
+}
+
+
+// where the parser saw a feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureUse {
+    pub feature: Feature,
+    pub pos: Option<SourcePos>, // None without token positions
+}
+
+
+// the uses a standard does not allow, in source order
+pub fn violations(uses: &[FeatureUse], std: Standard) -> Vec<FeatureUse> {
+    let mut found: Vec<FeatureUse> = uses.iter().filter(|u| !u.feature.allowed_in(std)).copied().collect();
+    found.sort_by_key(|u| u.pos.map(|p| (p.line, p.col)));
+    found
+}
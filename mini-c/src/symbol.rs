@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt;
 use crate::ast::Type;
 
 #[allow(dead_code)]
@@ -8,10 +9,20 @@ use crate::ast::Type;
 // a symbol in the symbol table
 pub enum Symbol {
     Function(FunctionSig),
-    Variable { name: String, ty: Type },
+    Variable { name: String, ty: Type, is_const: bool },
     Param { name: String, ty: Type },
 }
 
+// the declared name of any kind of symbol
+impl Symbol {
+    pub fn name(&self) -> &str {
+        match self {
+            Symbol::Function(sig) => &sig.name,
+            Symbol::Variable { name, .. } | Symbol::Param { name, .. } => name,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 
 
@@ -20,6 +31,22 @@ pub struct FunctionSig {
     pub name: String,
     pub return_type: Type,
     pub params_types: Vec<Type>,
+    pub prototyped: bool, // whether calls are checked against params_types
+    pub variadic: bool,   // `...` after params_types
+}
+
+
+// the signature as a C prototype, such as `int printf(char *, ...)`
+impl fmt::Display for FunctionSig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params: Vec<String> = self.params_types.iter().map(|t| t.to_string()).collect();
+        if self.variadic {
+            params.push("...".to_string());
+        } else if self.prototyped && params.is_empty() {
+            params.push("void".to_string());
+        }
+        write!(f, "{} {}({})", self.return_type, self.name, params.join(", "))
+    }
 }
 
 
@@ -47,13 +74,19 @@ impl Scope {
 
 
 
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
 // helper methods for SymbolTable
 impl SymbolTable {
 
     // create a new symbol table with global scope
     pub fn new() -> Self {
-        let mut scopes = Vec::new();
-        scopes.push(Scope::new(None)); // global scope index 0
+        let scopes = vec![Scope::new(None)]; // global scope index 0
         SymbolTable { scopes, current: 0 }
     }
 
@@ -86,12 +119,12 @@ impl SymbolTable {
 
 
     // declare a local variable in the current scope
-    pub fn declare_local_var(&mut self, name: &str, ty: Type) -> Result<(), String> {
+    pub fn declare_local_var(&mut self, name: &str, ty: Type, is_const: bool) -> Result<(), String> {
         let scope = &mut self.scopes[self.current];
         if scope.symbols.contains_key(name) {
             return Err(format!("duplicate local: {}", name));
         }
-        scope.symbols.insert(name.to_string(), Symbol::Variable { name: name.to_string(), ty });
+        scope.symbols.insert(name.to_string(), Symbol::Variable { name: name.to_string(), ty, is_const });
         Ok(())
     }
 
@@ -125,10 +158,8 @@ impl SymbolTable {
 
     // lookup a global function by name
     pub fn find_global_function(&self, name: &str) -> Option<FunctionSig> {
-        if let Some(sym) = self.scopes[0].symbols.get(name) {
-            if let Symbol::Function(sig) = sym {
-                return Some(sig.clone());
-            }
+        if let Some(Symbol::Function(sig)) = self.scopes[0].symbols.get(name) {
+            return Some(sig.clone());
         }
         None
     }
@@ -1,15 +1,58 @@
 // to define the vocabulary of language
+use crate::ast::Type;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
-    Int,
-    Float,
+    // C11 keywords (6.4.1)
+    Auto,
+    Break,
+    Case,
     Char,
-    Void,
+    Const,
+    Continue,
+    Default,
+    Do,
+    Double,
+    Else,
+    Enum,
+    Extern,
+    Float,
+    For,
+    Goto,
+    If,
+    Inline,
+    Int,
+    Long,
+    Register,
+    Restrict,
     Return,
+    Short,
+    Signed,
+    Sizeof,
+    Static,
+    Struct,
+    Switch,
+    Typedef,
+    Union,
+    Unsigned,
+    Void,
+    Volatile,
+    While,
+    Alignas,
+    Alignof,
+    Atomic,
+    Bool,
+    Complex,
+    Generic,
+    Imaginary,
+    Noreturn,
+    StaticAssert,
+    ThreadLocal,
+
     Ident(String),
-    Number(i64),
-    FloatNumber(f64),
+    Number(i64, Type),      // integer literal and the type picked from its suffix/base
+    FloatNumber(f64, Type), // floating literal: Float (f), Double or LongDouble (l)
     CharLiteral(char),
     String(String),
     Semicolon,
@@ -19,5 +62,126 @@ pub enum Token {
     RBrace,
     Assign,
     Comma,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Bang,
+    Amp,
+    Less,
+    Greater,
+    LessEq,
+    GreaterEq,
+    EqEq,
+    NotEq,
+    InvalidNumber(NumberError), // an integer literal with no value
+    Unknown(char),          // a character that cannot start any token
     EOF,
 }
+
+
+// why an integer literal could not be given a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberError {
+    InvalidDigit(char, u32), // the digit and the radix of the constant
+    NoDigits(u32),           // a 0x prefix with nothing after it
+    TooLarge,                // does not fit in unsigned long long
+    InvalidSuffix(String),   // whatever follows the digits and is not a suffix
+    NoExponentDigits,        // e, E, p or P without digits after it
+    NoHexExponent,           // a hexadecimal fraction without a p exponent
+}
+
+impl std::fmt::Display for NumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let base = |radix: &u32| match radix {
+            2 => "binary",
+            8 => "octal",
+            16 => "hexadecimal",
+            _ => "decimal",
+        };
+        match self {
+            NumberError::InvalidDigit(digit, radix) => write!(f, "invalid digit '{}' in {} constant", digit, base(radix)),
+            NumberError::NoDigits(radix) => write!(f, "{} constant has no digits", base(radix)),
+            NumberError::TooLarge => write!(f, "integer constant is too large for its type"),
+            NumberError::InvalidSuffix(suffix) => write!(f, "invalid suffix '{}' on numeric constant", suffix),
+            NumberError::NoExponentDigits => write!(f, "exponent has no digits"),
+            NumberError::NoHexExponent => write!(f, "hexadecimal floating constant requires an exponent"),
+        }
+    }
+}
+
+
+// spelling of every keyword and the token it lexes to
+const KEYWORDS: &[(&str, Token)] = &[
+    ("auto", Token::Auto),
+    ("break", Token::Break),
+    ("case", Token::Case),
+    ("char", Token::Char),
+    ("const", Token::Const),
+    ("continue", Token::Continue),
+    ("default", Token::Default),
+    ("do", Token::Do),
+    ("double", Token::Double),
+    ("else", Token::Else),
+    ("enum", Token::Enum),
+    ("extern", Token::Extern),
+    ("float", Token::Float),
+    ("for", Token::For),
+    ("goto", Token::Goto),
+    ("if", Token::If),
+    ("inline", Token::Inline),
+    ("int", Token::Int),
+    ("long", Token::Long),
+    ("register", Token::Register),
+    ("restrict", Token::Restrict),
+    ("return", Token::Return),
+    ("short", Token::Short),
+    ("signed", Token::Signed),
+    ("sizeof", Token::Sizeof),
+    ("static", Token::Static),
+    ("struct", Token::Struct),
+    ("switch", Token::Switch),
+    ("typedef", Token::Typedef),
+    ("union", Token::Union),
+    ("unsigned", Token::Unsigned),
+    ("void", Token::Void),
+    ("volatile", Token::Volatile),
+    ("while", Token::While),
+    ("_Alignas", Token::Alignas),
+    ("_Alignof", Token::Alignof),
+    ("_Atomic", Token::Atomic),
+    ("_Bool", Token::Bool),
+    ("_Complex", Token::Complex),
+    ("_Generic", Token::Generic),
+    ("_Imaginary", Token::Imaginary),
+    ("_Noreturn", Token::Noreturn),
+    ("_Static_assert", Token::StaticAssert),
+    ("_Thread_local", Token::ThreadLocal),
+];
+
+
+// keyword lookups used by the lexer, parser and semantic checks
+impl Token {
+    // the keyword token for a word, if it is reserved
+    pub fn keyword(word: &str) -> Option<Token> {
+        // cheap rejection first: keywords are 2 to 14 bytes and start with a lowercase letter or '_'
+        let first = *word.as_bytes().first()?;
+        if !(2..=14).contains(&word.len()) || !(first.is_ascii_lowercase() || first == b'_') {
+            return None;
+        }
+        KEYWORDS
+            .iter()
+            .find(|(kw, _)| kw.len() == word.len() && kw.as_bytes()[0] == first && *kw == word)
+            .map(|(_, tok)| tok.clone())
+    }
+
+    // whether a word is a reserved keyword
+    pub fn is_keyword(word: &str) -> bool {
+        Token::keyword(word).is_some()
+    }
+
+    // source spelling of a keyword token, None for any other token
+    pub fn keyword_text(&self) -> Option<&'static str> {
+        KEYWORDS.iter().find(|(_, tok)| tok == self).map(|(kw, _)| *kw)
+    }
+}
@@ -0,0 +1,137 @@
+// Warning names and the driver-side configuration deciding which warnings are
+// reported and which are errors: -W flags and `// mini-c: allow(name)` comments.
+// semantic analysis always collects every warning; this only filters them
+use crate::lexer::{Lexer, TriviaKind};
+use crate::token::Token;
+use std::collections::{HashMap, HashSet};
+
+
+// the flag that turns a warning on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    Default, // on unless disabled
+    All,     // -Wall
+    Extra,   // -Wextra
+}
+
+
+// every warning by the name used in flags and allow comments
+pub const WARNINGS: &[(&str, Group)] = &[
+    ("unreachable-code", Group::Default),
+    ("maybe-uninitialized", Group::Default),
+    ("unsequenced", Group::Default),
+    ("unused-variable", Group::All),
+    ("unused-function", Group::All),
+    ("tautological-compare", Group::All),
+    ("format", Group::Default),
+    ("overflow", Group::Default),
+    ("div-by-zero", Group::Default),
+    ("implicit-function-declaration", Group::Default),
+    ("unused-parameter", Group::Extra),
+    ("shadow", Group::Extra),
+    ("conversion", Group::Extra),
+];
+
+
+// the canonical name of a warning, if there is one called `name`
+fn lookup(name: &str) -> Option<&'static str> {
+    WARNINGS.iter().find(|(n, _)| *n == name).map(|(n, _)| *n)
+}
+
+
+// which warnings are enabled and which of them stop compilation
+#[derive(Debug, Clone)]
+pub struct WarningConfig {
+    enabled: HashSet<&'static str>,
+    errors: HashSet<&'static str>,
+    all_errors: bool, // -Werror
+}
+
+
+impl Default for WarningConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl WarningConfig {
+    // only the default group enabled, nothing an error
+    pub fn new() -> Self {
+        let mut config = WarningConfig { enabled: HashSet::new(), errors: HashSet::new(), all_errors: false };
+        config.enable_group(Group::Default);
+        config
+    }
+
+    fn enable_group(&mut self, group: Group) {
+        self.enabled.extend(WARNINGS.iter().filter(|(_, g)| *g == group).map(|(n, _)| *n));
+    }
+
+    // apply one command line option starting with -W; later options override
+    // earlier ones: -Wall, -Wextra, -W<name>, -Wno-<name>, -Werror, -Werror=<name>
+    pub fn apply_flag(&mut self, flag: &str) -> Result<(), String> {
+        let option = flag.strip_prefix("-W").ok_or_else(|| format!("not a warning option: {}", flag))?;
+        let known = |name: &str| lookup(name).ok_or_else(|| format!("unknown warning option: {}", flag));
+        match option {
+            "all" => {
+                self.enable_group(Group::Default);
+                self.enable_group(Group::All);
+            }
+            "extra" => self.enable_group(Group::Extra),
+            "error" => self.all_errors = true,
+            _ => {
+                if let Some(name) = option.strip_prefix("error=") {
+                    let name = known(name)?;
+                    self.enabled.insert(name);
+                    self.errors.insert(name);
+                } else if let Some(name) = option.strip_prefix("no-") {
+                    self.enabled.remove(known(name)?);
+                } else {
+                    self.enabled.insert(known(option)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.contains(name)
+    }
+
+    // an enabled warning turned into an error by -Werror or -Werror=<name>
+    pub fn is_error(&self, name: &str) -> bool {
+        self.is_enabled(name) && (self.all_errors || self.errors.contains(name))
+    }
+}
+
+
+// the warnings each line of a source file allows: a `// mini-c: allow(a, b)`
+// comment after code covers its own line, one on a line of its own the next
+pub fn allowed_lines(source: &str) -> HashMap<usize, Vec<String>> {
+    let mut allowed: HashMap<usize, Vec<String>> = HashMap::new();
+    let mut lexer = Lexer::lossless(source);
+    let mut code_line = 0;
+    loop {
+        while let Some(kind) = lexer.next_trivia() {
+            if kind != TriviaKind::LineComment {
+                continue;
+            }
+            let Some(names) = allow_comment(lexer.token_text()) else { continue };
+            let line = lexer.token_pos().line;
+            let target = if line == code_line { line } else { line + 1 };
+            allowed.entry(target).or_default().extend(names);
+        }
+        if lexer.next_token() == Token::EOF {
+            return allowed;
+        }
+        code_line = lexer.token_pos().line;
+    }
+}
+
+
+// the names listed by an allow comment, None for any other comment
+fn allow_comment(comment: &str) -> Option<Vec<String>> {
+    let body = comment.strip_prefix("//")?.trim();
+    let list = body.strip_prefix("mini-c:")?.trim().strip_prefix("allow(")?.strip_suffix(')')?;
+    Some(list.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect())
+}
@@ -0,0 +1,131 @@
+use mini_c::codegen::{self, RunConfig, RunOutput, RuntimeError};
+use mini_c::lexer::Lexer;
+use mini_c::parser::Parser;
+use mini_c::semantic;
+
+// run a program with the given configuration, returning main's exit code
+fn run(src: &str, config: &mut RunConfig) -> Result<i32, RuntimeError> {
+    let prog = Parser::new(Lexer::new(src).collect()).parse_program();
+    let tables = semantic::analyze(&prog).unwrap();
+    codegen::run_with(&prog, &tables, config).map(|output| output.exit_code)
+}
+
+#[test]
+fn integers_keep_the_width_of_their_type() {
+    let mut config = RunConfig::default();
+    assert_eq!(run("int main() { int big = 2147483647; int w = big + 1; return w == -2147483647 - 1; }", &mut config), Ok(1));
+    assert_eq!(run("int main() { unsigned int z = 0u; unsigned int u = z - 1u; return u == 4294967295u; }", &mut config), Ok(1));
+    assert_eq!(run("int main() { int m = -1; unsigned int z = 0u; return m < z; }", &mut config), Ok(0));
+    assert_eq!(run("int main() { long l = 4294967297l; int i = l; return i; }", &mut config), Ok(1));
+    assert_eq!(run("int main() { char c = 'a'; char d = c + 200; return d == 41; }", &mut config), Ok(1));
+    assert_eq!(run("int main() { char c = 'a'; char n = c + 100; return n == -59; }", &mut config), Ok(1));
+    // any nonzero value converts to a _Bool of 1
+    assert_eq!(run("int main() { _Bool b = 256; _Bool h = -1l; _Bool z = 0; return b * 100 + h * 10 + z; }", &mut config), Ok(110));
+}
+
+#[test]
+fn undefined_arithmetic_stops_the_program() {
+    let mut config = RunConfig::default();
+    let err = run("int div(int a, int b) { return a / b; } int main() { return div(10, 0); }", &mut config).unwrap_err();
+    assert_eq!(err.to_string(), "division by zero in `a / b`");
+    assert!(err.node().is_some());
+    let err = run("int div(int a, int b) { return a / b; } int main() { return div(-2147483647 - 1, -1); }", &mut config).unwrap_err();
+    assert_eq!(err.to_string(), "integer overflow in `a / b` of type int");
+
+    let mut trapping = RunConfig { trap_overflow: true, ..Default::default() };
+    let src = "int main() { int big = 2147483647; int w = big * 2; return w == -2; }";
+    assert_eq!(run(src, &mut config), Ok(1));
+    assert_eq!(run(src, &mut trapping).unwrap_err().to_string(), "integer overflow in `big * 2` of type int");
+    assert_eq!(run("int main() { unsigned int big = 4294967295u; unsigned int w = big + 1u; return w; }", &mut trapping), Ok(0));
+}
+
+#[test]
+fn formatted_output_counts_what_it_writes() {
+    let mut config = RunConfig::default();
+    assert_eq!(run("int main() { char *b = \"\"; return sprintf(b, \"%-6s|%+.2e\", \"ab\", 1.5); }", &mut config), Ok(16));
+    assert_eq!(run("int main() { char *b = \"\"; int n = snprintf(b, 3, \"%d\", 12345); return n * 10 + sprintf(b, \"%s\", b); }", &mut config), Ok(52));
+    let err = run("int main() { return printf(\"%y\", 1); }", &mut config).unwrap_err();
+    assert_eq!(err.to_string(), "printf: invalid conversion `%y`");
+    let err = run("int main() { return printf(\"%s %d\", 1); }", &mut config).unwrap_err();
+    assert_eq!(err.to_string(), "printf: argument does not match `%s`");
+}
+
+#[test]
+fn input_comes_from_the_configured_source() {
+    let src = "#include <stdio.h>\nint main() { int a; int b; char *rest = \"\"; int n = scanf(\"%d,%d\", &a, &b); int nl = getchar(); fgets(rest, 3, stdin); int c = getchar(); return n * 100 + a * b + (nl == 10) + (c == 'z'); }";
+    let prog = {
+        let mut pp = mini_c::preprocessor::Preprocessor::new();
+        let out = pp.preprocess_str(src, "test.c").unwrap();
+        Parser::with_positions(out.tokens_with_pos()).parse_program()
+    };
+    let options = semantic::AnalysisOptions { headers: vec!["stdio.h".to_string()], ..Default::default() };
+    let tables = semantic::analyze_with(&prog, &options).unwrap();
+    let mut input: &[u8] = b"6,7\nxyz";
+    let mut config = RunConfig { stdin: Some(&mut input), ..Default::default() };
+    assert_eq!(codegen::run_with(&prog, &tables, &mut config).map(|output| output.exit_code), Ok(244));
+
+    // at the end of the input scanf and getchar give EOF
+    let src = "int main() { int a = 0; int n = scanf(\"%d\", &a); return (n == -1) + (getchar() == -1); }";
+    let mut empty: &[u8] = b"";
+    assert_eq!(run(src, &mut RunConfig { stdin: Some(&mut empty), ..Default::default() }), Ok(2));
+
+    // fgets with room for the terminator only stores "" and returns the buffer
+    let src = "int main() { char *b = \"x\"; printf(\"[%s]\", fgets(b, 1, __stream(0))); printf(\"[%s]\", b); return getchar(); }";
+    let prog = Parser::new(Lexer::new(src).collect()).parse_program();
+    let tables = semantic::analyze(&prog).unwrap();
+    let mut input: &[u8] = b"q";
+    let output = codegen::run_with(&prog, &tables, &mut RunConfig { stdin: Some(&mut input), ..Default::default() }).unwrap();
+    assert_eq!(output, RunOutput { exit_code: b'q' as i32, stdout: b"[][]".to_vec() });
+}
+
+#[test]
+fn output_is_captured_or_written_where_configured() {
+    let src = "int main(int argc, char **argv) { int c = getchar(); printf(\"%d args, read %c\\n\", argc, c); puts(\"done\"); return 7; }";
+    let prog = Parser::new(Lexer::new(src).collect()).parse_program();
+    let tables = semantic::analyze(&prog).unwrap();
+
+    let mut input: &[u8] = b"q";
+    let args = vec!["prog".to_string(), "one".to_string()];
+    let mut config = RunConfig { stdin: Some(&mut input), args: args.clone(), ..Default::default() };
+    let output = codegen::run_with(&prog, &tables, &mut config).unwrap();
+    assert_eq!(output, RunOutput { exit_code: 7, stdout: b"2 args, read q\ndone\n".to_vec() });
+
+    let mut written = Vec::new();
+    let mut input: &[u8] = b"r";
+    let mut config = RunConfig { stdin: Some(&mut input), stdout: Some(&mut written), args, ..Default::default() };
+    assert_eq!(codegen::run_with(&prog, &tables, &mut config).unwrap().stdout, b"");
+    assert_eq!(written, b"2 args, read r\ndone\n");
+}
+
+#[test]
+fn main_sees_the_configured_command_line() {
+    let src = "int count(char **v, int n) { if (v) return n; return 0; }\nint main(int argc, char **argv) { return count(argv, argc) * 10 + argc; }";
+    let args = ["prog", "a", "b"].iter().map(|a| a.to_string()).collect();
+    assert_eq!(run(src, &mut RunConfig { args, ..Default::default() }), Ok(33));
+    let err = run("int main(int argc, char **argv) { return printf(\"%d\", argv); }", &mut RunConfig::default()).unwrap_err();
+    assert_eq!(err.to_string(), "printf: addresses cannot be printed");
+}
+
+#[test]
+fn a_caller_may_pass_its_own_lock_on_stdin() {
+    // run on another thread so a second lock of stdin fails the test instead of hanging it
+    let (done, finished) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin().lock();
+        let mut config = RunConfig { stdin: Some(&mut stdin), ..Default::default() };
+        done.send(run("int main() { return 3; }", &mut config)).unwrap();
+    });
+    assert_eq!(finished.recv_timeout(std::time::Duration::from_secs(10)), Ok(Ok(3)));
+}
+
+#[test]
+fn branches_and_loops_run() {
+    let mut config = RunConfig::default();
+    let src = "int sum(int n) { int total = 0; while (n > 0) { total = total + n; n = n - 1; } return total; }
+               int sign(int x) { if (x < 0) return -1; else if (x == 0) return 0; return 1; }
+               int main() { return sum(10) - sign(-5) * 100 + sign(0) + sign(7); }";
+    assert_eq!(run(src, &mut config), Ok(156));
+    // a block keeps its declarations to itself
+    assert_eq!(run("int main() { int x = 1; { int x = 2; x = 3; } return x; }", &mut config), Ok(1));
+    assert_eq!(run("int main() { int i = 0; while (1) { i = i + 1; if (i == 5) return i; } }", &mut config), Ok(5));
+}
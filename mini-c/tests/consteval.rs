@@ -0,0 +1,77 @@
+use mini_c::ast::{Expr, Program, StmtKind, Type};
+use mini_c::consteval::{eval, ConstError, ConstValue};
+use mini_c::lexer::Lexer;
+use mini_c::parser::Parser;
+use mini_c::codegen;
+use mini_c::semantic::{self, SideTables};
+
+fn parse(src: &str) -> Program {
+    Parser::new(Lexer::new(src).collect()).parse_program()
+}
+
+// evaluate the value returned by the first function, typed by semantic analysis
+fn eval_return(src: &str) -> Result<Option<ConstValue>, ConstError> {
+    let prog = parse(src);
+    let tables: SideTables = semantic::analyze(&prog).unwrap();
+    let StmtKind::Return(Some(expr)) = &prog.functions[0].body.stmts.last().unwrap().kind else { panic!("no return") };
+    eval(expr, &|e: &Expr| tables.type_of(e).cloned())
+}
+
+#[test]
+fn integer_arithmetic_follows_the_type() {
+    assert_eq!(eval_return("int f() { return (1 + 2) * -3 / 2; }"), Ok(Some(ConstValue::Int(-4))));
+    assert_eq!(eval_return("int f() { return 2.5 < 3; }"), Ok(Some(ConstValue::Int(1))));
+    assert_eq!(eval_return("unsigned int f() { return 0u - 1u; }"), Ok(Some(ConstValue::Int(4294967295))));
+    assert_eq!(eval_return("long f() { return 2147483647l + 1l; }"), Ok(Some(ConstValue::Int(2147483648))));
+    assert_eq!(eval_return("int f() { int x = 1; return x + 1; }"), Ok(None));
+
+    let overflow = eval_return("int f() { return 2 * (2147483647 + 1); }").unwrap_err();
+    assert_eq!(overflow, ConstError::Overflow { expr: "2147483647 + 1".to_string(), ty: Type::Int });
+    let division = eval_return("int f() { int x = 1; return x / (1 - 1); }").unwrap_err();
+    assert_eq!(division.to_string(), "division by zero in `x / (1 - 1)`");
+    assert_eq!(eval_return("double f() { return 1.0 / 0; }"), Ok(Some(ConstValue::Float(f64::INFINITY))));
+}
+
+#[test]
+fn analysis_reports_constants_without_a_value_once() {
+    let tables = semantic::analyze(&parse("int main() { int a = -(-2147483647 - 1) + 1; int b = a / 0; return b; }")).unwrap();
+    let found: Vec<String> = tables.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        found,
+        vec![
+            "Integer overflow in `-(-2147483647 - 1)` of type int in function 'main'.",
+            "Division by zero in `a / 0` in function 'main'.",
+        ]
+    );
+}
+
+// `left op right` folded from literals and computed at run time from variables
+// initialized with the same literals
+fn folded_and_run(left: (&str, &str), op: &str, right: (&str, &str)) -> (i64, i32) {
+    let prog = parse(&format!("int main() {{ return {} {} {}; }}", left.1, op, right.1));
+    let tables = semantic::analyze(&prog).unwrap();
+    assert!(tables.warnings.is_empty(), "{:?}", tables.warnings);
+    let StmtKind::Return(Some(expr)) = &prog.functions[0].body.stmts[0].kind else { panic!("no return") };
+    let Some(ConstValue::Int(folded)) = tables.const_of(expr) else { panic!("not folded") };
+
+    let prog = parse(&format!("int main() {{ {} a = {}; {} b = {}; return a {} b; }}", left.0, left.1, right.0, right.1, op));
+    let tables = semantic::analyze(&prog).unwrap();
+    (folded, codegen::run(&prog, &tables).unwrap())
+}
+
+#[test]
+fn folding_applies_the_usual_arithmetic_conversions() {
+    let cases = [
+        (("int", "-1"), "<", ("unsigned int", "0u"), 0),
+        (("char", "'\\xff'"), "==", ("int", "-1"), 1),
+        (("char", "'a'"), "+", ("char", "'b'"), 195),
+        (("long", "-1l"), ">", ("unsigned int", "1u"), 0),
+        (("unsigned long", "1ul"), ">", ("long", "-1l"), 0),
+        (("char", "'\\x80'"), "<", ("int", "0"), 1),
+    ];
+    for (left, op, right, expected) in cases {
+        let (folded, run) = folded_and_run(left, op, right);
+        assert_eq!(folded, expected, "{} {} {}", left.1, op, right.1);
+        assert_eq!(run as i64, expected & 0xff, "{} {} {}", left.1, op, right.1);
+    }
+}
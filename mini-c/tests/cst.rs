@@ -0,0 +1,71 @@
+use mini_c::cst::{self, SyntaxKind};
+use mini_c::lexer::Lexer;
+use mini_c::token::Token;
+use std::fs;
+
+#[test]
+fn examples_round_trip_byte_for_byte() {
+    for path in ["examples/example1.c", "examples/bad.c"] {
+        let source = fs::read_to_string(path).unwrap();
+        assert_eq!(cst::parse(&source).text(), source, "{}", path);
+    }
+}
+
+#[test]
+fn trivia_and_odd_input_round_trip() {
+    let sources = [
+        "",
+        "   \n\n",
+        "\u{feff}int main() {\r\n\treturn 0; // done\r\n}\r\n",
+        "int f(int a, float b) { /* multi\nline */ g(a, (b)); }\n\n// trailing comment",
+        "int x = 1 + 2 @ $;\n}}} ) ( int",
+        "int sp\\\nlit() { return 'a' ; }\r\rint y;\\\r\n",
+        "int s = \"unterminated\n/* unterminated comment",
+        "\\u12 caf\\u00e9 \u{feff} ok",
+    ];
+    for src in sources {
+        assert_eq!(cst::parse(src).text(), src, "{:?}", src);
+    }
+}
+
+#[test]
+fn trivia_is_split_at_line_ends() {
+    let tree = cst::parse("// adds numbers\nint add(int a) { return a; } // same line\n/* next */ int z;\n");
+    let functions = tree.descendants(SyntaxKind::Function);
+    assert_eq!(functions.len(), 1);
+    assert_eq!(functions[0].leading_comments(), vec!["// adds numbers"]);
+
+    // the comment after the closing brace stays on its line, the next one leads `int z`
+    let rbrace = tree.tokens().into_iter().find(|t| t.kind == Token::RBrace).unwrap();
+    assert_eq!(rbrace.trailing.iter().map(|t| t.text.as_str()).collect::<String>(), " // same line");
+    assert_eq!(tree.descendants(SyntaxKind::Declaration)[0].leading_comments(), vec!["/* next */"]);
+}
+
+#[test]
+fn tree_follows_the_grammar() {
+    let tree = cst::parse("int main(void) { int x = 5; printf(\"%d\", (x)); return x; }");
+    assert_eq!(tree.descendants(SyntaxKind::Param).len(), 1);
+    assert_eq!(tree.descendants(SyntaxKind::VarDecl).len(), 1);
+    assert_eq!(tree.descendants(SyntaxKind::ReturnStmt).len(), 1);
+    assert_eq!(tree.descendants(SyntaxKind::Arg).len(), 2);
+    assert_eq!(tree.descendants(SyntaxKind::ParenExpr)[0].text(), "(x)");
+    assert_eq!(tree.descendants(SyntaxKind::CallExpr)[0].first_token().unwrap().text, "printf");
+}
+
+#[test]
+fn branches_and_loops_hold_their_statements() {
+    let src = "int main(void) { while (n) { if (n == 1) return 1; else n = n - 1; } return 0; }";
+    let tree = cst::parse(src);
+    assert_eq!(tree.text(), src);
+    let [loop_] = &tree.descendants(SyntaxKind::WhileStmt)[..] else { panic!() };
+    let [branch] = &loop_.descendants(SyntaxKind::IfStmt)[..] else { panic!() };
+    assert_eq!(branch.text(), "if (n == 1) return 1; else n = n - 1; ");
+    assert_eq!(branch.descendants(SyntaxKind::ParenExpr)[0].text(), "(n == 1) ");
+    assert_eq!(tree.descendants(SyntaxKind::ReturnStmt).len(), 2);
+}
+
+#[test]
+fn unknown_characters_do_not_end_the_token_stream() {
+    let tokens: Vec<Token> = Lexer::new("a @ b").collect();
+    assert_eq!(tokens, vec![Token::Ident("a".to_string()), Token::Unknown('@'), Token::Ident("b".to_string())]);
+}
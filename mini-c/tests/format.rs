@@ -0,0 +1,81 @@
+use mini_c::format::{render, scan, RenderError};
+use mini_c::format::FormatArg::{self, Float, Int};
+
+fn s(text: &str) -> FormatArg {
+    FormatArg::Str(text.to_string())
+}
+
+// formats and arguments with what glibc's printf writes for them
+#[test]
+fn printf_output_matches_glibc() {
+    let table: Vec<(&str, Vec<FormatArg>, &str)> = vec![
+        ("%d|%i|%5d|%-5d|%05d", vec![Int(42), Int(-42), Int(42), Int(42), Int(-42)], "42|-42|   42|42   |-0042"),
+        ("%+d|% d|%+.3d|%.0d|%.0d", vec![Int(7), Int(7), Int(-7), Int(0), Int(3)], "+7| 7|-007||3"),
+        ("%hhd|%hd|%ld|%lld", vec![Int(300), Int(70000), Int(-9000000000), Int(i64::MAX)], "44|4464|-9000000000|9223372036854775807"),
+        ("%u|%u|%lu|%hhu", vec![Int(3000000000), Int(-1), Int(-1), Int(511)], "3000000000|4294967295|18446744073709551615|255"),
+        ("%x|%X|%#x|%#X|%#x|%08x|%#010x", vec![Int(255), Int(255), Int(255), Int(3054), Int(0), Int(255), Int(255)], "ff|FF|0xff|0XBEE|0|000000ff|0x000000ff"),
+        ("%o|%#o|%#o|%#.3o|%.0o|%#.0o", vec![Int(8), Int(8), Int(0), Int(8), Int(0), Int(0)], "10|010|0|010||0"),
+        ("%c|%3c|%-3c|%%", vec![Int(65), Int(98), Int(99)], "A|  b|c  |%"),
+        ("%s|%8s|%-8s|%.2s|%8.3s|%s", vec![s("hello"), s("hi"), s("hi"), s("hello"), s("hello"), s("")], "hello|      hi|hi      |he|     hel|"),
+        ("%f|%.2f|%10.3f|%-10.1f|%+f|% f", vec![Float(3.16159), Float(2.675), Float(-1.5), Float(1.25), Float(1.0), Float(2.5)], "3.161590|2.67|    -1.500|1.2       |+1.000000| 2.500000"),
+        ("%.0f|%.0f|%.0f|%#.0f|%010.2f|%f", vec![Float(0.5), Float(1.5), Float(2.5), Float(3.0), Float(-3.16159), Float(1e20)], "0|2|2|3.|-000003.16|100000000000000000000.000000"),
+        ("%e|%.2e|%E|%.0e|%#.0e|%e", vec![Float(12345.678), Float(0.000123), Float(1e-300), Float(5e10), Float(7.0), Float(0.0)], "1.234568e+04|1.23e-04|1.000000E-300|5e+10|7.e+00|0.000000e+00"),
+        ("%g|%g|%g|%g|%g|%g", vec![Float(100000.0), Float(1000000.0), Float(0.0001), Float(0.00001), Float(123.456), Float(0.0)], "100000|1e+06|0.0001|1e-05|123.456|0"),
+        ("%.3g|%.10g|%#g|%#.2g|%G|%g", vec![Float(3.16159), Float(1.0 / 3.0), Float(1.5), Float(100.0), Float(1e-10), Float(1e100)], "3.16|0.3333333333|1.50000|1.0e+02|1E-10|1e+100"),
+        (
+            "%f|%F|%e|%g|%5.1f|%-6f|%06f",
+            vec![Float(f64::INFINITY), Float(f64::NEG_INFINITY), Float(-f64::NAN), Float(f64::NAN), Float(f64::INFINITY), Float(f64::INFINITY), Float(f64::NEG_INFINITY)],
+            "inf|-INF|-nan|nan|  inf|inf   |  -inf",
+        ),
+        ("%a|%A|%.1a|%.0a|%a|%a|%a", vec![Float(1.5), Float(255.0), Float(1.03125), Float(1.5), Float(0.0), Float(0.1), Float(-2.0)], "0x1.8p+0|0X1.FEP+7|0x1.0p+0|0x2p+0|0x0p+0|0x1.999999999999ap-4|-0x1p+1"),
+        ("%*d|%-*d|%.*f|%*.*s|%.*d", vec![Int(6), Int(42), Int(6), Int(42), Int(2), Float(3.16159), Int(5), Int(2), s("hello"), Int(-1), Int(7)], "    42|42    |3.16|   he|7"),
+        ("%*d|%.*s", vec![Int(-4), Int(9), Int(-1), s("abc")], "9   |abc"),
+        ("%p|%p|%20p|%-20p|", vec![Int(0), Int(0x1234), Int(0x1234), Int(0)], "(nil)|0x1234|              0x1234|(nil)               |"),
+        ("%lx|%llX|%jd|%zu|%td", vec![Int(-1), Int(255), Int(-5), Int(12), Int(-3)], "ffffffffffffffff|FF|-5|12|-3"),
+        ("%f|%.15f|%.20e|%g", vec![Float(0.1), Float(0.1), Float(0.1), Float(1e-5)], "0.100000|0.100000000000000|1.00000000000000005551e-01|1e-05"),
+        ("%5s|%-5s|%05d|%-05d|%+05d", vec![s("abcdefg"), s("x"), Int(-3), Int(-3), Int(3)], "abcdefg|x    |-0003|-3   |+0003"),
+        ("% 05.1f|%+.1e|%#x|%#o|%.3x", vec![Float(2.25), Float(-0.0), Int(1), Int(1), Int(10)], " 02.2|-0.0e+00|0x1|01|00a"),
+    ];
+    for (format, args, expected) in table {
+        let out = render(format, &args).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected, "format {:?}", format);
+    }
+}
+
+#[test]
+fn unformattable_specifications_are_reported() {
+    assert_eq!(render("%d %d", &[Int(1)]), Err(RenderError::MissingArgument("%d".to_string())));
+    assert_eq!(render("%s", &[Int(1)]), Err(RenderError::Mismatch("%s".to_string())));
+    assert_eq!(render("%*d", &[Float(1.0), Int(1)]), Err(RenderError::Mismatch("%*".to_string())));
+    assert_eq!(render("%y", &[]), Err(RenderError::Invalid("%y".to_string())));
+    assert_eq!(render("100%", &[]), Err(RenderError::Invalid("%".to_string())));
+    assert_eq!(RenderError::MissingArgument("%d".to_string()).to_string(), "too few arguments for `%d`");
+    assert_eq!(render("%c", &[Int(200)]), Ok(vec![200]));
+}
+
+// inputs and formats with the values and result glibc's sscanf gives for them
+#[test]
+fn scanf_input_matches_glibc() {
+    let table: Vec<(&str, &str, Vec<FormatArg>, i32)> = vec![
+        (
+            "  -17 0x1f 010 0X1A abcdefgh xyz123!",
+            "%d %x %i %i %5s %[a-z]%n %c",
+            vec![Int(-17), Int(31), Int(8), Int(26), s("abcde"), s("fgh"), Int(28), s("x")],
+            7,
+        ),
+        ("3.5e2x 42", "%lf%*c %u", vec![Float(350.0), Int(42)], 2),
+        ("12 abc", "%d %d", vec![Int(12)], 1),
+        ("", "%d", vec![], -1),
+        ("   ", "%d", vec![], -1),
+        ("5:7", "%d:%d", vec![Int(5), Int(7)], 2),
+        ("5;7", "%d:%d", vec![Int(5)], 1),
+        ("abc", "%2c%c", vec![s("ab"), s("c")], 2),
+        ("-1 100%", "%u %d%%", vec![Int(-1), Int(100)], 2),
+        ("x", "%d", vec![], 0),
+        ("123456", "%3d%d", vec![Int(123), Int(456)], 2),
+    ];
+    for (input, format, values, result) in table {
+        let scanned = scan(format, &mut input.as_bytes()).unwrap();
+        assert_eq!((scanned.values, scanned.result), (values, result), "format {:?} on {:?}", format, input);
+    }
+}
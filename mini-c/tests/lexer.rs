@@ -0,0 +1,166 @@
+use mini_c::ast::Type;
+use mini_c::lexer::Lexer;
+use mini_c::token::{NumberError, Token};
+
+fn lex_one(src: &str) -> Token {
+    let mut lexer = Lexer::new(src);
+    let tok = lexer.next_token();
+    assert_eq!(lexer.next_token(), Token::EOF, "trailing input after {:?}", src);
+    tok
+}
+
+#[test]
+fn integer_bases() {
+    assert_eq!(lex_one("42"), Token::Number(42, Type::Int));
+    assert_eq!(lex_one("0x1F"), Token::Number(31, Type::Int));
+    assert_eq!(lex_one("017"), Token::Number(15, Type::Int));
+    assert_eq!(lex_one("0b101"), Token::Number(5, Type::Int));
+    assert_eq!(lex_one("0"), Token::Number(0, Type::Int));
+}
+
+#[test]
+fn integer_suffixes_select_type() {
+    assert_eq!(lex_one("7u"), Token::Number(7, Type::UnsignedInt));
+    assert_eq!(lex_one("7l"), Token::Number(7, Type::Long));
+    assert_eq!(lex_one("7LL"), Token::Number(7, Type::LongLong));
+    assert_eq!(lex_one("7ul"), Token::Number(7, Type::UnsignedLong));
+    assert_eq!(lex_one("7LLU"), Token::Number(7, Type::UnsignedLongLong));
+}
+
+#[test]
+fn integer_type_grows_with_value() {
+    assert_eq!(lex_one("2147483648"), Token::Number(2147483648, Type::Long));
+    // hex constants may become unsigned before growing
+    assert_eq!(lex_one("0xFFFFFFFF"), Token::Number(0xFFFF_FFFF, Type::UnsignedInt));
+    assert_eq!(lex_one("0xFFFFFFFFFFFFFFFF"), Token::Number(-1, Type::UnsignedLong));
+}
+
+#[test]
+fn invalid_integer_literals_are_reported() {
+    assert_eq!(lex_one("09"), Token::InvalidNumber(NumberError::InvalidDigit('9', 8)));
+    assert_eq!(lex_one("0x"), Token::InvalidNumber(NumberError::NoDigits(16)));
+    assert_eq!(lex_all("0x;"), vec![Token::InvalidNumber(NumberError::NoDigits(16)), Token::Semicolon]);
+    assert_eq!(lex_one("99999999999999999999999"), Token::InvalidNumber(NumberError::TooLarge));
+    assert_eq!(lex_one("0x10000000000000000"), Token::InvalidNumber(NumberError::TooLarge));
+    assert_eq!(lex_one("18446744073709551615"), Token::Number(-1, Type::UnsignedLongLong));
+    assert_eq!(NumberError::InvalidDigit('9', 8).to_string(), "invalid digit '9' in octal constant");
+}
+
+#[test]
+fn a_number_runs_to_the_end_of_its_preprocessing_number() {
+    let invalid = |e| Token::InvalidNumber(e);
+    assert_eq!(lex_all("12abc;"), vec![invalid(NumberError::InvalidSuffix("abc".to_string())), Token::Semicolon]);
+    assert_eq!(lex_one("12ux"), invalid(NumberError::InvalidSuffix("ux".to_string())));
+    assert_eq!(lex_one("1.5fx"), invalid(NumberError::InvalidSuffix("fx".to_string())));
+    assert_eq!(lex_one("1.2.3"), invalid(NumberError::InvalidSuffix(".3".to_string())));
+    assert_eq!(lex_one("0x1e+2"), invalid(NumberError::InvalidSuffix("+2".to_string())));
+    assert_eq!(lex_one("0b102"), invalid(NumberError::InvalidDigit('2', 2)));
+    assert_eq!(lex_one("1e"), invalid(NumberError::NoExponentDigits));
+    assert_eq!(lex_one("2.5e+"), invalid(NumberError::NoExponentDigits));
+    assert_eq!(lex_one("0x1p"), invalid(NumberError::NoExponentDigits));
+    assert_eq!(lex_one("0x1.8"), invalid(NumberError::NoHexExponent));
+    assert_eq!(lex_all("0x1.8;"), vec![invalid(NumberError::NoHexExponent), Token::Semicolon]);
+    // an operator after a number is not part of it
+    assert_eq!(lex_all("1+2"), vec![Token::Number(1, Type::Int), Token::Plus, Token::Number(2, Type::Int)]);
+    assert_eq!(lex_all("1e3-x"), vec![Token::FloatNumber(1000.0, Type::Double), Token::Minus, Token::Ident("x".to_string())]);
+}
+
+#[test]
+fn floating_literals() {
+    assert_eq!(lex_one("1.5"), Token::FloatNumber(1.5, Type::Double));
+    assert_eq!(lex_one("1e-3"), Token::FloatNumber(0.001, Type::Double));
+    assert_eq!(lex_one(".5"), Token::FloatNumber(0.5, Type::Double));
+    assert_eq!(lex_one("2.f"), Token::FloatNumber(2.0, Type::Float));
+    assert_eq!(lex_one("2.5E+2L"), Token::FloatNumber(250.0, Type::LongDouble));
+    assert_eq!(lex_one("0x1.8p1"), Token::FloatNumber(3.0, Type::Double));
+    assert_eq!(lex_one("0x10p-4f"), Token::FloatNumber(1.0, Type::Float));
+}
+
+fn lex_all(src: &str) -> Vec<Token> {
+    Lexer::new(src).collect()
+}
+
+#[test]
+fn identifiers_follow_c11_rules() {
+    assert_eq!(
+        lex_all("my_var _start x1 café caf\\u00e9"),
+        vec![
+            Token::Ident("my_var".to_string()),
+            Token::Ident("_start".to_string()),
+            Token::Ident("x1".to_string()),
+            Token::Ident("café".to_string()),
+            Token::Ident("café".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn full_keyword_table() {
+    assert_eq!(
+        lex_all("while unsigned _Bool _Static_assert sizeof _Thread_local"),
+        vec![Token::While, Token::Unsigned, Token::Bool, Token::StaticAssert, Token::Sizeof, Token::ThreadLocal]
+    );
+    // keywords are case sensitive and must match whole words
+    assert_eq!(lex_all("While whiles"), vec![Token::Ident("While".to_string()), Token::Ident("whiles".to_string())]);
+    assert_eq!(Token::Register.keyword_text(), Some("register"));
+}
+
+#[test]
+fn crlf_bom_and_lone_cr_lex_like_lf() {
+    let unix = lex_all("int main() {\n    return 0;\n}\n");
+    assert_eq!(lex_all("\u{feff}int main() {\r\n    return 0;\r\n}\r\n"), unix);
+    assert_eq!(lex_all("int main() {\r    return 0;\r}\r"), unix);
+}
+
+#[test]
+fn backslash_newline_splices_lines() {
+    assert_eq!(lex_all("in\\\nt my\\\r\n_var"), vec![Token::Int, Token::Ident("my_var".to_string())]);
+    // a // comment continues onto the spliced line
+    assert_eq!(lex_all("// comment \\\nstill comment\nx"), vec![Token::Ident("x".to_string())]);
+}
+
+#[test]
+fn positions_are_tab_aware_and_follow_physical_lines() {
+    let mut lexer = Lexer::new("\tint x;\r\n  a\\\n  b c");
+    let positions: Vec<(usize, usize)> = (0..5).map(|_| lexer.next_token_with_pos().1).map(|p| (p.line, p.col)).collect();
+    assert_eq!(positions, vec![(1, 9), (1, 13), (1, 14), (2, 3), (3, 3)]);
+
+    let mut narrow = Lexer::with_tab_width("x\t\ty", 4);
+    narrow.next_token();
+    narrow.next_token();
+    assert_eq!(narrow.token_pos(), mini_c::lexer::SourcePos { line: 1, col: 9 });
+}
+
+#[test]
+fn iterator_stops_at_eof_and_exposes_token_text() {
+    let src = "int x = 0x1Fu; // done";
+    assert_eq!(Lexer::new(src).count(), 5);
+
+    let mut lexer = Lexer::new(src);
+    let texts: Vec<String> = std::iter::from_fn(|| lexer.next().map(|_| lexer.token_text().to_string())).collect();
+    assert_eq!(texts, vec!["int", "x", "=", "0x1Fu", ";"]);
+}
+
+#[test]
+fn invalid_universal_character_name_does_not_stall() {
+    assert_eq!(
+        lex_all("a \\u00 b"),
+        vec![
+            Token::Ident("a".to_string()),
+            Token::Unknown('\\'),
+            Token::Ident("u00".to_string()),
+            Token::Ident("b".to_string()),
+        ]
+    );
+    assert_eq!(lex_all("\\uZZ"), vec![Token::Unknown('\\'), Token::Ident("uZZ".to_string())]);
+}
+
+#[test]
+fn comparison_operators_take_the_longest_match() {
+    let tokens: Vec<Token> = Lexer::new("a<=b >c==d!=!e<f>=g=h").collect();
+    let ops: Vec<Token> = tokens.into_iter().filter(|t| !matches!(t, Token::Ident(_))).collect();
+    assert_eq!(
+        ops,
+        vec![Token::LessEq, Token::Greater, Token::EqEq, Token::NotEq, Token::Bang, Token::Less, Token::GreaterEq, Token::Assign]
+    );
+}
@@ -0,0 +1,55 @@
+use mini_c::codegen;
+use mini_c::lexer::Lexer;
+use mini_c::parser::Parser;
+use mini_c::semantic;
+
+// the messages of the parse errors in a program
+fn parse_errors(src: &str) -> Vec<String> {
+    let mut parser = Parser::new(Lexer::new(src).collect());
+    parser.parse_program();
+    parser.errors().iter().map(|e| e.to_string()).collect()
+}
+
+#[test]
+fn malformed_static_assertions_are_errors() {
+    let expected_message = vec!["expected ',' and a string literal in _Static_assert"];
+    assert_eq!(parse_errors("int main(void) { _Static_assert(1 == 2); return 3; }"), expected_message);
+    assert_eq!(parse_errors("int main(void) { _Static_assert(1 == 2,); return 3; }"), expected_message);
+    assert_eq!(parse_errors("int main(void) { _Static_assert(1 == 2, 5); return 3; }"), expected_message);
+    assert_eq!(parse_errors("int main(void) { _Static_assert 1, \"x\"; return 3; }"), vec!["expected '(' after _Static_assert"]);
+    assert_eq!(parse_errors("int main(void) { _Static_assert(1, \"x\"; return 3; }"), vec!["expected ')' after the _Static_assert message"]);
+    assert_eq!(parse_errors("int main(void) { _Static_assert(1, \"x\") return 3; }"), vec!["expected ';'"]);
+    assert_eq!(parse_errors("int main(void) { _Static_assert(, \"x\"); return 3; }"), vec!["expected an expression"]);
+    // at file scope too, without losing the functions after it
+    assert_eq!(parse_errors("_Static_assert(1); int main(void) { return 3; }"), expected_message);
+    assert_eq!(parse_errors("_Static_assert(1, \"x\"); int main(void) { return 3; }"), Vec::<String>::new());
+}
+
+#[test]
+fn a_bad_statement_does_not_end_the_function() {
+    let src = "int main(void) { int a = 1; a = ; int = 2; return 3; }";
+    assert_eq!(parse_errors(src), vec!["expected an expression", "expected a name in the declaration"]);
+    let mut parser = Parser::new(Lexer::new(src).collect());
+    let prog = parser.parse_program();
+    assert_eq!(prog.functions[0].body.stmts.len(), 2);
+    assert_eq!(parse_errors("int main(void) { return 1 return 2; }"), vec!["expected ';'"]);
+    assert_eq!(parse_errors("int main(void) { return 1; } 5; int f(void) { return 2; }"), vec!["expected a function definition"]);
+}
+
+#[test]
+fn empty_statements_are_accepted() {
+    let src = "int main(void) { int a = 1;; ; return a + 6; }";
+    assert_eq!(parse_errors(src), Vec::<String>::new());
+    let prog = Parser::new(Lexer::new(src).collect()).parse_program();
+    let tables = semantic::analyze(&prog).unwrap();
+    assert_eq!(codegen::run(&prog, &tables), Ok(7));
+}
+
+#[test]
+fn malformed_branches_and_loops_are_errors() {
+    assert_eq!(parse_errors("int main(void) { if 1) return 1; return 0; }"), vec!["expected '(' after 'if'"]);
+    assert_eq!(parse_errors("int main(void) { while (1 return 1; return 0; }"), vec!["expected ')' after the condition of 'while'"]);
+    assert_eq!(parse_errors("int main(void) { if (1) int x = 1; return 0; }"), vec!["expected a statement, found a declaration"]);
+    assert_eq!(parse_errors("int main(void) { if (1) { return 1; "), vec!["expected '}' at the end of the input", "expected '}' at the end of the input"]);
+    assert_eq!(parse_errors("int main(void) { if (1) { x = ; } else return 1; return 0; }"), vec!["expected an expression"]);
+}
@@ -0,0 +1,107 @@
+use mini_c::preprocessor::{Preprocessor, SourceLoc};
+use std::fs;
+
+fn pp(src: &str) -> String {
+    let out = Preprocessor::new().preprocess_str(src, "test.c").expect("preprocessing failed");
+    // compare token spelling only, not the exact spacing
+    out.text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[test]
+fn object_and_function_like_macros() {
+    let src = "#define N 10\n#define SQ(x) ((x) * (x))\nint a = SQ(N + 1);\n";
+    assert_eq!(pp(src), "int a = ((10 + 1) * (10 + 1));");
+}
+
+#[test]
+fn stringize_paste_and_varargs() {
+    let src = concat!(
+        "#define STR(x) #x\n",
+        "#define CAT(a, b) a ## b\n",
+        "#define CALL(f, ...) f(__VA_ARGS__)\n",
+        "STR(a \"b\" c) CAT(my, _var) CAT(, x) CALL(printf, \"%d\", 1)\n",
+    );
+    assert_eq!(pp(src), "\"a \\\"b\\\" c\" my_var x printf(\"%d\", 1)");
+}
+
+#[test]
+fn recursive_macros_do_not_loop() {
+    let src = "#define foo foo + bar\n#define bar foo\nfoo\n";
+    assert_eq!(pp(src), "foo + foo");
+}
+
+#[test]
+fn conditionals_evaluate_constant_expressions() {
+    let src = concat!(
+        "#define V 3\n",
+        "#if V * 2 > 5 && defined(V)\nyes1\n#else\nno1\n#endif\n",
+        "#ifdef MISSING\nno2\n#elif UNKNOWN || (1 << 4) == 0x10\nyes2\n#endif\n",
+        "#ifndef V\nno3\n#elif 1\nyes3\n#else\nno4\n#endif\n",
+        "#if 0\n#if 1\nno6\n#endif\n' unbalanced quote is fine here\n#endif\n",
+    );
+    assert_eq!(pp(src), "yes1 yes2 yes3");
+}
+
+#[test]
+fn line_and_file_and_locations() {
+    let out = Preprocessor::new().preprocess_str("#define X 1\n\nint a = __LINE__;\n  X __FILE__\n", "dir/a.c").unwrap();
+    assert_eq!(out.text, "int a = 3;\n  1 \"dir/a.c\"\n");
+    assert_eq!(out.location(2), Some(&SourceLoc { file: "dir/a.c".to_string(), line: 4 }));
+}
+
+#[test]
+fn command_line_defines() {
+    let mut p = Preprocessor::new();
+    p.define("DEBUG");
+    p.define("LEVEL=2");
+    p.define("__STDC_HOSTED__=0");
+    p.undefine("__STDC_HOSTED__");
+    let out = p.preprocess_str("DEBUG LEVEL __STDC_HOSTED__ __STDC__\n", "t.c").unwrap();
+    assert_eq!(out.text.trim(), "1 2 __STDC_HOSTED__ 1");
+}
+
+#[test]
+fn includes_search_paths_and_pragma_once() {
+    let root = std::env::temp_dir().join(format!("mini-c-pp-{}", std::process::id()));
+    let inc = root.join("inc");
+    fs::create_dir_all(&inc).unwrap();
+    fs::write(inc.join("once.h"), "#pragma once\nint from_once;\n").unwrap();
+    fs::write(root.join("local.h"), "#include <once.h>\nint from_local;\n").unwrap();
+    fs::write(root.join("main.c"), "#include \"local.h\"\n#include \"once.h\"\n#include <stdio.h>\nint x = EOF;\n").unwrap();
+
+    let mut p = Preprocessor::new();
+    p.add_include_path(&inc);
+    let out = p.preprocess_file(root.join("main.c")).unwrap();
+    let text = out.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    assert_eq!(text, "int from_once; int from_local; int x = (-1);");
+    assert_eq!(out.headers, vec!["stdio.h".to_string()]);
+    assert!(out.location(1).unwrap().file.ends_with("once.h"));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn errors_carry_locations() {
+    let errs = Preprocessor::new().preprocess_str("int a;\n  #error stop here\n#include <nope.h>\n#if 1\n", "e.c").unwrap_err();
+    let msgs: Vec<String> = errs.iter().map(|e| e.to_string()).collect();
+    assert_eq!(msgs[0], "e.c:2:4: #error stop here");
+    assert!(msgs[1].starts_with("e.c:3:2: 'nope.h' file not found"));
+    assert!(msgs[2].contains("unterminated conditional"));
+}
+
+#[test]
+fn windows_sources_and_continued_directives() {
+    let src = "\u{feff}#define SUM(a, b) \\\r\n    ((a) + (b))\r\nint s = SUM(1, 2);\r\n\t#error bad\r\n";
+    let errs = Preprocessor::new().preprocess_str(src, "w.c").unwrap_err();
+    // the tab counts up to the next tab stop, the spliced line is still line 2
+    assert_eq!(errs[0].to_string(), "w.c:4:10: #error bad");
+
+    let mut p = Preprocessor::new();
+    p.set_tab_width(4);
+    let errs = p.preprocess_str(src, "w.c").unwrap_err();
+    assert_eq!((errs[0].line, errs[0].col), (4, 6));
+
+    let out = Preprocessor::new().preprocess_str("#define SUM(a, b) \\\r\n    ((a) + (b))\r\nint s = SUM(1, 2);\r\n", "w.c").unwrap();
+    assert_eq!(out.text, "int s = ((1) + (2));\n");
+    assert_eq!(out.location(1).unwrap().line, 3);
+}
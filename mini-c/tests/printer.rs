@@ -0,0 +1,208 @@
+use mini_c::ast::{BinaryOp, Block, Expr, ExprKind, Function, NodeId, Program, Stmt, StmtKind, Type, UnaryOp};
+use mini_c::lexer::Lexer;
+use mini_c::parser::Parser;
+use mini_c::printer::{print_expr, print_program, print_stmt};
+
+fn parse(src: &str) -> Program {
+    Parser::new(Lexer::new(src).collect()).parse_program()
+}
+
+// small deterministic xorshift generator so failures are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+const NAMES: &[&str] = &["a", "b", "x1", "total", "_tmp", "value"];
+const FUNCS: &[&str] = &["f", "g", "printf", "compute"];
+const CHARS: &[char] = &['a', 'Z', '0', '7', ' ', '?', '"', '\'', '\\', '\n', '\t', '\r', '\0', '\x07', '\x1b', 'é', '€', '\u{1F600}'];
+const VAR_TYPES: &[Type] = &[
+    Type::Int, Type::Char, Type::Float, Type::Double, Type::LongDouble, Type::UnsignedInt,
+    Type::Long, Type::UnsignedLong, Type::LongLong, Type::UnsignedLongLong,
+];
+
+fn gen_literal(rng: &mut Rng) -> Expr {
+    match rng.below(5) {
+        0 => Expr::new(ExprKind::Number(rng.below(i32::MAX as u64 + 1) as i64, Type::Int)),
+        1 => {
+            let ty = rng.pick(&[Type::UnsignedInt, Type::Long, Type::UnsignedLong, Type::LongLong, Type::UnsignedLongLong]).clone();
+            let value = match ty {
+                Type::UnsignedInt => rng.below(u32::MAX as u64 + 1) as i64,
+                Type::Long | Type::LongLong => (rng.next() >> 1) as i64,
+                _ => rng.next() as i64,
+            };
+            Expr::new(ExprKind::Number(value, ty))
+        }
+        2 => {
+            let ty = rng.pick(&[Type::Float, Type::Double, Type::LongDouble]).clone();
+            let mut value = f64::from_bits(rng.next() >> 2);
+            if ty == Type::Float {
+                value = f32::from_bits((rng.next() >> 34) as u32) as f64;
+            }
+            Expr::new(ExprKind::FloatNumber(value, ty))
+        }
+        3 => Expr::new(ExprKind::CharLiteral(*rng.pick(CHARS))),
+        _ => Expr::new(ExprKind::StringLiteral((0..rng.below(6)).map(|_| *rng.pick(CHARS)).collect())),
+    }
+}
+
+fn gen_expr(rng: &mut Rng, depth: u32) -> Expr {
+    if depth == 0 {
+        return match rng.below(2) {
+            0 => gen_literal(rng),
+            _ => Expr::new(ExprKind::Ident(rng.pick(NAMES).to_string())),
+        };
+    }
+    let kind = match rng.below(7) {
+        0 => return gen_literal(rng),
+        1 => ExprKind::Ident(rng.pick(NAMES).to_string()),
+        2 => ExprKind::Unary { op: *rng.pick(&[UnaryOp::Neg, UnaryOp::Not]), expr: Box::new(gen_expr(rng, depth - 1)) },
+        3 | 4 => ExprKind::Binary {
+            op: *rng.pick(&[
+                BinaryOp::Add, BinaryOp::Sub, BinaryOp::Mul, BinaryOp::Div,
+                BinaryOp::Lt, BinaryOp::Gt, BinaryOp::Le, BinaryOp::Ge, BinaryOp::Eq, BinaryOp::Ne,
+            ]),
+            left: Box::new(gen_expr(rng, depth - 1)),
+            right: Box::new(gen_expr(rng, depth - 1)),
+        },
+        5 => ExprKind::Assign { target: Box::new(gen_expr(rng, depth - 1)), value: Box::new(gen_expr(rng, depth - 1)) },
+        _ => ExprKind::Call {
+            name: rng.pick(FUNCS).to_string(),
+            args: (0..rng.below(4)).map(|_| gen_expr(rng, depth - 1)).collect(),
+        },
+    };
+    Expr::new(kind)
+}
+
+fn gen_program(rng: &mut Rng) -> Program {
+    let functions = (0..1 + rng.below(3))
+        .map(|i| Function {
+            id: NodeId::DUMMY,
+            name: format!("func{}", i),
+            return_type: rng.pick(&[Type::Void, Type::Int, Type::Double]).clone(),
+            params: (0..rng.below(4)).map(|p| (rng.pick(VAR_TYPES).clone(), format!("p{}", p))).collect(),
+            prototyped: true,
+            body: gen_block(rng, 2),
+        })
+        .collect();
+    Program { functions, static_asserts: Vec::new() }
+}
+
+fn gen_block(rng: &mut Rng, depth: u32) -> Block {
+    Block { stmts: (0..rng.below(6)).map(|_| gen_stmt(rng, depth, true)).collect() }
+}
+
+// declarations only appear directly in blocks
+fn gen_stmt(rng: &mut Rng, depth: u32, in_block: bool) -> Stmt {
+    let kinds = if depth == 0 { 4 } else { 8 };
+    Stmt::new(match rng.below(kinds) {
+        0 if in_block => StmtKind::VarDecl { ty: rng.pick(VAR_TYPES).clone(), name: rng.pick(NAMES).to_string(), value: (rng.below(4) > 0).then(|| gen_expr(rng, 4)), is_const: rng.below(2) == 0 },
+        0 | 1 => StmtKind::ExprStmt(gen_expr(rng, 4)),
+        2 => StmtKind::Return(None),
+        3 => StmtKind::Return(Some(gen_expr(rng, 4))),
+        4 => StmtKind::Block(gen_block(rng, depth - 1)),
+        5 => StmtKind::While { cond: gen_expr(rng, 2), body: Box::new(gen_stmt(rng, depth - 1, false)) },
+        _ => {
+            let cond = gen_expr(rng, 2);
+            let then = Box::new(gen_stmt(rng, depth - 1, false));
+            // the parser gives an `else` to the innermost `if` without one
+            let otherwise = (rng.below(2) == 0 && !ends_in_open_if(&then)).then(|| Box::new(gen_stmt(rng, depth - 1, false)));
+            StmtKind::If { cond, then, otherwise }
+        }
+    })
+}
+
+fn ends_in_open_if(stmt: &Stmt) -> bool {
+    match &stmt.kind {
+        StmtKind::If { otherwise: None, .. } => true,
+        StmtKind::If { otherwise: Some(last), .. } | StmtKind::While { body: last, .. } => ends_in_open_if(last),
+        _ => false,
+    }
+}
+
+#[test]
+fn generated_programs_round_trip() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    for case in 0..500 {
+        let program = gen_program(&mut rng);
+        let source = print_program(&program);
+        assert_eq!(parse(&source), program, "case {}:\n{}", case, source);
+        // printing is canonical: a second round gives the same text
+        assert_eq!(print_program(&parse(&source)), source);
+    }
+}
+
+#[test]
+fn parentheses_only_where_needed() {
+    let program = parse("int main() { return (a - (b - c)) * -(x = 1) + (f((1), 2) / 3); }");
+    let StmtKind::Return(Some(expr)) = &program.functions[0].body.stmts[0].kind else { panic!("expected a return") };
+    assert_eq!(print_expr(expr), "(a - (b - c)) * -(x = 1) + f(1, 2) / 3");
+
+    let y = Expr::new(ExprKind::Ident("y".to_string()));
+    let nested = Expr::new(ExprKind::Unary { op: UnaryOp::Neg, expr: Box::new(Expr::new(ExprKind::Unary { op: UnaryOp::Neg, expr: Box::new(y) })) });
+    assert_eq!(print_expr(&nested), "-(-y)");
+}
+
+#[test]
+fn literals_keep_their_type_and_escapes() {
+    let program = parse("long long main(void) { printf(\"tab\\there\\n\\0017\" \"!\", 'q', '\\'', 7ul, 2.5f, 0x10p0l); return 1ll; }");
+    assert_eq!(
+        print_program(&program),
+        "long long main(void) {\n    printf(\"tab\\there\\n\\0017!\", 'q', '\\'', 7ul, 2.5f, 16.0l);\n    return 1ll;\n}\n"
+    );
+}
+
+#[test]
+fn synthesized_literals_print_as_c() {
+    let number = |n, ty| print_expr(&Expr::new(ExprKind::Number(n, ty)));
+    assert_eq!(number(-1, Type::Int), "(-1)");
+    assert_eq!(number(i32::MIN as i64, Type::Int), "(-2147483647 - 1)");
+    assert_eq!(number(i64::MIN, Type::LongLong), "(-9223372036854775807ll - 1)");
+    assert_eq!(number(-1, Type::UnsignedInt), "4294967295u");
+
+    let float = |f, ty| print_expr(&Expr::new(ExprKind::FloatNumber(f, ty)));
+    assert_eq!(float(-2.5, Type::Double), "(-2.5)");
+    assert_eq!(float(f64::INFINITY, Type::Double), "(1.0 / 0.0)");
+    assert_eq!(float(f64::NEG_INFINITY, Type::Float), "(-1.0f / 0.0f)");
+    assert_eq!(float(f64::NAN, Type::LongDouble), "(0.0l / 0.0l)");
+
+    // the text parses back to an expression with the same meaning
+    let negated = Expr::new(ExprKind::Unary { op: UnaryOp::Neg, expr: Box::new(Expr::new(ExprKind::Number(-1, Type::Int))) });
+    let source = format!("int main() {{ return {}; }}", print_expr(&negated));
+    assert_eq!(source, "int main() { return -(-1); }");
+    let StmtKind::Return(Some(expr)) = &parse(&source).functions[0].body.stmts[0].kind else { panic!("expected a return") };
+    let ExprKind::Unary { op: UnaryOp::Neg, expr: inner } = &expr.kind else { panic!("expected a negation") };
+    assert!(matches!(&inner.kind, ExprKind::Unary { op: UnaryOp::Neg, expr } if expr.kind == ExprKind::Number(1, Type::Int)));
+}
+
+#[test]
+fn an_else_stays_with_its_if() {
+    let program = parse("int main() { if (a) if (b) x = 1; else x = 2; if (a) { if (b) x = 3; } else if (b) x = 4; while (a) if (b) x = 5; else { x = 6; } }");
+    let source = print_program(&program);
+    assert_eq!(
+        source,
+        "int main() {\n    if (a)\n        if (b)\n            x = 1;\n        else\n            x = 2;\n    if (a) {\n        if (b)\n            x = 3;\n    } else if (b)\n        x = 4;\n    while (a)\n        if (b)\n            x = 5;\n        else {\n            x = 6;\n        }\n}\n"
+    );
+    assert_eq!(parse(&source), program);
+}
+
+#[test]
+fn a_synthesized_if_without_else_before_an_else_gets_braces() {
+    let open = Stmt::new(StmtKind::If { cond: Expr::new(ExprKind::Ident("b".to_string())), then: Box::new(Stmt::new(StmtKind::Return(None))), otherwise: None });
+    let stmt = Stmt::new(StmtKind::If { cond: Expr::new(ExprKind::Ident("a".to_string())), then: Box::new(open), otherwise: Some(Box::new(Stmt::new(StmtKind::Empty))) });
+    assert_eq!(print_stmt(&stmt), "if (a) {\n    if (b)\n        return;\n} else\n    ;");
+}
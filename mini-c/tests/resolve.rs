@@ -0,0 +1,71 @@
+use mini_c::ast::{ExprKind, Program, StmtKind};
+use mini_c::lexer::Lexer;
+use mini_c::parser::Parser;
+use mini_c::resolve::{resolve, ScopeId};
+use mini_c::symbol::Symbol;
+
+fn parse(src: &str) -> Program {
+    Parser::new(Lexer::new(src).collect()).parse_program()
+}
+
+const SRC: &str = "int f(int a) { int b = a + 1; a = b * a; return f(a); }\nint main() { int f = 2; int a = f; return a; }";
+
+#[test]
+fn every_use_is_bound_to_its_declaration() {
+    let prog = parse(SRC);
+    let res = resolve(&prog);
+    assert!(res.unresolved.is_empty());
+
+    // `f` inside main is the local variable, not the function
+    let main = &prog.functions[1];
+    let StmtKind::VarDecl { value: Some(value), .. } = &main.body.stmts[1].kind else { panic!() };
+    let local_f = res.binding(value.id).unwrap();
+    assert_eq!(Some(local_f), res.declared_by(main.body.stmts[0].id));
+    assert!(matches!(res.declaration(local_f).symbol, Symbol::Variable { .. }));
+
+    // the recursive call in f binds to the function
+    let StmtKind::Return(Some(call)) = &prog.functions[0].body.stmts[2].kind else { panic!() };
+    assert_eq!(res.binding(call.id), res.declared_by(prog.functions[0].id));
+    assert!(matches!(&call.kind, ExprKind::Call { .. }));
+}
+
+#[test]
+fn references_and_scope_tree() {
+    let prog = parse(SRC);
+    let res = resolve(&prog);
+    let f = &prog.functions[0];
+    let param_a = res.params_of(f.id)[0];
+
+    // `a` in f: read in `b = a + 1`, assigned, read twice more
+    assert_eq!(res.references(param_a).len(), 4);
+    let main_a = res.declared_by(prog.functions[1].body.stmts[1].id).unwrap();
+    assert_ne!(param_a, main_a);
+    assert_eq!(res.references(main_a).len(), 1);
+
+    // global scope with one child scope per function, all retained
+    let global = res.scope(ScopeId::GLOBAL);
+    assert_eq!(global.symbols.len(), 2);
+    assert_eq!(global.children.len(), 2);
+    let body = global.children[0];
+    assert_eq!(res.scope(body).owner, Some(f.id));
+    assert_eq!(res.lookup(body, "b"), res.declared_by(f.body.stmts[0].id));
+    assert_eq!(res.lookup(ScopeId::GLOBAL, "b"), None);
+}
+
+#[test]
+fn unknown_names_are_collected() {
+    let res = resolve(&parse("int main() { printf(\"%d\", x); return 0; }"));
+    let names: Vec<&str> = res.unresolved.iter().map(|(_, n)| n.as_str()).collect();
+    assert_eq!(names, vec!["printf", "x"]);
+}
+
+#[test]
+fn redeclared_names_get_distinct_ids_and_ir_names() {
+    let prog = parse("int main() { int x = 1; int y = 2; return x; }\nint g(int x) { return x; }");
+    let res = resolve(&prog);
+    let main_x = res.declared_by(prog.functions[0].body.stmts[0].id).unwrap();
+    let g_x = res.params_of(prog.functions[1].id)[0];
+    // different functions may reuse a name without renaming
+    assert_eq!(res.unique_name(main_x), "x");
+    assert_eq!(res.unique_name(g_x), "x");
+}
@@ -2,19 +2,13 @@ use std::fs;
 use mini_c::lexer::Lexer;
 use mini_c::parser::Parser;
 use mini_c::semantic;
+use mini_c::ast::{assign_ids, ExprKind, StmtKind, Type};
+use mini_c::semantic::{AnalysisOptions, ConstValue};
+use mini_c::symbol::Symbol;
 
 fn parse_file(path: &str) -> mini_c::ast::Program {
     let input = fs::read_to_string(path).expect("Could not read file");
-    let mut lexer = Lexer::new(&input);
-    let mut tokens = Vec::new();
-    loop {
-        let tok = lexer.next_token();
-        if tok == mini_c::token::Token::EOF {
-            break;
-        }
-        tokens.push(tok);
-    }
-    let mut parser = Parser::new(tokens);
+    let mut parser = Parser::new(Lexer::new(&input).collect());
     parser.parse_program()
 }
 
@@ -31,3 +25,332 @@ fn bad_should_fail_semantic() {
     let res = semantic::analyze(&prog);
     assert!(res.is_err());
 }
+
+fn parse_source(src: &str) -> mini_c::ast::Program {
+    let mut parser = Parser::new(Lexer::new(src).collect());
+    parser.parse_program()
+}
+
+#[test]
+fn literal_suffix_types_are_checked() {
+    let ok = parse_source("float f() { return 1.5f; } int main() { int a = 5u; return 0x10; }");
+    assert!(semantic::analyze(&ok).is_ok());
+
+    let bad = parse_source("int main() { int a = 1.5; return 0; }");
+    let errs = semantic::analyze(&bad).unwrap_err();
+    assert!(errs.iter().any(|e| matches!(e, semantic::SemanticError::TypeMismatch { found: mini_c::ast::Type::Double, .. })));
+}
+
+#[test]
+fn keyword_used_as_identifier_is_reported() {
+    let prog = parse_source("int main() { int while = 1; unsigned long n = 2ul; return 0; }");
+    let errs = semantic::analyze(&prog).unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert!(matches!(&errs[0], semantic::SemanticError::ReservedKeyword { name, .. } if name == "while"));
+    assert!(errs[0].to_string().contains("reserved keyword"));
+}
+
+#[test]
+fn side_tables_record_types_symbols_and_constants() {
+    let prog = parse_source("int add(int a, int b) { return a + b; } int main() { double d = 1.5 * 2.0; int x = 2 + 3 * 4; return add(x, -1); }");
+    let tables = semantic::analyze(&prog).unwrap();
+    let stmts = &prog.functions[1].body.stmts;
+
+    let StmtKind::VarDecl { value: Some(d), .. } = &stmts[0].kind else { panic!() };
+    assert_eq!(tables.type_of(d), Some(&Type::Double));
+    assert_eq!(tables.const_of(d), Some(ConstValue::Float(3.0)));
+
+    let StmtKind::VarDecl { value: Some(x), .. } = &stmts[1].kind else { panic!() };
+    assert_eq!(tables.const_of(x), Some(ConstValue::Int(14)));
+
+    let StmtKind::Return(Some(call)) = &stmts[2].kind else { panic!() };
+    assert_eq!(tables.type_of(call), Some(&Type::Int));
+    assert!(matches!(tables.symbol_of(call), Some(Symbol::Function(sig)) if sig.name == "add"));
+    let ExprKind::Call { args, .. } = &call.kind else { panic!() };
+    assert!(matches!(tables.symbol_of(&args[0]), Some(Symbol::Variable { name, .. }) if name == "x"));
+    assert_eq!(tables.const_of(&args[0]), None);
+    assert_eq!(tables.const_of(&args[1]), Some(ConstValue::Int(-1)));
+}
+
+#[test]
+fn node_ids_are_unique_and_reproducible() {
+    let mut prog = parse_source("int f(int a) { int b = a * 2 + 1; return f(b - a); } int main() { return f(3); }");
+    let mut ids = Vec::new();
+    for func in &prog.functions {
+        ids.push(func.id);
+        for stmt in &func.body.stmts {
+            ids.push(stmt.id);
+        }
+    }
+    let parsed = ids.clone();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), parsed.len());
+
+    // renumbering a parsed tree gives the ids the parser handed out
+    let before = prog.clone();
+    assign_ids(&mut prog);
+    assert!(prog.functions.iter().zip(&before.functions).all(|(a, b)| a.id == b.id && a.body.stmts.iter().zip(&b.body.stmts).all(|(x, y)| x.id == y.id)));
+}
+
+#[test]
+fn returns_must_match_the_function_and_cover_every_path() {
+    use semantic::SemanticError::*;
+    let prog = parse_source("int f(int a) { a = a + 1; } void g() { return 1; } int h() { return; } void k() { printf(\"k\"); return; } int main() { k(); }");
+    let errs = semantic::analyze(&prog).unwrap_err();
+    assert_eq!(errs.len(), 3, "{:?}", errs);
+    assert!(matches!(&errs[0], ReturnMissing { func, expected: Type::Int } if func == "f"));
+    assert!(matches!(&errs[1], ReturnValueInVoid { func } if func == "g"));
+    assert!(matches!(&errs[2], ReturnWithoutValue { func, .. } if func == "h"));
+}
+
+#[test]
+fn branches_join_in_the_return_check() {
+    let ok = parse_source(
+        "int f(int c) { if (c) return 1; else return 2; }
+         int g(int c) { if (c) { return 1; } else if (c == 2) return 2; else { return 3; } }
+         int h(void) { while (1) { } }
+         int k(int c) { { return c; } }
+         int main() { return f(1) + g(2) + h() + k(3); }",
+    );
+    assert!(semantic::analyze(&ok).is_ok());
+
+    use semantic::SemanticError::ReturnMissing;
+    let bad = parse_source(
+        "int f(int c) { if (c) return 1; }
+         int g(int c) { if (c) return 1; else c = 2; }
+         int h(int c) { while (c) return 1; }
+         int main() { return f(1) + g(2) + h(3); }",
+    );
+    let errs = semantic::analyze(&bad).unwrap_err();
+    let missing: Vec<_> = errs.iter().filter_map(|e| if let ReturnMissing { func, .. } = e { Some(func.as_str()) } else { None }).collect();
+    assert_eq!(missing, vec!["f", "g", "h"], "{:?}", errs);
+}
+
+#[test]
+fn statements_after_return_are_warned_about() {
+    let prog = parse_source("int main() { int x = 1; return x; x = 2; printf(\"%d\", x); }");
+    let tables = semantic::analyze(&prog).unwrap();
+    let unreachable: Vec<_> = tables.warnings.iter().filter(|w| w.name() == "unreachable-code").collect();
+    assert_eq!(unreachable.len(), 1);
+    assert_eq!(unreachable[0].to_string(), "Unreachable code in function 'main': `x = 2;` follows a return.");
+}
+
+#[test]
+fn statements_after_a_return_in_a_branch_are_warned_about() {
+    let prog = parse_source("int main() { int x = 1; if (x) { return x; x = 2; } else return 0; x = 3; }");
+    let tables = semantic::analyze(&prog).unwrap();
+    let unreachable: Vec<String> = tables.warnings.iter().filter(|w| w.name() == "unreachable-code").map(|w| w.to_string()).collect();
+    assert_eq!(
+        unreachable,
+        vec![
+            "Unreachable code in function 'main': `x = 2;` follows a return.",
+            "Unreachable code in function 'main': `x = 3;` follows a return.",
+        ]
+    );
+}
+
+#[test]
+fn call_arguments_are_checked_against_the_prototype() {
+    use semantic::SemanticError::*;
+    let prog = parse_source(
+        "int f(void) { return 1; } int g() { return 2; } double h(double x, char c) { return x; } void v(int n) { return; }
+         int main() { int n = 0; f(1, 2); g(1, 2); h(1.5f, 7); h(n, 'c'); n(3); int r = v(1); v(v(2)); v('a'); return 0; }",
+    );
+    let errs = semantic::analyze(&prog).unwrap_err();
+    assert_eq!(errs.len(), 5, "{:?}", errs);
+    assert!(matches!(&errs[0], WrongArgCount { name, expected: 0, found: 2, .. } if name == "f"));
+    assert!(matches!(&errs[1], ArgTypeMismatch { name, index: 1, expected: Type::Double, found: Type::Int, .. } if name == "h"));
+    assert!(matches!(&errs[2], NotAFunction { name, .. } if name == "n"));
+    assert!(matches!(&errs[3], VoidValueUsed { name, .. } if name == "v"));
+    assert!(matches!(&errs[4], VoidValueUsed { name, .. } if name == "v"));
+    assert_eq!(errs[1].to_string(), "Argument 1 of call to 'h' in function 'main' has type int, expected double.");
+}
+
+#[test]
+fn included_headers_declare_library_functions() {
+    use semantic::SemanticError::*;
+    let options = |headers: &[&str]| AnalysisOptions { headers: headers.iter().map(|h| h.to_string()).collect(), ..Default::default() };
+    let prog = parse_source(
+        "int main() { double d = sqrt(2.0); int n = printf(\"%f\\n\", pow(d, 2.0)); puts(n); printf(); strlen(1); return 0; }",
+    );
+    let errs = semantic::analyze_with(&prog, &options(&["stdio.h", "math.h"])).unwrap_err();
+    assert_eq!(errs.len(), 2, "{:?}", errs);
+    assert!(matches!(&errs[0], ArgTypeMismatch { name, index: 1, found: Type::Int, .. } if name == "puts"));
+    assert!(matches!(&errs[1], TooFewArgs { name, min: 1, found: 0, .. } if name == "printf"));
+
+    // without the header the calls are unchecked
+    let prog = parse_source("int main() { puts(1); return sqrt(4.0, 1); }");
+    assert!(semantic::analyze(&prog).is_ok());
+
+    let prog = parse_source("int abs(int x) { return x; } double sqrt() { return 1.0; } long labs(int x) { return x; } int main() { return abs(-1) + (sqrt() > 0.0); }");
+    let errs = semantic::analyze_with(&prog, &options(&["stdlib.h", "math.h"])).unwrap_err();
+    assert_eq!(errs.len(), 1, "{:?}", errs);
+    assert_eq!(errs[0].to_string(), "Conflicting types for 'labs': the standard library declares `long labs(long)`.");
+}
+
+#[test]
+fn main_must_have_a_standard_signature() {
+    use semantic::SemanticError::*;
+    for ok in ["int main() { }", "int main(void) { return 0; }", "int main(int argc, char **argv) { return argc; }"] {
+        assert!(semantic::analyze(&parse_source(ok)).is_ok(), "{}", ok);
+    }
+    for bad in ["void main() { }", "int main(int argc) { return argc; }", "double main(void) { return 0.0; }", "int main(long n, char **argv) { return 0; }"] {
+        let errs = semantic::analyze(&parse_source(bad)).unwrap_err();
+        assert!(matches!(&errs[..], [InvalidMain { .. }]), "{}: {:?}", bad, errs);
+    }
+    let errs = semantic::analyze(&parse_source("int main(int argc) { return argc; }")).unwrap_err();
+    assert_eq!(errs[0].to_string(), "'main' must be declared as `int main(void)` or `int main(int argc, char **argv)`, found `int main(int)`.");
+
+    // only an executable needs an entry point
+    let prog = parse_source("int f(void) { return 1; }");
+    assert!(semantic::analyze(&prog).is_ok());
+    let errs = semantic::analyze_with(&prog, &AnalysisOptions { executable: true, ..Default::default() }).unwrap_err();
+    assert!(matches!(&errs[..], [MissingMain]));
+
+    // falling off the end of main returns 0
+    let prog = parse_source("int main() { int x = 1; }");
+    let tables = semantic::analyze(&prog).unwrap();
+    let ir = mini_c::lower::lower_program(&prog, &tables);
+    assert_eq!(ir[0].instrs.last().unwrap().to_string(), "return 0");
+    assert_eq!(mini_c::codegen::run(&prog, &tables), Ok(0));
+}
+
+#[test]
+fn only_modifiable_lvalues_can_be_assigned() {
+    use semantic::SemanticError::*;
+    let prog = parse_source(
+        "int f(double p) { p = 2.0; return 0; }
+         int main() { const int c = 1; int x = 0; x = c; c = 2; f = 3; x + 1 = 4; (x) = 5; -x = 6; x = 1.5; return 0; }",
+    );
+    let errs = semantic::analyze(&prog).unwrap_err();
+    assert_eq!(errs.len(), 5, "{:?}", errs);
+    assert!(matches!(&errs[0], AssignToConst { name, .. } if name == "c"));
+    assert!(matches!(&errs[1], AssignToFunction { name, .. } if name == "f"));
+    assert_eq!(errs[2].to_string(), "Cannot assign to 'x + 1' in function 'main': not an lvalue.");
+    assert!(matches!(&errs[3], NotAnLvalue { expr, .. } if expr == "-x"));
+    assert!(matches!(&errs[4], TypeMismatch { expected: Type::Int, found: Type::Double, .. }));
+}
+
+#[test]
+fn assignments_have_the_type_of_their_target() {
+    let prog = parse_source("int main() { double d = 0.0; float f = 1.0f; d = f = 2.0f; return 0; }");
+    let tables = semantic::analyze(&prog).unwrap();
+    let StmtKind::ExprStmt(assign) = &prog.functions[0].body.stmts[2].kind else { panic!() };
+    assert_eq!(tables.type_of(assign), Some(&Type::Double));
+    let ExprKind::Assign { value, .. } = &assign.kind else { panic!() };
+    assert_eq!(tables.type_of(value), Some(&Type::Float));
+}
+
+#[test]
+fn locals_must_be_assigned_before_they_are_read() {
+    let prog = parse_source(
+        "int f(int p) { int a; int b = p; a = b; return a + p; }
+         int main() { int x; int y = x + 1; int z; int w = (z = 1) + z; int self = self; return y; }",
+    );
+    let errs = semantic::analyze(&prog).unwrap_err();
+    assert_eq!(errs.len(), 2, "{:?}", errs);
+    assert_eq!(
+        errs[0].to_string(),
+        "Variable 'x' is read before it is assigned in function 'main': declared by `int x;`, read in `int y = x + 1;`."
+    );
+    assert!(matches!(&errs[1], semantic::SemanticError::UninitializedRead { name, .. } if name == "self"));
+
+    // a local assigned on only one branch may be unset after the `if`
+    let prog = parse_source("int main() { int c = 1; int x; if (c) x = 1; return x; }");
+    let tables = semantic::analyze(&prog).unwrap();
+    let found: Vec<String> = tables.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(found, vec!["Variable 'x' may be read before it is assigned in function 'main': declared by `int x;`, read in `return x;`."]);
+    for src in [
+        "int main() { int c = 1; int x; if (c) x = 1; else x = 2; return x; }",
+        "int main() { int c = 1; int x; if (c) return 0; else x = 1; return x; }",
+        "int main() { int c = 1; int x; if (c) { x = 1; } else { if (c) x = 2; else return 0; } return x; }",
+    ] {
+        assert!(semantic::analyze(&parse_source(src)).unwrap().warnings.is_empty(), "{}", src);
+    }
+    // a loop body may not run, and later iterations see earlier ones
+    let prog = parse_source("int main() { int c = 1; int x; while (c) { c = x; x = 0; } return c; }");
+    let tables = semantic::analyze(&prog).unwrap();
+    assert!(matches!(&tables.warnings[..], [semantic::SemanticWarning::MaybeUninitialized { name, .. }] if name == "x"));
+    let prog = parse_source("int main() { int c = 1; int x; while (c) x = 0; return x; }");
+    assert!(matches!(&semantic::analyze(&prog).unwrap().warnings[..], [semantic::SemanticWarning::MaybeUninitialized { name, .. }] if name == "x"));
+    let prog = parse_source("int main() { int c = 1; while (c) { int x; c = x; } return c; }");
+    let errs = semantic::analyze(&prog).unwrap_err();
+    assert!(matches!(&errs[..], [semantic::SemanticError::UninitializedRead { name, .. }] if name == "x"));
+
+    // a local whose address is taken may be assigned through the pointer, or not
+    let uninitialized = |src: &str| -> Vec<String> {
+        let tables = semantic::analyze(&parse_source(src)).unwrap();
+        tables.warnings.iter().filter(|w| w.name() == "maybe-uninitialized").map(|w| w.to_string()).collect()
+    };
+    assert_eq!(
+        uninitialized("int main() { int x; int *p = &x; return x; }"),
+        vec!["Variable 'x' may be read before it is assigned in function 'main': declared by `int x;`, read in `return x;`."]
+    );
+    assert!(uninitialized("int main() { int x; int *p = &x; x = 1; return x; }").is_empty());
+}
+
+#[test]
+fn unsequenced_accesses_are_undefined() {
+    // the operands of `+` are unsequenced, so `z` is read while it is assigned
+    let prog = parse_source("int main() { int z; int w = (z = 1) + z; return w; }");
+    let found: Vec<String> = semantic::analyze(&prog).unwrap().warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        found,
+        vec!["Unsequenced accesses to 'z' in function 'main': `int w = (z = 1) + z;` assigns it in one operand and uses it in another, which is undefined behaviour."]
+    );
+
+    let prog = parse_source("int f(int a, int b) { return a + b; }\nint main() { int x = 0; int y = 0; x = f(x = 1, x = 2) + (y = 3); return x + y; }");
+    let tables = semantic::analyze(&prog).unwrap();
+    assert!(matches!(&tables.warnings[..], [semantic::SemanticWarning::Unsequenced { name, .. }] if name == "x"));
+
+    // an assignment sequences its value before the store, and so does a statement
+    let prog = parse_source("int main() { int x = 1; x = x + 1; int y = x * (x - 1); return y; }");
+    assert!(semantic::analyze(&prog).unwrap().warnings.is_empty());
+}
+
+#[test]
+fn static_assertions_are_checked_at_file_and_block_scope() {
+    let src = "_Static_assert(4 * 2 == 8, \"eight\");\nint main() { int n = 1; _Static_assert(3 > 2, \"order\"); return n; }\n";
+    let prog = parse_source(src);
+    assert_eq!(prog.static_asserts.len(), 1);
+    assert!(semantic::analyze(&prog).is_ok());
+    assert_eq!(parse_source(&mini_c::printer::print_program(&prog)), prog);
+
+    let prog = parse_source("_Static_assert(4 * 2 == 9, \"size \" \"mismatch\");\nint main() { int n = 1; _Static_assert(n, \"const\"); _Static_assert(1 - 1, \"zero\"); return n; }");
+    let errs: Vec<String> = semantic::analyze(&prog).unwrap_err().iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        errs,
+        vec![
+            "Static assertion failed: \"size mismatch\": `4 * 2 == 9` evaluates to 8 == 9.",
+            "Static assertion condition `n` is not an integer constant expression.",
+            "Static assertion failed: \"zero\": `1 - 1` evaluates to 0.",
+        ]
+    );
+
+    // undefined arithmetic is an error there, not a warning
+    let prog = parse_source("_Static_assert(2147483647 + 1 > 0, \"x\");\nint main() { _Static_assert(1 / 0, \"y\"); return 0; }");
+    let errs: Vec<String> = semantic::analyze(&prog).unwrap_err().iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        errs,
+        vec![
+            "Static assertion condition `2147483647 + 1 > 0` is not an integer constant expression: integer overflow in `2147483647 + 1` of type int.",
+            "Static assertion condition `1 / 0` is not an integer constant expression: division by zero in `1 / 0`.",
+        ]
+    );
+    let tables = semantic::analyze(&parse_source("int main() { int x = 2147483647 + 1; return x; }")).unwrap();
+    assert!(matches!(&tables.warnings[..], [semantic::SemanticWarning::Overflow { .. }]));
+}
+
+#[test]
+fn only_variables_have_an_address() {
+    let prog = parse_source("int main() { int x; int y = 2; scanf(\"%d %d\", &x, &(y + 1)); return x; }");
+    let errs: Vec<String> = semantic::analyze(&prog).unwrap_err().iter().map(|e| e.to_string()).collect();
+    assert_eq!(errs, vec!["Cannot take the address of 'y + 1' in function 'main': only variables have one."]);
+
+    let prog = parse_source("int main() { int x; scanf(\"%d\", &x); return x; }");
+    let tables = semantic::analyze(&prog).unwrap();
+    let StmtKind::ExprStmt(call) = &prog.functions[0].body.stmts[1].kind else { panic!() };
+    let ExprKind::Call { args, .. } = &call.kind else { panic!() };
+    assert_eq!(tables.type_of(&args[1]), Some(&Type::Pointer(Box::new(Type::Int))));
+}
@@ -0,0 +1,56 @@
+use mini_c::parser::Parser;
+use mini_c::preprocessor::Preprocessor;
+use mini_c::semantic::{self, AnalysisOptions};
+use mini_c::standard::{violations, Feature, Standard};
+
+// the features each standard rejects in a preprocessed source, with their lines
+fn rejected(src: &str, std: Standard) -> Vec<(Feature, usize)> {
+    let mut pp = Preprocessor::new();
+    pp.set_standard(std);
+    let out = pp.preprocess_str(src, "test.c").unwrap();
+    let mut parser = Parser::with_positions(out.tokens_with_pos());
+    parser.parse_program();
+    violations(parser.features(), std).iter().map(|u| (u.feature, out.location(u.pos.unwrap().line).unwrap().line)).collect()
+}
+
+const SRC: &str = "f() { return 1; }\nint main() {\n  long long big = 1ll;\n  f();\n  int late = 2;\n  return late;\n}\n";
+
+#[test]
+fn each_standard_rejects_what_it_lacks() {
+    assert_eq!(rejected(SRC, Standard::C89), vec![(Feature::LongLong, 3), (Feature::LongLong, 3), (Feature::MixedDeclaration, 5)]);
+    assert_eq!(rejected(SRC, Standard::C99), vec![(Feature::ImplicitInt, 1)]);
+    assert_eq!(rejected(SRC, Standard::C11), vec![(Feature::ImplicitInt, 1)]);
+    assert_eq!(Feature::MixedDeclaration.diagnostic(), "declarations after statements require C99");
+    assert_eq!(Standard::from_name("ansi"), Some(Standard::C89));
+}
+
+#[test]
+fn stdbool_needs_c99() {
+    let src = "#include <stdbool.h>\nint main() {\n  bool b = 5;\n  _Bool f = false;\n  return b + f + true;\n}\n";
+    assert_eq!(rejected(src, Standard::C89), vec![(Feature::Bool, 3), (Feature::Bool, 4)]);
+    assert!(rejected(src, Standard::C99).is_empty());
+    assert_eq!(Feature::Bool.diagnostic(), "`_Bool` requires C99");
+}
+
+#[test]
+fn the_preprocessor_follows_the_standard() {
+    let mut pp = Preprocessor::new();
+    pp.set_standard(Standard::C89);
+    let errors = pp.preprocess_str("int x; // note\n#ifdef __STDC_VERSION__\n#error versioned\n#endif\n", "test.c").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "test.c:1:8: `//` comments require C99 (--std=c89)");
+
+    pp.set_standard(Standard::C99);
+    let out = pp.preprocess_str("long v = __STDC_VERSION__; // ok\n", "test.c").unwrap();
+    assert_eq!(out.text.trim(), "long v = 199901L;");
+}
+
+#[test]
+fn undeclared_functions_are_implicit_only_in_c89() {
+    let prog = Parser::new(mini_c::lexer::Lexer::new("int main() { g(1); g(2); return 0; }").collect()).parse_program();
+    let c89 = AnalysisOptions { standard: Standard::C89, ..Default::default() };
+    assert!(semantic::analyze_with(&prog, &c89).unwrap().warnings.is_empty());
+    let warnings = semantic::analyze(&prog).unwrap().warnings;
+    let found: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(found, vec!["Implicit declaration of function 'g' in function 'main' is not allowed since C99."]);
+}
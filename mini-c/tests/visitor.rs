@@ -0,0 +1,103 @@
+use mini_c::ast::*;
+use mini_c::lexer::Lexer;
+use mini_c::parser::Parser;
+use mini_c::printer::print_program;
+
+fn parse(src: &str) -> Program {
+    Parser::new(Lexer::new(src).collect()).parse_program()
+}
+
+const SRC: &str = "int main() { int a = 1 + 2 * 3; a = f(a, -4); return a / (2 - 1); }";
+
+// identifiers in visiting order
+struct Names(Vec<String>);
+
+impl Visitor for Names {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Ident(name) | ExprKind::Call { name, .. } => self.0.push(name.clone()),
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[test]
+fn visitor_reaches_every_expression_in_order() {
+    let mut names = Names(Vec::new());
+    names.visit_program(&parse(SRC));
+    assert_eq!(names.0, vec!["a", "f", "a", "a"]);
+}
+
+// renames a variable in place
+struct Rename;
+
+impl VisitorMut for Rename {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if let StmtKind::VarDecl { name, .. } = &mut stmt.kind {
+            name.push_str("_1");
+        }
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let ExprKind::Ident(name) = &mut expr.kind {
+            name.push_str("_1");
+        }
+        walk_expr_mut(self, expr);
+    }
+}
+
+#[test]
+fn visitor_mut_edits_in_place() {
+    let mut program = parse(SRC);
+    Rename.visit_program_mut(&mut program);
+    assert_eq!(
+        print_program(&program),
+        "int main() {\n    int a_1 = 1 + 2 * 3;\n    a_1 = f(a_1, -4);\n    return a_1 / (2 - 1);\n}\n"
+    );
+}
+
+// folds integer arithmetic on literals, bottom up
+struct ConstFold;
+
+impl Folder for ConstFold {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let folded = fold_expr(self, expr);
+        match folded.kind {
+            ExprKind::Binary { op, left, right } => match (left.kind, right.kind) {
+                (ExprKind::Number(a, Type::Int), ExprKind::Number(b, Type::Int)) if op != BinaryOp::Div || b != 0 => {
+                    let value = match op {
+                        BinaryOp::Add => a + b,
+                        BinaryOp::Sub => a - b,
+                        BinaryOp::Mul => a * b,
+                        BinaryOp::Div => a / b,
+                        cmp => (match cmp {
+                            BinaryOp::Lt => a < b,
+                            BinaryOp::Gt => a > b,
+                            BinaryOp::Le => a <= b,
+                            BinaryOp::Ge => a >= b,
+                            BinaryOp::Eq => a == b,
+                            _ => a != b,
+                        }) as i64,
+                    };
+                    Expr { id: folded.id, kind: ExprKind::Number(value, Type::Int) }
+                }
+                (l, r) => Expr {
+                    id: folded.id,
+                    kind: ExprKind::Binary { op, left: Box::new(Expr { id: left.id, kind: l }), right: Box::new(Expr { id: right.id, kind: r }) },
+                },
+            },
+            kind => Expr { id: folded.id, kind },
+        }
+    }
+}
+
+#[test]
+fn folder_replaces_nodes() {
+    let program = ConstFold.fold_program(parse(SRC));
+    assert_eq!(
+        print_program(&program),
+        "int main() {\n    int a = 7;\n    a = f(a, -4);\n    return a / 1;\n}\n"
+    );
+}
@@ -0,0 +1,155 @@
+use mini_c::ast::Program;
+use mini_c::lexer::Lexer;
+use mini_c::parser::Parser;
+use mini_c::preprocessor::Preprocessor;
+use mini_c::semantic::{self, SemanticWarning};
+use mini_c::warnings::{allowed_lines, WarningConfig};
+
+fn parse(src: &str) -> Program {
+    Parser::new(Lexer::new(src).collect()).parse_program()
+}
+
+fn warning_names(src: &str) -> Vec<&'static str> {
+    let tables = semantic::analyze(&parse(src)).unwrap();
+    let mut names: Vec<&str> = tables.warnings.iter().map(|w| w.name()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn each_warning_is_detected() {
+    assert_eq!(warning_names("int f(int a, int b) { return a; } int main() { return f(1, 2); }"), vec!["unused-parameter"]);
+    assert_eq!(warning_names("int main() { int x = 1; return 0; }"), vec!["unused-variable"]);
+    assert_eq!(warning_names("int f() { return 1; } int main() { return 0; }"), vec!["unused-function"]);
+    assert_eq!(warning_names("int f() { return 1; } int main() { int f = 2; return f; }"), vec!["shadow", "unused-function"]);
+    assert_eq!(warning_names("int main() { long l = 300l; char c = l; char d = 65l; return c + d; }"), vec!["conversion"]);
+    assert_eq!(warning_names("int main() { double d = 0.1; float f = d; float g = 0.5; return f < g; }"), vec!["conversion"]);
+    assert_eq!(warning_names("int main() { return 0; return 1; }"), vec!["unreachable-code"]);
+}
+
+#[test]
+fn shadowing_names_what_it_hides() {
+    let prog = parse("int n() { return 1; }\nint f(int p) {\n    int x = p;\n    {\n        int p = 2;\n        int x = p;\n        int n = x;\n        return n;\n    }\n}\nint main() { return f(n()); }");
+    let tables = semantic::analyze(&prog).unwrap();
+    let shadows: Vec<String> = tables.warnings.iter().filter(|w| w.name() == "shadow").map(|w| w.to_string()).collect();
+    assert_eq!(
+        shadows,
+        vec![
+            "Declaration of 'p' in function 'f' shadows a parameter.",
+            "Declaration of 'x' in function 'f' shadows a local variable declared by `int x = p;`.",
+            "Declaration of 'n' in function 'f' shadows a function.",
+        ]
+    );
+
+    // each points at the declaration it hides
+    let pp = Preprocessor::new().preprocess_str("int f(int p) {\n    {\n        int p = 2;\n        return p;\n    }\n}\n", "s.c").unwrap();
+    let mut parser = Parser::with_positions(pp.tokens_with_pos());
+    let prog = parser.parse_program();
+    let tables = semantic::analyze(&prog).unwrap();
+    let shadow = tables.warnings.iter().find(|w| w.name() == "shadow").unwrap();
+    assert_eq!(parser.positions()[&shadow.node()].line, 3);
+    assert_eq!(parser.positions()[&shadow.previous().unwrap()].line, 1);
+}
+
+#[test]
+fn comparisons_decided_by_types_are_reported() {
+    let tables = semantic::analyze(&parse(
+        "int main() { unsigned int u = 1u; char c = 'a'; int x = 2; return (u >= 0) + (0 > u) + (c < 300) + (c == 1000) + (x <= x) + (x < 3) + (1 < 2); }",
+    ))
+    .unwrap();
+    let found: Vec<String> = tables.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        found,
+        vec![
+            "Comparison `u >= 0` in function 'main' is always true.",
+            "Comparison `0 > u` in function 'main' is always false.",
+            "Comparison `c < 300` in function 'main' is always true.",
+            "Comparison `c == 1000` in function 'main' is always false.",
+            "Comparison `x <= x` in function 'main' is always true.",
+        ]
+    );
+
+    // the constant is compared after the usual arithmetic conversions
+    let tables = semantic::analyze(&parse(
+        "int main() { unsigned int u = 5u; int x = 1; return (u > -1) + (u <= -1) + (x < 0u) + (x >= 4294967296l); }",
+    ))
+    .unwrap();
+    let found: Vec<String> = tables.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        found,
+        vec![
+            "Comparison `u > -1` in function 'main' is always false.",
+            "Comparison `u <= -1` in function 'main' is always true.",
+            "Comparison `x >= 4294967296l` in function 'main' is always false.",
+        ]
+    );
+}
+
+#[test]
+fn format_strings_are_checked_against_their_arguments() {
+    let tables = semantic::analyze(&parse(
+        "int main() { char c = 'a'; float f = 1.5f; long l = 2l; printf(\"%c %d %5.2f %ld %*d%% %s\\n\", c, c, f, l, 3, 4, \"ok\"); printf(\"%d %f\", 1.5, 2); printf(\"%d %d\", 1); printf(\"%s\", 1); sprintf(\"buf\", \"%ld\", 1); scanf(\"%d\", 1); printf(\"%y\"); return 0; }",
+    ))
+    .unwrap();
+    let found: Vec<String> = tables.warnings.iter().filter(|w| w.name() == "format").map(|w| w.to_string()).collect();
+    assert_eq!(
+        found,
+        vec![
+            "Conversion `%d` in call to 'printf' in function 'main' expects int, but argument 2 has type double.",
+            "Conversion `%f` in call to 'printf' in function 'main' expects double, but argument 3 has type int.",
+            "Format of call to 'printf' in function 'main' expects 2 arguments, found 1.",
+            "Conversion `%s` in call to 'printf' in function 'main' expects char *, but argument 2 has type int.",
+            "Conversion `%ld` in call to 'sprintf' in function 'main' expects long, but argument 3 has type int.",
+            "Conversion `%d` in call to 'scanf' in function 'main' expects int *, but argument 2 has type int.",
+            "Invalid conversion `%y` in the format of call to 'printf' in function 'main'.",
+        ]
+    );
+    let implicit = tables.warnings.iter().filter(|w| w.name() == "implicit-function-declaration").count();
+    assert_eq!(implicit, 3, "printf, sprintf and scanf are each reported once");
+
+    let errors = semantic::analyze(&parse("int main() { printf(); return 0; }")).unwrap_err();
+    assert_eq!(errors[0].to_string(), "Too few arguments for call to 'printf' in function 'main': expected at least 1, found 0.");
+}
+
+#[test]
+fn flags_enable_disable_and_promote_warnings() {
+    let mut config = WarningConfig::new();
+    assert!(config.is_enabled("unreachable-code"));
+    assert!(!config.is_enabled("unused-variable"));
+
+    config.apply_flag("-Wall").unwrap();
+    assert!(config.is_enabled("unused-variable") && !config.is_enabled("shadow"));
+    config.apply_flag("-Wextra").unwrap();
+    config.apply_flag("-Wno-shadow").unwrap();
+    assert!(config.is_enabled("unused-parameter") && !config.is_enabled("shadow"));
+
+    config.apply_flag("-Werror=conversion").unwrap();
+    assert!(config.is_error("conversion") && !config.is_error("unused-variable"));
+    config.apply_flag("-Wno-unused-variable").unwrap();
+    config.apply_flag("-Werror").unwrap();
+    assert!(config.is_error("unused-function") && !config.is_error("unused-variable"));
+
+    assert!(config.apply_flag("-Wbogus").is_err());
+    assert!(config.apply_flag("-Werror=bogus").is_err());
+}
+
+#[test]
+fn allow_comments_cover_their_line_or_the_next() {
+    let src = "int main() {\n    int a = 1; // mini-c: allow(unused-variable)\n    // mini-c: allow(shadow, conversion)\n    char c = 300;\n    // just a comment\n    return 0;\n}\n";
+    let allowed = allowed_lines(src);
+    assert_eq!(allowed.get(&2), Some(&vec!["unused-variable".to_string()]));
+    assert_eq!(allowed.get(&4), Some(&vec!["shadow".to_string(), "conversion".to_string()]));
+    assert_eq!(allowed.len(), 2);
+}
+
+#[test]
+fn warnings_point_at_source_lines() {
+    let pp = Preprocessor::new().preprocess_str("#define ZERO 0\nint main() {\n    int unused = ZERO;\n    return 0;\n}\n", "w.c").unwrap();
+    let mut parser = Parser::with_positions(pp.tokens_with_pos());
+    let prog = parser.parse_program();
+    let tables = semantic::analyze(&prog).unwrap();
+    let [w @ SemanticWarning::UnusedVariable { .. }] = &tables.warnings[..] else { panic!("{:?}", tables.warnings) };
+    let pos = parser.positions()[&w.node()];
+    assert_eq!(pp.location(pos.line).map(|l| (l.file.as_str(), l.line)), Some(("w.c", 3)));
+    assert_eq!(pos.col, 5);
+}